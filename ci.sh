@@ -37,6 +37,22 @@ ruby)
 rust)
     cd rust
     cargo test
+    cargo test --features std
+    # The same tests with the software AES fallback on CPUs with AES-NI
+    RUSTFLAGS="--cfg miscreant_force_soft_aes" cargo test --features std
+    # Statistical check that the software AES timing doesn't depend on data
+    cargo test --release -- --ignored timing
+    # Keep the benchmarks compiling without spending CI time running them
+    cargo bench --features std --no-run
+    # Without `std` the crate only needs `core`, so it builds for bare-metal
+    # targets like Cortex-M
+    rustup target add thumbv7em-none-eabihf
+    cargo build --no-default-features --target thumbv7em-none-eabihf
+    # C API, exercised by a C program linked against the static library
+    make -C ffi test
+    # WebAssembly bindings, tested under Node.js
+    cargo install wasm-pack
+    wasm-pack test --node -- --features wasm
     ;;
 *)
     echo "*** ERROR: Unknown test suite: '$SUITE'"
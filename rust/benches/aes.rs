@@ -0,0 +1,55 @@
+#![feature(test)]
+
+extern crate miscreant;
+extern crate test;
+
+use miscreant::internals::{Aes128, Aes256, Block, Block8, BlockCipher, BLOCK8_SIZE, BLOCK_SIZE};
+use test::Bencher;
+
+/// Benchmark single-block encryption with the given cipher
+fn bench_encrypt<C: BlockCipher>(b: &mut Bencher, cipher: &C) {
+    let mut block = Block::new();
+
+    b.iter(|| cipher.encrypt(test::black_box(&mut block)));
+    b.bytes = BLOCK_SIZE as u64;
+}
+
+/// Benchmark eight-block parallel encryption with the given cipher
+fn bench_encrypt8<C: BlockCipher>(b: &mut Bencher, cipher: &C) {
+    let mut blocks = Block8::new();
+
+    b.iter(|| cipher.encrypt8(test::black_box(&mut blocks)));
+    b.bytes = BLOCK8_SIZE as u64;
+}
+
+/// Uses the AES instructions (AES-NI or ARMv8) when the CPU supports them
+#[bench]
+fn bench_aes128_encrypt(b: &mut Bencher) {
+    bench_encrypt(b, &Aes128::new(&[0x42u8; 16]));
+}
+
+/// Baseline: portable software implementation
+#[bench]
+fn bench_aes128_encrypt_software(b: &mut Bencher) {
+    bench_encrypt(b, &Aes128::new_software(&[0x42u8; 16]));
+}
+
+#[bench]
+fn bench_aes128_encrypt8(b: &mut Bencher) {
+    bench_encrypt8(b, &Aes128::new(&[0x42u8; 16]));
+}
+
+#[bench]
+fn bench_aes128_encrypt8_software(b: &mut Bencher) {
+    bench_encrypt8(b, &Aes128::new_software(&[0x42u8; 16]));
+}
+
+#[bench]
+fn bench_aes256_encrypt8(b: &mut Bencher) {
+    bench_encrypt8(b, &Aes256::new(&[0x42u8; 32]));
+}
+
+#[bench]
+fn bench_aes256_encrypt8_software(b: &mut Bencher) {
+    bench_encrypt8(b, &Aes256::new_software(&[0x42u8; 32]));
+}
@@ -0,0 +1,58 @@
+#![feature(test)]
+
+extern crate miscreant;
+extern crate test;
+
+use miscreant::internals::{xor, Block, BLOCK_SIZE};
+use test::Bencher;
+
+#[bench]
+fn bench_xor_in_place_aligned(b: &mut Bencher) {
+    let mut block = Block::new();
+    let other = Block::new();
+
+    b.iter(|| block.xor_in_place(test::black_box(&other)));
+    b.bytes = BLOCK_SIZE as u64;
+}
+
+#[bench]
+fn bench_xor_in_place_unaligned(b: &mut Bencher) {
+    let mut block = Block::new();
+    let buf = [0u8; BLOCK_SIZE + 1];
+    let unaligned = if buf.as_ptr() as usize % BLOCK_SIZE == 0 {
+        &buf[1..]
+    } else {
+        &buf[..BLOCK_SIZE]
+    };
+
+    b.iter(|| block.xor_in_place(test::black_box(unaligned)));
+    b.bytes = BLOCK_SIZE as u64;
+}
+
+/// Bulk XOR of unaligned 64 KiB buffers, as done for CTR keystream and CMAC
+#[bench]
+fn bench_xor_in_place_64kib(b: &mut Bencher) {
+    let mut buf = vec![0u8; 65536 + 1];
+    let other = vec![0u8; 65536];
+
+    b.iter(|| xor::in_place(&mut buf[1..], test::black_box(&other)));
+    b.bytes = other.len() as u64;
+}
+
+/// GF(2^128) doubling, as used by CMAC subkey generation and S2V
+#[bench]
+fn bench_dbl(b: &mut Bencher) {
+    let mut block = Block::from([0x87u8; BLOCK_SIZE]);
+
+    b.iter(|| test::black_box(&mut block).dbl());
+    b.bytes = BLOCK_SIZE as u64;
+}
+
+/// Multiplication by the inverse of x, as used by PMAC
+#[bench]
+fn bench_dbl_inv(b: &mut Bencher) {
+    let mut block = Block::from([0x87u8; BLOCK_SIZE]);
+
+    b.iter(|| test::black_box(&mut block).dbl_inv());
+    b.bytes = BLOCK_SIZE as u64;
+}
@@ -0,0 +1,63 @@
+#![feature(test)]
+
+extern crate miscreant;
+extern crate test;
+
+use miscreant::internals::{Aes128, Cmac, CmacKey};
+use test::Bencher;
+
+/// Number of messages to authenticate in each iteration
+const MESSAGE_COUNT: usize = 1024;
+
+/// Size of each message (e.g. a small record)
+const MESSAGE_SIZE: usize = 64;
+
+#[bench]
+fn bench_aes128_cmac_batch_64b(b: &mut Bencher) {
+    let key = CmacKey::new(Aes128::new(&[0x42u8; 16]));
+    let buffer = vec![0u8; MESSAGE_COUNT * MESSAGE_SIZE];
+    let messages: Vec<&[u8]> = buffer.chunks(MESSAGE_SIZE).collect();
+
+    b.iter(|| Cmac::compute_batch(&key, test::black_box(&messages)));
+    b.bytes = (MESSAGE_COUNT * MESSAGE_SIZE) as u64;
+}
+
+/// Baseline: the same messages authenticated one at a time
+#[bench]
+fn bench_aes128_cmac_sequential_64b(b: &mut Bencher) {
+    let key = CmacKey::new(Aes128::new(&[0x42u8; 16]));
+    let buffer = vec![0u8; MESSAGE_COUNT * MESSAGE_SIZE];
+    let messages: Vec<&[u8]> = buffer.chunks(MESSAGE_SIZE).collect();
+
+    b.iter(|| {
+        test::black_box(&messages)
+            .iter()
+            .map(|msg| key.mac().chain_update(msg).unwrap().finish().unwrap())
+            .collect::<Vec<_>>()
+    });
+    b.bytes = (MESSAGE_COUNT * MESSAGE_SIZE) as u64;
+}
+
+/// Authenticate a single message of the given size
+fn bench_cmac(b: &mut Bencher, size: usize) {
+    let key = CmacKey::new(Aes128::new(&[0x42u8; 16]));
+    let message = vec![0u8; size];
+
+    b.iter(|| key.mac().chain_update(test::black_box(&message)).unwrap().finish().unwrap());
+    b.bytes = size as u64;
+}
+
+#[bench]
+fn bench_aes128_cmac_64b(b: &mut Bencher) {
+    bench_cmac(b, 64);
+}
+
+#[bench]
+fn bench_aes128_cmac_1kib(b: &mut Bencher) {
+    bench_cmac(b, 1024);
+}
+
+#[bench]
+fn bench_aes128_cmac_1mib(b: &mut Bencher) {
+    bench_cmac(b, 1024 * 1024);
+}
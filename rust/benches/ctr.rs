@@ -0,0 +1,79 @@
+#![feature(test)]
+
+extern crate miscreant;
+extern crate test;
+
+use miscreant::Ctr;
+use miscreant::internals::{Aes128, Block, BLOCK8_SIZE};
+use test::Bencher;
+
+/// Size of the buffer to encrypt in each iteration (1 MiB)
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+#[bench]
+fn bench_aes128_ctr_1mib(b: &mut Bencher) {
+    let mut ctr = Ctr::new(Aes128::new(&[0x42u8; 16]), &Block::new());
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+
+    b.iter(|| ctr.apply_keystream(test::black_box(&mut buffer)).unwrap());
+    b.bytes = BUFFER_SIZE as u64;
+}
+
+/// Baseline: calls which are too short to use the batched keystream path
+#[bench]
+fn bench_aes128_ctr_1mib_unbatched(b: &mut Bencher) {
+    let mut ctr = Ctr::new(Aes128::new(&[0x42u8; 16]), &Block::new());
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+
+    b.iter(|| {
+        for chunk in buffer.chunks_mut(BLOCK8_SIZE - 1) {
+            ctr.apply_keystream(test::black_box(chunk)).unwrap();
+        }
+    });
+    b.bytes = BUFFER_SIZE as u64;
+}
+
+/// Number of messages encrypted in each iteration of the small message benches
+const MESSAGES: usize = 1024;
+
+/// Size of each small message
+const MESSAGE_SIZE: usize = 64;
+
+/// Many small messages, each restarting one `Ctr` from a new IV
+#[bench]
+fn bench_aes128_ctr_small_messages_reset_iv(b: &mut Bencher) {
+    let mut ctr = Ctr::new(Aes128::new(&[0x42u8; 16]), &Block::new());
+    let mut message = [0u8; MESSAGE_SIZE];
+
+    b.iter(|| {
+        for i in 0..MESSAGES {
+            ctr.reset_iv(&iv(i));
+            ctr.apply_keystream(test::black_box(&mut message)).unwrap();
+        }
+    });
+    b.bytes = (MESSAGES * MESSAGE_SIZE) as u64;
+}
+
+/// Baseline: many small messages, each with a newly created `Ctr`
+#[bench]
+fn bench_aes128_ctr_small_messages_new(b: &mut Bencher) {
+    let cipher = Aes128::new(&[0x42u8; 16]);
+    let mut message = [0u8; MESSAGE_SIZE];
+
+    b.iter(|| {
+        for i in 0..MESSAGES {
+            Ctr::new(cipher.clone(), &iv(i))
+                .apply_keystream(test::black_box(&mut message))
+                .unwrap();
+        }
+    });
+    b.bytes = (MESSAGES * MESSAGE_SIZE) as u64;
+}
+
+/// A distinct IV for each small message
+fn iv(i: usize) -> Block {
+    let mut iv = [0u8; 16];
+    iv[0] = (i >> 8) as u8;
+    iv[1] = i as u8;
+    Block::from(iv)
+}
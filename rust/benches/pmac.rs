@@ -0,0 +1,76 @@
+#![feature(test)]
+
+extern crate miscreant;
+extern crate test;
+
+use miscreant::internals::{Aes128, Cmac, Pmac, BLOCK_SIZE};
+use test::Bencher;
+
+fn bench_cmac(b: &mut Bencher, len: usize) {
+    let mut cmac = Cmac::new(Aes128::new(&[0x42u8; 16]));
+    let msg = vec![0u8; len];
+
+    b.iter(|| {
+        cmac.reset();
+        cmac.update(test::black_box(&msg)).unwrap();
+        cmac.finish().unwrap()
+    });
+    b.bytes = len as u64;
+}
+
+fn bench_pmac(b: &mut Bencher, len: usize) {
+    let mut pmac = Pmac::new(Aes128::new(&[0x42u8; 16]));
+    let msg = vec![0u8; len];
+
+    b.iter(|| {
+        pmac.reset();
+        pmac.update(test::black_box(&msg)).unwrap();
+        pmac.finish().unwrap()
+    });
+    b.bytes = len as u64;
+}
+
+/// Baseline: PMAC fed one block at a time, which never batches blocks
+fn bench_pmac_sequential(b: &mut Bencher, len: usize) {
+    let mut pmac = Pmac::new(Aes128::new(&[0x42u8; 16]));
+    let msg = vec![0u8; len];
+
+    b.iter(|| {
+        pmac.reset();
+        for block in test::black_box(&msg).chunks(BLOCK_SIZE) {
+            pmac.update(block).unwrap();
+        }
+        pmac.finish().unwrap()
+    });
+    b.bytes = len as u64;
+}
+
+#[bench]
+fn bench_aes128_cmac_4kib(b: &mut Bencher) {
+    bench_cmac(b, 4096)
+}
+
+#[bench]
+fn bench_aes128_cmac_1mib(b: &mut Bencher) {
+    bench_cmac(b, 1024 * 1024)
+}
+
+#[bench]
+fn bench_aes128_pmac_4kib(b: &mut Bencher) {
+    bench_pmac(b, 4096)
+}
+
+#[bench]
+fn bench_aes128_pmac_1mib(b: &mut Bencher) {
+    bench_pmac(b, 1024 * 1024)
+}
+
+#[bench]
+fn bench_aes128_pmac_sequential_4kib(b: &mut Bencher) {
+    bench_pmac_sequential(b, 4096)
+}
+
+#[bench]
+fn bench_aes128_pmac_sequential_1mib(b: &mut Bencher) {
+    bench_pmac_sequential(b, 1024 * 1024)
+}
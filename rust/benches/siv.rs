@@ -0,0 +1,97 @@
+#![feature(test)]
+
+extern crate miscreant;
+extern crate test;
+
+use miscreant::Aes128Siv;
+use miscreant::internals::BLOCK_SIZE;
+use test::Bencher;
+
+/// Number of messages sealed in each iteration
+const MESSAGES: usize = 1024;
+
+/// Size of each message's plaintext
+const MESSAGE_SIZE: usize = 64;
+
+/// Many small messages sealed with one instance, which reuses its CTR context
+#[bench]
+fn bench_aes128_siv_seal_small_messages(b: &mut Bencher) {
+    let mut siv = Aes128Siv::new(&[0x42u8; 32]);
+    let mut buffer = [0u8; MESSAGE_SIZE + BLOCK_SIZE];
+
+    b.iter(|| {
+        for i in 0..MESSAGES {
+            siv.seal_in_place(&[&[i as u8]], test::black_box(&mut buffer));
+        }
+    });
+    b.bytes = (MESSAGES * MESSAGE_SIZE) as u64;
+}
+
+/// Many small messages opened with one instance
+#[bench]
+fn bench_aes128_siv_open_small_messages(b: &mut Bencher) {
+    let mut siv = Aes128Siv::new(&[0x42u8; 32]);
+    let mut sealed = [0u8; MESSAGE_SIZE + BLOCK_SIZE];
+    siv.seal_in_place(&[b"nonce"], &mut sealed);
+
+    b.iter(|| {
+        for _ in 0..MESSAGES {
+            let mut buffer = sealed;
+            siv.open_in_place(&[b"nonce"], test::black_box(&mut buffer)).unwrap();
+        }
+    });
+    b.bytes = (MESSAGES * MESSAGE_SIZE) as u64;
+}
+
+/// Seal a message of the given size, reusing the buffer every iteration
+fn bench_seal(b: &mut Bencher, size: usize) {
+    let mut siv = Aes128Siv::new(&[0x42u8; 32]);
+    let mut buffer = vec![0u8; size + BLOCK_SIZE];
+
+    b.iter(|| siv.seal_in_place(&[b"nonce"], test::black_box(&mut buffer)));
+    b.bytes = size as u64;
+}
+
+/// Open a sealed message of the given size, copying it back in each iteration
+fn bench_open(b: &mut Bencher, size: usize) {
+    let mut siv = Aes128Siv::new(&[0x42u8; 32]);
+    let mut sealed = vec![0u8; size + BLOCK_SIZE];
+    siv.seal_in_place(&[b"nonce"], &mut sealed);
+    let mut buffer = sealed.clone();
+
+    b.iter(|| {
+        buffer.copy_from_slice(&sealed);
+        siv.open_in_place(&[b"nonce"], test::black_box(&mut buffer)).unwrap();
+    });
+    b.bytes = size as u64;
+}
+
+#[bench]
+fn bench_aes128_siv_seal_64b(b: &mut Bencher) {
+    bench_seal(b, 64);
+}
+
+#[bench]
+fn bench_aes128_siv_seal_1kib(b: &mut Bencher) {
+    bench_seal(b, 1024);
+}
+
+#[bench]
+fn bench_aes128_siv_seal_1mib(b: &mut Bencher) {
+    bench_seal(b, 1024 * 1024);
+}
+
+#[bench]
+fn bench_aes128_siv_open_64b(b: &mut Bencher) {
+    bench_open(b, 64);
+}
+
+#[bench]
+fn bench_aes128_siv_open_1kib(b: &mut Bencher) {
+    bench_open(b, 1024);
+}
+
+#[bench]
+fn bench_aes128_siv_open_1mib(b: &mut Bencher) {
+    bench_open(b, 1024 * 1024);
+}
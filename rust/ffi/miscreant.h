@@ -0,0 +1,86 @@
+#ifndef MISCREANT_H
+#define MISCREANT_H
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit by hand. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * The operation succeeded
+ */
+#define MISCREANT_OK 0
+
+/**
+ * The message was truncated or failed authentication
+ */
+#define MISCREANT_ERR_AUTH -1
+
+/**
+ * A key or output buffer was the wrong length
+ */
+#define MISCREANT_ERR_LENGTH -2
+
+/**
+ * A required pointer was null
+ */
+#define MISCREANT_ERR_NULL -3
+
+/**
+ * An internal error (a Rust panic) occurred
+ */
+#define MISCREANT_ERR_PANIC -4
+
+/**
+ * Opaque AES-SIV instance with a 256-bit key
+ */
+typedef struct MiscreantAes128Siv MiscreantAes128Siv;
+
+/**
+ * Create an AES-SIV instance from a 32-byte key, returning null if the key
+ * is null or the wrong length. Free it with `miscreant_aes128_siv_free`.
+ */
+MiscreantAes128Siv *miscreant_aes128_siv_new(const uint8_t *key, size_t key_len);
+
+/**
+ * Encrypt `plaintext` into `out` as the SIV tag followed by the ciphertext,
+ * with `ad` and then `nonce` as the associated data
+ *
+ * `*out_len` must hold the capacity of `out`, at least `plaintext_len + 16`
+ * bytes, and is set to the number of bytes written.
+ */
+int miscreant_aes128_siv_seal(MiscreantAes128Siv *siv,
+                              const uint8_t *nonce,
+                              size_t nonce_len,
+                              const uint8_t *ad,
+                              size_t ad_len,
+                              const uint8_t *plaintext,
+                              size_t plaintext_len,
+                              uint8_t *out,
+                              size_t *out_len);
+
+/**
+ * Decrypt and authenticate `ciphertext` (as produced by
+ * `miscreant_aes128_siv_seal`) into `out`
+ *
+ * `*out_len` must hold the capacity of `out`, at least `ciphertext_len - 16`
+ * bytes, and is set to the length of the plaintext. Nothing is written to
+ * `out` unless the message is authentic.
+ */
+int miscreant_aes128_siv_open(MiscreantAes128Siv *siv,
+                              const uint8_t *nonce,
+                              size_t nonce_len,
+                              const uint8_t *ad,
+                              size_t ad_len,
+                              const uint8_t *ciphertext,
+                              size_t ciphertext_len,
+                              uint8_t *out,
+                              size_t *out_len);
+
+/**
+ * Free an instance created by `miscreant_aes128_siv_new`, zeroizing its
+ * keys. Passing null does nothing.
+ */
+void miscreant_aes128_siv_free(MiscreantAes128Siv *siv);
+
+#endif /* MISCREANT_H */
@@ -0,0 +1,55 @@
+/* Round-trips a message through the C API and checks forgeries are rejected */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "miscreant.h"
+
+#define CHECK(cond)                                                  \
+    do {                                                             \
+        if (!(cond)) {                                               \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,   \
+                    __LINE__, #cond);                                \
+            return 1;                                                \
+        }                                                            \
+    } while (0)
+
+int main(void) {
+    uint8_t key[32];
+    const uint8_t nonce[] = "nonce";
+    const uint8_t ad[] = "associated data";
+    const uint8_t plaintext[] = "Hello, world!";
+    uint8_t sealed[sizeof(plaintext) + 16];
+    uint8_t opened[sizeof(plaintext)];
+    size_t sealed_len = sizeof(sealed), opened_len = sizeof(opened);
+    MiscreantAes128Siv *siv;
+
+    memset(key, 0x42, sizeof(key));
+
+    CHECK(miscreant_aes128_siv_new(key, 16) == NULL);
+
+    siv = miscreant_aes128_siv_new(key, sizeof(key));
+    CHECK(siv != NULL);
+
+    CHECK(miscreant_aes128_siv_seal(siv, nonce, sizeof(nonce), ad, sizeof(ad),
+                                    plaintext, sizeof(plaintext), sealed,
+                                    &sealed_len) == MISCREANT_OK);
+    CHECK(sealed_len == sizeof(sealed));
+
+    CHECK(miscreant_aes128_siv_open(siv, nonce, sizeof(nonce), ad, sizeof(ad),
+                                    sealed, sealed_len, opened,
+                                    &opened_len) == MISCREANT_OK);
+    CHECK(opened_len == sizeof(plaintext));
+    CHECK(memcmp(opened, plaintext, sizeof(plaintext)) == 0);
+
+    sealed[sealed_len - 1] ^= 1;
+    opened_len = sizeof(opened);
+    CHECK(miscreant_aes128_siv_open(siv, nonce, sizeof(nonce), ad, sizeof(ad),
+                                    sealed, sealed_len, opened,
+                                    &opened_len) == MISCREANT_ERR_AUTH);
+
+    miscreant_aes128_siv_free(siv);
+
+    printf("ok\n");
+    return 0;
+}
@@ -0,0 +1,276 @@
+//! `drbg.rs`: NIST SP 800-90A CTR_DRBG with AES-256, without a derivation
+//! function
+//!
+//! A deterministic random bit generator: its output is a function of the
+//! entropy input, personalization string, and any additional input, so it's
+//! only as unpredictable as the entropy it's seeded with. Without a
+//! derivation function the entropy input must be full-entropy and exactly
+//! `SEED_SIZE` bytes.
+//!
+//! Each output block is AES-256 of the incremented 128-bit counter `V`, and
+//! the key and `V` are replaced after every request (the "update" function),
+//! so earlier output can't be recovered from the current state.
+
+use byteorder::{BigEndian, ByteOrder};
+use error::Error;
+use internals::{xor, Aes256, Block, Ctr, BLOCK_SIZE};
+use zeroize::Zeroize;
+
+/// Size of the entropy input, and the maximum size of the personalization
+/// string and additional input (the AES-256 key plus one block)
+pub const SEED_SIZE: usize = 48;
+
+/// Maximum number of bytes returned by a single request (2^19 bits)
+pub const MAX_REQUEST_SIZE: usize = 1 << 16;
+
+/// Number of requests allowed before `reseed` must be called
+pub const RESEED_INTERVAL: u64 = 1 << 48;
+
+/// Size of an AES-256 key
+const KEY_SIZE: usize = 32;
+
+/// CTR_DRBG using AES-256 with no derivation function
+pub struct CtrDrbg {
+    cipher: Aes256,
+    v: Block,
+    reseed_counter: u64,
+}
+
+impl CtrDrbg {
+    /// Instantiate a DRBG from `SEED_SIZE` bytes of entropy and an optional
+    /// personalization string of at most `SEED_SIZE` bytes
+    ///
+    /// Returns `Error::SeedSize` if either is the wrong length.
+    pub fn new(entropy_input: &[u8], personalization: &[u8]) -> Result<Self, Error> {
+        let seed_material = seed_material(entropy_input, personalization)?;
+
+        let mut drbg = Self {
+            cipher: Aes256::new(&[0u8; KEY_SIZE]),
+            v: Block::new(),
+            reseed_counter: 1,
+        };
+
+        drbg.update(&seed_material);
+        Ok(drbg)
+    }
+
+    /// Mix in `SEED_SIZE` bytes of fresh entropy and optional additional
+    /// input of at most `SEED_SIZE` bytes, resetting the reseed counter
+    ///
+    /// Returns `Error::SeedSize` if either is the wrong length.
+    pub fn reseed(&mut self, entropy_input: &[u8], additional_input: &[u8]) -> Result<(), Error> {
+        let seed_material = seed_material(entropy_input, additional_input)?;
+
+        self.update(&seed_material);
+        self.reseed_counter = 1;
+        Ok(())
+    }
+
+    /// Fill `out` with pseudorandom bytes, exactly like
+    /// `generate(out, &[])`
+    pub fn fill_bytes(&mut self, out: &mut [u8]) -> Result<(), Error> {
+        self.generate(out, &[])
+    }
+
+    /// Fill `out` with pseudorandom bytes, first mixing in optional
+    /// additional input of at most `SEED_SIZE` bytes. Any non-empty
+    /// additional input is mixed in, even if it's all zeroes.
+    ///
+    /// Returns `Error::ReseedRequired` (leaving `out` untouched) once
+    /// `RESEED_INTERVAL` requests have been made since the last (re)seed,
+    /// `Error::RequestSize` if `out` is longer than `MAX_REQUEST_SIZE`, or
+    /// `Error::SeedSize` if the additional input is too long.
+    pub fn generate(&mut self, out: &mut [u8], additional_input: &[u8]) -> Result<(), Error> {
+        if out.len() > MAX_REQUEST_SIZE {
+            return Err(Error::RequestSize);
+        }
+
+        if self.reseed_counter > RESEED_INTERVAL {
+            return Err(Error::ReseedRequired);
+        }
+
+        let provided = !additional_input.is_empty();
+        let additional_input = pad(additional_input)?;
+
+        if provided {
+            self.update(&additional_input);
+        }
+
+        self.keystream(out);
+        self.update(&additional_input);
+        self.reseed_counter += 1;
+
+        Ok(())
+    }
+
+    /// Replace the key and `V` with the next `SEED_SIZE` bytes of output
+    /// XORed with the provided data (the CTR_DRBG_Update function)
+    fn update(&mut self, provided_data: &[u8; SEED_SIZE]) {
+        let mut temp = [0u8; SEED_SIZE];
+        self.keystream(&mut temp);
+        xor::in_place(&mut temp, provided_data);
+
+        self.cipher = Aes256::new(array_ref!(temp, 0, KEY_SIZE));
+        self.v = Block::from(*array_ref!(temp, KEY_SIZE, BLOCK_SIZE));
+        temp.zeroize();
+    }
+
+    /// Write the encryptions of `V + 1`, `V + 2`, ... into `out`, leaving `V`
+    /// set to the last counter value used
+    fn keystream(&mut self, out: &mut [u8]) {
+        if out.is_empty() {
+            return;
+        }
+
+        let blocks = ((out.len() + BLOCK_SIZE - 1) / BLOCK_SIZE) as u128;
+        let start = BigEndian::read_u128(self.v.as_ref()).wrapping_add(1);
+
+        // `V` wraps around modulo 2^128, but a `Ctr` refuses to, so split
+        // the output at the point where the counter returns to zero
+        let split = if u128::max_value() - start < blocks - 1 {
+            (u128::max_value() - start + 1) as usize * BLOCK_SIZE
+        } else {
+            out.len()
+        };
+
+        let (before_wrap, after_wrap) = out.split_at_mut(split);
+        self.ctr_keystream(start, before_wrap);
+        self.ctr_keystream(0, after_wrap);
+
+        let v: &mut [u8] = self.v.as_mut();
+        BigEndian::write_u128(v, start.wrapping_add(blocks - 1));
+    }
+
+    /// Write the CTR keystream starting from the given counter into `out`
+    fn ctr_keystream(&self, counter: u128, out: &mut [u8]) {
+        let mut iv = Block::new();
+        {
+            let bytes: &mut [u8] = iv.as_mut();
+            BigEndian::write_u128(bytes, counter);
+        }
+
+        Ctr::new(self.cipher.clone(), &iv)
+            .keystream(out)
+            .expect("split before the counter wraps");
+    }
+}
+
+/// Combine the entropy input with a personalization string or additional
+/// input, as the seed material for instantiating or reseeding
+fn seed_material(entropy_input: &[u8], input: &[u8]) -> Result<[u8; SEED_SIZE], Error> {
+    if entropy_input.len() != SEED_SIZE {
+        return Err(Error::SeedSize);
+    }
+
+    let mut seed_material = pad(input)?;
+    xor::in_place(&mut seed_material, entropy_input);
+    Ok(seed_material)
+}
+
+/// Pad a personalization string or additional input with zeroes to
+/// `SEED_SIZE` bytes
+fn pad(input: &[u8]) -> Result<[u8; SEED_SIZE], Error> {
+    if input.len() > SEED_SIZE {
+        return Err(Error::SeedSize);
+    }
+
+    let mut padded = [0u8; SEED_SIZE];
+    padded[..input.len()].copy_from_slice(input);
+    Ok(padded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CtrDrbg, MAX_REQUEST_SIZE, RESEED_INTERVAL, SEED_SIZE};
+    use error::Error;
+    use internals::{Block, BlockCipher, BLOCK_SIZE};
+
+    #[test]
+    fn invalid_seed_sizes() {
+        let entropy = [0u8; SEED_SIZE + 1];
+
+        for &len in &[0, SEED_SIZE - 1, SEED_SIZE + 1] {
+            assert_eq!(CtrDrbg::new(&entropy[..len], b"").err(), Some(Error::SeedSize));
+        }
+
+        assert_eq!(CtrDrbg::new(&entropy[..SEED_SIZE], &entropy).err(), Some(Error::SeedSize));
+
+        let mut drbg = CtrDrbg::new(&entropy[..SEED_SIZE], &entropy[..SEED_SIZE]).unwrap();
+        assert_eq!(drbg.reseed(&entropy, b"").err(), Some(Error::SeedSize));
+        assert_eq!(drbg.reseed(&entropy[..SEED_SIZE], &entropy).err(), Some(Error::SeedSize));
+        assert_eq!(drbg.generate(&mut [0u8; 16], &entropy).err(), Some(Error::SeedSize));
+    }
+
+    #[test]
+    fn request_too_long() {
+        let mut drbg = CtrDrbg::new(&[0x42u8; SEED_SIZE], b"").unwrap();
+        let mut out = [0x24u8; MAX_REQUEST_SIZE + 1];
+
+        assert_eq!(drbg.fill_bytes(&mut out).err(), Some(Error::RequestSize));
+        assert!(out.iter().all(|&b| b == 0x24));
+        assert_eq!(drbg.reseed_counter, 1);
+
+        drbg.fill_bytes(&mut out[..MAX_REQUEST_SIZE]).unwrap();
+    }
+
+    #[test]
+    fn all_zero_additional_input_is_mixed_in() {
+        let mut empty = CtrDrbg::new(&[0x42u8; SEED_SIZE], b"").unwrap();
+        let mut zeroes = CtrDrbg::new(&[0x42u8; SEED_SIZE], b"").unwrap();
+        let mut expected = [0u8; 32];
+        let mut out = [0u8; 32];
+
+        empty.generate(&mut expected, &[]).unwrap();
+        zeroes.generate(&mut out, &[0u8; SEED_SIZE]).unwrap();
+
+        // Both update after generating, but only the explicit all-zero input
+        // also updates before it
+        assert_ne!(out, expected);
+    }
+
+    #[test]
+    fn reseed_counter_exhaustion() {
+        let mut drbg = CtrDrbg::new(&[0x42u8; SEED_SIZE], b"").unwrap();
+        let mut out = [0u8; 16];
+
+        // The last request allowed before reseeding
+        drbg.reseed_counter = RESEED_INTERVAL;
+        drbg.fill_bytes(&mut out).unwrap();
+
+        let previous = out;
+        assert_eq!(drbg.fill_bytes(&mut out).err(), Some(Error::ReseedRequired));
+        assert_eq!(drbg.generate(&mut out, b"input").err(), Some(Error::ReseedRequired));
+        assert_eq!(out, previous);
+
+        drbg.reseed(&[0x24u8; SEED_SIZE], b"").unwrap();
+        drbg.fill_bytes(&mut out).unwrap();
+        assert_ne!(out, previous);
+    }
+
+    #[test]
+    fn counter_wraps_modulo_2_128() {
+        let mut drbg = CtrDrbg::new(&[0x42u8; SEED_SIZE], b"").unwrap();
+        let mut v = [0xffu8; BLOCK_SIZE];
+        v[BLOCK_SIZE - 1] = 0xfe;
+        drbg.v = Block::from(v);
+
+        let mut out = [0u8; BLOCK_SIZE * 3 - 5];
+        drbg.keystream(&mut out);
+
+        let mut one = [0u8; BLOCK_SIZE];
+        one[BLOCK_SIZE - 1] = 1;
+
+        // Counters ff..ff, 00..00, and 00..01
+        let mut expected = [0u8; BLOCK_SIZE * 3];
+        let counters = [[0xffu8; BLOCK_SIZE], [0u8; BLOCK_SIZE], one];
+
+        for (chunk, counter) in expected.chunks_mut(BLOCK_SIZE).zip(counters.iter()) {
+            let mut block = Block::from(*counter);
+            drbg.cipher.encrypt(&mut block);
+            chunk.copy_from_slice(block.as_ref());
+        }
+
+        assert_eq!(&out[..], &expected[..out.len()]);
+        assert_eq!(drbg.v.as_ref(), &one[..]);
+    }
+}
@@ -0,0 +1,79 @@
+//! `error.rs`: Error types
+
+use core::fmt;
+
+/// Errors which can occur when using this crate
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// Slice is the wrong size to be converted into a block (or tag)
+    BlockSize {
+        /// Length of the offending slice
+        len: usize,
+    },
+
+    /// Output buffer is too small to hold the result
+    BufferSize,
+
+    /// Key is the wrong size for the algorithm
+    KeySize,
+
+    /// Hex string is the wrong length
+    HexLength,
+
+    /// Hex string contains characters other than `0-9`, `a-f`, or `A-F`
+    HexCharacter,
+
+    /// Base64 string is malformed
+    Base64,
+
+    /// A MAC was used after it was finished without being reset, or a STREAM
+    /// was used after its last chunk
+    AlreadyFinished,
+
+    /// A MAC tag failed to verify (see `Cmac::verify`)
+    TagMismatch,
+
+    /// A message failed to authenticate when opening it, e.g. because it was
+    /// tampered with, truncated, or sealed with a different key, nonce, or
+    /// associated data
+    Authentication,
+
+    /// A nonce was reused within the window tracked by `NonceTracker`
+    NonceReuse,
+
+    /// A CTR counter would have wrapped around, reusing keystream
+    CounterOverflow,
+
+    /// DRBG entropy input, personalization string, or additional input is
+    /// the wrong size
+    SeedSize,
+
+    /// A DRBG has made the maximum number of requests since it was seeded
+    ReseedRequired,
+
+    /// More bytes were requested from a DRBG than one request may return
+    RequestSize,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::BlockSize { len } => {
+                write!(f, "slice is the wrong size for a block (size: {})", len)
+            }
+            Error::BufferSize => write!(f, "output buffer is too small"),
+            Error::KeySize => write!(f, "key is the wrong size"),
+            Error::HexLength => write!(f, "hex string is the wrong length"),
+            Error::HexCharacter => write!(f, "invalid character in hex string"),
+            Error::Base64 => write!(f, "malformed base64 string"),
+            Error::AlreadyFinished => write!(f, "already finished"),
+            Error::TagMismatch => write!(f, "MAC tag verification failed"),
+            Error::Authentication => write!(f, "message failed to authenticate"),
+            Error::NonceReuse => write!(f, "nonce reused"),
+            Error::CounterOverflow => write!(f, "CTR counter overflowed"),
+            Error::SeedSize => write!(f, "DRBG seed material is the wrong size"),
+            Error::ReseedRequired => write!(f, "DRBG must be reseeded"),
+            Error::RequestSize => write!(f, "DRBG request is too long"),
+        }
+    }
+}
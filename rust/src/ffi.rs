@@ -0,0 +1,317 @@
+//! `ffi.rs`: C API for AES-SIV, for embedding in other languages
+//!
+//! Buffers are passed as a pointer and a length. Functions return
+//! `MISCREANT_OK` on success or one of the negative `MISCREANT_ERR_*` codes,
+//! and never unwind into the caller: a panic is caught and reported as
+//! `MISCREANT_ERR_PANIC`. See `ffi/miscreant.h` for the C declarations.
+
+use internals::BLOCK_SIZE;
+use siv::Aes128Siv;
+use std::boxed::Box;
+use std::os::raw::c_int;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+use zeroize::Zeroize;
+
+/// The operation succeeded
+pub const MISCREANT_OK: c_int = 0;
+
+/// The message was truncated or failed authentication
+pub const MISCREANT_ERR_AUTH: c_int = -1;
+
+/// A key or output buffer was the wrong length
+pub const MISCREANT_ERR_LENGTH: c_int = -2;
+
+/// A required pointer was null
+pub const MISCREANT_ERR_NULL: c_int = -3;
+
+/// An internal error (a Rust panic) occurred
+pub const MISCREANT_ERR_PANIC: c_int = -4;
+
+/// Opaque AES-SIV instance with a 256-bit key
+pub struct MiscreantAes128Siv(Aes128Siv);
+
+/// Create an AES-SIV instance from a 32-byte key, returning null if the key
+/// is null or the wrong length. Free it with `miscreant_aes128_siv_free`.
+#[no_mangle]
+pub unsafe extern "C" fn miscreant_aes128_siv_new(
+    key: *const u8,
+    key_len: usize,
+) -> *mut MiscreantAes128Siv {
+    if key.is_null() || key_len != 32 {
+        return ptr::null_mut();
+    }
+
+    let key = slice::from_raw_parts(key, key_len);
+
+    panic::catch_unwind(|| {
+        Box::into_raw(Box::new(MiscreantAes128Siv(Aes128Siv::new(array_ref!(key, 0, 32)))))
+    }).unwrap_or(ptr::null_mut())
+}
+
+/// Encrypt `plaintext` into `out` as the SIV tag followed by the ciphertext,
+/// with `ad` and then `nonce` as the associated data
+///
+/// `*out_len` must hold the capacity of `out`, at least `plaintext_len + 16`
+/// bytes, and is set to the number of bytes written.
+#[no_mangle]
+pub unsafe extern "C" fn miscreant_aes128_siv_seal(
+    siv: *mut MiscreantAes128Siv,
+    nonce: *const u8,
+    nonce_len: usize,
+    ad: *const u8,
+    ad_len: usize,
+    plaintext: *const u8,
+    plaintext_len: usize,
+    out: *mut u8,
+    out_len: *mut usize,
+) -> c_int {
+    if siv.is_null() || out_len.is_null() {
+        return MISCREANT_ERR_NULL;
+    }
+
+    let (nonce, ad, plaintext, out) = match (
+        input(nonce, nonce_len),
+        input(ad, ad_len),
+        input(plaintext, plaintext_len),
+        output(out, *out_len),
+    ) {
+        (Some(n), Some(a), Some(p), Some(o)) => (n, a, p, o),
+        _ => return MISCREANT_ERR_NULL,
+    };
+
+    let siv = &mut (*siv).0;
+
+    guard(|| match siv.seal_to_slice(&[ad, nonce], plaintext, out) {
+        Ok(len) => {
+            *out_len = len;
+            MISCREANT_OK
+        }
+        Err(_) => MISCREANT_ERR_LENGTH,
+    })
+}
+
+/// Decrypt and authenticate `ciphertext` (as produced by
+/// `miscreant_aes128_siv_seal`) into `out`
+///
+/// `*out_len` must hold the capacity of `out`, at least `ciphertext_len - 16`
+/// bytes, and is set to the length of the plaintext. Nothing is written to
+/// `out` unless the message is authentic.
+#[no_mangle]
+pub unsafe extern "C" fn miscreant_aes128_siv_open(
+    siv: *mut MiscreantAes128Siv,
+    nonce: *const u8,
+    nonce_len: usize,
+    ad: *const u8,
+    ad_len: usize,
+    ciphertext: *const u8,
+    ciphertext_len: usize,
+    out: *mut u8,
+    out_len: *mut usize,
+) -> c_int {
+    if siv.is_null() || out_len.is_null() {
+        return MISCREANT_ERR_NULL;
+    }
+
+    let (nonce, ad, ciphertext, out) = match (
+        input(nonce, nonce_len),
+        input(ad, ad_len),
+        input(ciphertext, ciphertext_len),
+        output(out, *out_len),
+    ) {
+        (Some(n), Some(a), Some(c), Some(o)) => (n, a, c, o),
+        _ => return MISCREANT_ERR_NULL,
+    };
+
+    let siv = &mut (*siv).0;
+
+    guard(|| {
+        if ciphertext.len() < BLOCK_SIZE {
+            return MISCREANT_ERR_AUTH;
+        }
+
+        let len = ciphertext.len() - BLOCK_SIZE;
+
+        if out.len() < len {
+            return MISCREANT_ERR_LENGTH;
+        }
+
+        let mut buffer = ciphertext.to_vec();
+
+        let result = match siv.open_in_place(&[ad, nonce], &mut buffer) {
+            Ok(plaintext) => {
+                out[..len].copy_from_slice(plaintext);
+                *out_len = len;
+                MISCREANT_OK
+            }
+            Err(_) => MISCREANT_ERR_AUTH,
+        };
+
+        // Don't leave plaintext (authenticated or not) behind in the heap
+        buffer.as_mut_slice().zeroize();
+        result
+    })
+}
+
+/// Free an instance created by `miscreant_aes128_siv_new`, zeroizing its
+/// keys. Passing null does nothing.
+#[no_mangle]
+pub unsafe extern "C" fn miscreant_aes128_siv_free(siv: *mut MiscreantAes128Siv) {
+    if !siv.is_null() {
+        drop(Box::from_raw(siv));
+    }
+}
+
+/// Run `f`, reporting a panic as `MISCREANT_ERR_PANIC` instead of unwinding
+fn guard<F: FnOnce() -> c_int>(f: F) -> c_int {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(MISCREANT_ERR_PANIC)
+}
+
+/// Borrow an input buffer, which may only be null if it's empty
+unsafe fn input<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    if len == 0 {
+        Some(&[])
+    } else if data.is_null() {
+        None
+    } else {
+        Some(slice::from_raw_parts(data, len))
+    }
+}
+
+/// Borrow an output buffer, which may only be null if it's empty
+unsafe fn output<'a>(data: *mut u8, len: usize) -> Option<&'a mut [u8]> {
+    if len == 0 {
+        Some(&mut [])
+    } else if data.is_null() {
+        None
+    } else {
+        Some(slice::from_raw_parts_mut(data, len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{miscreant_aes128_siv_free, miscreant_aes128_siv_new, miscreant_aes128_siv_open,
+                miscreant_aes128_siv_seal, MiscreantAes128Siv, MISCREANT_ERR_AUTH,
+                MISCREANT_ERR_LENGTH, MISCREANT_ERR_NULL, MISCREANT_OK};
+    use core::ptr;
+    use siv::Aes128Siv;
+    use std::os::raw::c_int;
+
+    const KEY: [u8; 32] = [0x42; 32];
+    const NONCE: &[u8] = b"nonce";
+    const AD: &[u8] = b"associated data";
+
+    unsafe fn seal(
+        siv: *mut MiscreantAes128Siv,
+        plaintext: &[u8],
+        out: &mut [u8],
+    ) -> (c_int, usize) {
+        let mut len = out.len();
+        let status = miscreant_aes128_siv_seal(
+            siv,
+            NONCE.as_ptr(),
+            NONCE.len(),
+            AD.as_ptr(),
+            AD.len(),
+            plaintext.as_ptr(),
+            plaintext.len(),
+            out.as_mut_ptr(),
+            &mut len,
+        );
+        (status, len)
+    }
+
+    unsafe fn open(
+        siv: *mut MiscreantAes128Siv,
+        ciphertext: &[u8],
+        out: &mut [u8],
+    ) -> (c_int, usize) {
+        let mut len = out.len();
+        let status = miscreant_aes128_siv_open(
+            siv,
+            NONCE.as_ptr(),
+            NONCE.len(),
+            AD.as_ptr(),
+            AD.len(),
+            ciphertext.as_ptr(),
+            ciphertext.len(),
+            out.as_mut_ptr(),
+            &mut len,
+        );
+        (status, len)
+    }
+
+    #[test]
+    fn seal_and_open() {
+        unsafe {
+            let siv = miscreant_aes128_siv_new(KEY.as_ptr(), KEY.len());
+            assert!(!siv.is_null());
+
+            let mut sealed = [0u8; 32];
+            assert_eq!(seal(siv, b"hello", &mut sealed), (MISCREANT_OK, 21));
+
+            let mut expected = [0u8; 21];
+            Aes128Siv::new(&KEY).seal_to_slice(&[AD, NONCE], b"hello", &mut expected).unwrap();
+            assert_eq!(&sealed[..21], &expected[..]);
+
+            let mut opened = [0u8; 5];
+            assert_eq!(open(siv, &sealed[..21], &mut opened), (MISCREANT_OK, 5));
+            assert_eq!(&opened, b"hello");
+
+            miscreant_aes128_siv_free(siv);
+        }
+    }
+
+    #[test]
+    fn open_rejects_forgeries() {
+        unsafe {
+            let siv = miscreant_aes128_siv_new(KEY.as_ptr(), KEY.len());
+            let mut sealed = [0u8; 21];
+            seal(siv, b"hello", &mut sealed);
+            sealed[0] ^= 1;
+
+            let mut opened = [0u8; 5];
+            assert_eq!(open(siv, &sealed, &mut opened).0, MISCREANT_ERR_AUTH);
+            assert_eq!(open(siv, &sealed[..15], &mut opened).0, MISCREANT_ERR_AUTH);
+            assert_eq!(opened, [0u8; 5]);
+
+            miscreant_aes128_siv_free(siv);
+        }
+    }
+
+    #[test]
+    fn bad_lengths_and_null_pointers() {
+        unsafe {
+            assert!(miscreant_aes128_siv_new(KEY.as_ptr(), 16).is_null());
+            assert!(miscreant_aes128_siv_new(ptr::null(), 32).is_null());
+
+            let siv = miscreant_aes128_siv_new(KEY.as_ptr(), KEY.len());
+            let mut short = [0u8; 20];
+            assert_eq!(seal(siv, b"hello", &mut short).0, MISCREANT_ERR_LENGTH);
+
+            let mut sealed = [0u8; 21];
+            seal(siv, b"hello", &mut sealed);
+            assert_eq!(open(siv, &sealed, &mut [0u8; 4]).0, MISCREANT_ERR_LENGTH);
+
+            let mut len = 0;
+            let status = miscreant_aes128_siv_seal(
+                siv,
+                ptr::null(),
+                1,
+                ptr::null(),
+                0,
+                ptr::null(),
+                0,
+                ptr::null_mut(),
+                &mut len,
+            );
+            assert_eq!(status, MISCREANT_ERR_NULL);
+            assert_eq!(seal(ptr::null_mut(), b"", &mut sealed).0, MISCREANT_ERR_NULL);
+
+            miscreant_aes128_siv_free(siv);
+            miscreant_aes128_siv_free(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! `file.rs`: Encrypting whole files with STREAM
+//!
+//! Encrypted files start with a 12-byte header: a random 8-byte STREAM nonce
+//! prefix followed by the chunk size as a 32-bit big endian integer. The rest
+//! of the file is the plaintext split into chunks of that size (the last one
+//! possibly shorter, or empty), each sealed with AES-SIV STREAM using the
+//! header as associated data.
+
+use byteorder::{BigEndian, ByteOrder};
+use error::Error;
+use getrandom;
+use internals::BLOCK_SIZE;
+use std::error::Error as StdError;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::vec::Vec;
+use stream::{Aes128SivDecryptor, Aes128SivEncryptor, NONCE_PREFIX_SIZE};
+
+/// Size of the header at the start of an encrypted file
+pub const HEADER_SIZE: usize = NONCE_PREFIX_SIZE + 4;
+
+/// Errors which can occur when encrypting or decrypting a file
+#[derive(Debug)]
+pub enum FileError {
+    /// Reading, writing, or generating the random nonce prefix failed
+    Io(io::Error),
+
+    /// The file's header is truncated or has a chunk size of zero
+    InvalidHeader,
+
+    /// The chunk size passed to `encrypt_file` is zero
+    InvalidChunkSize,
+
+    /// Encrypting a chunk with STREAM failed
+    Stream(Error),
+
+    /// A chunk failed to authenticate: the file was modified, truncated, or
+    /// encrypted with a different key
+    Authentication,
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FileError::Io(ref err) => write!(f, "I/O error: {}", err),
+            FileError::InvalidHeader => write!(f, "invalid encrypted file header"),
+            FileError::InvalidChunkSize => write!(f, "chunk size must be nonzero"),
+            FileError::Stream(err) => write!(f, "STREAM encryption failed: {}", err),
+            FileError::Authentication => write!(f, "encrypted file failed to authenticate"),
+        }
+    }
+}
+
+impl StdError for FileError {
+    fn description(&self) -> &str {
+        match *self {
+            FileError::Io(ref err) => err.description(),
+            FileError::InvalidHeader => "invalid encrypted file header",
+            FileError::InvalidChunkSize => "chunk size must be nonzero",
+            FileError::Stream(_) => "STREAM encryption failed",
+            FileError::Authentication => "encrypted file failed to authenticate",
+        }
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        match *self {
+            FileError::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for FileError {
+    fn from(err: io::Error) -> FileError {
+        FileError::Io(err)
+    }
+}
+
+/// Encrypt the file at `input` to `output` with AES-SIV STREAM, in chunks of
+/// `chunk_size` bytes, under a freshly generated random nonce prefix
+///
+/// Returns `FileError::InvalidChunkSize` if `chunk_size` is zero, before
+/// either file is opened.
+pub fn encrypt_file(
+    key: &[u8; 32],
+    input: &Path,
+    output: &Path,
+    chunk_size: u32,
+) -> Result<(), FileError> {
+    if chunk_size == 0 {
+        return Err(FileError::InvalidChunkSize);
+    }
+
+    let mut header = [0u8; HEADER_SIZE];
+    getrandom::getrandom(&mut header[..NONCE_PREFIX_SIZE]).map_err(|_| {
+        io::Error::new(io::ErrorKind::Other, "failed to generate nonce prefix")
+    })?;
+    BigEndian::write_u32(&mut header[NONCE_PREFIX_SIZE..], chunk_size);
+
+    let mut reader = BufReader::new(File::open(input)?);
+    let mut writer = BufWriter::new(File::create(output)?);
+    writer.write_all(&header)?;
+
+    let mut encryptor = Aes128SivEncryptor::new(key, array_ref!(header, 0, NONCE_PREFIX_SIZE));
+    let mut chunk = read_chunk(&mut reader, chunk_size as usize)?;
+
+    // Read one chunk ahead so we know when we've reached the last one
+    loop {
+        let next = read_chunk(&mut reader, chunk_size as usize)?;
+
+        if next.is_empty() {
+            let sealed = encryptor
+                .encrypt_last(&header, &chunk)
+                .map_err(FileError::Stream)?;
+
+            writer.write_all(&sealed)?;
+            break;
+        }
+
+        let sealed = encryptor
+            .encrypt_next(&header, &chunk)
+            .map_err(FileError::Stream)?;
+
+        writer.write_all(&sealed)?;
+        chunk = next;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Decrypt a file produced by `encrypt_file` at `input` to `output`
+///
+/// If the file fails to authenticate (including if it's been truncated,
+/// which is only detected at the end), the partially written output is
+/// removed so no unauthenticated plaintext is left behind.
+pub fn decrypt_file(key: &[u8; 32], input: &Path, output: &Path) -> Result<(), FileError> {
+    let mut reader = BufReader::new(File::open(input)?);
+    let header = read_chunk(&mut reader, HEADER_SIZE)?;
+
+    if header.len() != HEADER_SIZE {
+        return Err(FileError::InvalidHeader);
+    }
+
+    let chunk_size = BigEndian::read_u32(&header[NONCE_PREFIX_SIZE..]);
+
+    if chunk_size == 0 {
+        return Err(FileError::InvalidHeader);
+    }
+
+    let writer = BufWriter::new(File::create(output)?);
+    let result = decrypt_chunks(key, &header, chunk_size, reader, writer);
+
+    if result.is_err() {
+        // Best effort: report the original error rather than this one
+        let _ = fs::remove_file(output);
+    }
+
+    result
+}
+
+/// Decrypt the chunks following the header, writing the plaintext out
+fn decrypt_chunks<R: Read, W: Write>(
+    key: &[u8; 32],
+    header: &[u8],
+    chunk_size: u32,
+    mut reader: R,
+    mut writer: W,
+) -> Result<(), FileError> {
+    let mut decryptor = Aes128SivDecryptor::new(key, array_ref!(header, 0, NONCE_PREFIX_SIZE));
+
+    // The chunk size comes from the header, so it mustn't be trusted not to
+    // overflow on targets where `usize` is 32 bits
+    let sealed_size = (chunk_size as usize)
+        .checked_add(BLOCK_SIZE)
+        .ok_or(FileError::InvalidHeader)?;
+    let mut chunk = read_chunk(&mut reader, sealed_size)?;
+
+    loop {
+        let next = read_chunk(&mut reader, sealed_size)?;
+
+        if next.is_empty() {
+            let plaintext = decryptor
+                .decrypt_last(header, &chunk)
+                .map_err(|_| FileError::Authentication)?;
+
+            writer.write_all(&plaintext)?;
+            break;
+        }
+
+        let plaintext = decryptor
+            .decrypt_next(header, &chunk)
+            .map_err(|_| FileError::Authentication)?;
+
+        writer.write_all(&plaintext)?;
+        chunk = next;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Read up to `size` bytes, stopping early only at the end of the input
+///
+/// Memory is allocated as data is read, so a bogus chunk size in a header
+/// can't force a huge allocation up front.
+fn read_chunk<R: Read>(reader: &mut R, size: usize) -> io::Result<Vec<u8>> {
+    let mut chunk = Vec::new();
+    reader.by_ref().take(size as u64).read_to_end(&mut chunk)?;
+    Ok(chunk)
+}
@@ -0,0 +1,590 @@
+//! `gcm_siv.rs`: The AES-GCM-SIV misuse resistant AEAD (RFC 8452)
+//!
+//! AES-GCM-SIV derives a fresh authentication and encryption key for every
+//! nonce, computes a synthetic IV with POLYVAL over the associated data and
+//! plaintext, and encrypts with a CTR mode whose counter is the first 32 bits
+//! of the tag (with its most significant bit set) incremented as a little
+//! endian integer. Like SIV, reusing a nonce only reveals whether the same
+//! message was encrypted under it.
+//!
+//! `GcmSiv` has the same `seal_in_place`/`open_in_place` surface as `Siv`,
+//! with the nonce as the last associated data item, so code can switch
+//! between them. Unlike SIV, the tag is *appended* to the ciphertext, as in
+//! RFC 8452.
+
+#[cfg(feature = "aead")]
+use aead::{self, AeadCore, AeadInPlace, Key, NewAead, Nonce};
+#[cfg(feature = "aead")]
+use aead::generic_array::GenericArray;
+#[cfg(feature = "aead")]
+use aead::generic_array::typenum::{U0, U12, U16, U32};
+use byteorder::{ByteOrder, LittleEndian};
+use error::Error;
+use internals::{Aes128, Aes256, BLOCK_SIZE, Block, Block8, BlockCipher, CounterMode, Ctr, Polyval};
+#[cfg(feature = "rand_core")]
+use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "std")]
+use std::vec::Vec;
+use subtle::ConstantTimeEq;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// Size of an AES-GCM-SIV nonce
+pub const NONCE_SIZE: usize = 12;
+
+/// Maximum length of the plaintext and of the associated data (2^36 bytes)
+pub const MAX_LENGTH: u64 = 1 << 36;
+
+/// Block ciphers usable with AES-GCM-SIV, which needs to instantiate the
+/// cipher with the encryption key it derives for each nonce
+pub trait GcmSivCipher: BlockCipher {
+    /// Create a new cipher from a key of exactly `KEY_SIZE` bytes
+    fn from_key_slice(key: &[u8]) -> Self;
+}
+
+impl GcmSivCipher for Aes128 {
+    #[inline]
+    fn from_key_slice(key: &[u8]) -> Self {
+        Aes128::new(array_ref!(key, 0, 16))
+    }
+}
+
+impl GcmSivCipher for Aes256 {
+    #[inline]
+    fn from_key_slice(key: &[u8]) -> Self {
+        Aes256::new(array_ref!(key, 0, 32))
+    }
+}
+
+/// The AES-GCM-SIV misuse resistant AEAD, generic over the block cipher
+///
+/// Only the key-generating key is kept: the per-nonce keys are derived for
+/// every message and wiped afterwards. The key-generating key's schedule is
+/// zeroized when a `GcmSiv` is dropped.
+///
+/// # Keys
+///
+/// Unlike SIV, the key is a single AES key (16 bytes for `Aes128GcmSiv`, 32
+/// bytes for `Aes256GcmSiv`), since the keys actually used for POLYVAL and
+/// CTR mode are derived from it. `generate_key` produces a key of the right
+/// size.
+#[derive(Clone)]
+pub struct GcmSiv<C: GcmSivCipher> {
+    key_generating_cipher: C,
+}
+
+/// AES-GCM-SIV with a 128-bit key
+pub type Aes128GcmSiv = GcmSiv<Aes128>;
+
+/// AES-GCM-SIV with a 256-bit key
+pub type Aes256GcmSiv = GcmSiv<Aes256>;
+
+/// The cipher wipes itself when dropped, so a `GcmSiv` needs no `Drop` of its
+/// own
+impl<C: GcmSivCipher + ZeroizeOnDrop> ZeroizeOnDrop for GcmSiv<C> {}
+
+impl GcmSiv<Aes128> {
+    /// Create a new AES-128-GCM-SIV instance with a 16-byte key
+    pub fn new(key: &[u8; 16]) -> Self {
+        Self { key_generating_cipher: Aes128::new(key) }
+    }
+
+    /// Generate a random 16-byte key with the given cryptographically secure
+    /// RNG
+    #[cfg(feature = "rand_core")]
+    pub fn generate_key<R: RngCore + CryptoRng>(rng: &mut R) -> [u8; 16] {
+        let mut key = [0u8; 16];
+        rng.fill_bytes(&mut key);
+        key
+    }
+}
+
+impl GcmSiv<Aes256> {
+    /// Create a new AES-256-GCM-SIV instance with a 32-byte key
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self { key_generating_cipher: Aes256::new(key) }
+    }
+
+    /// Generate a random 32-byte key with the given cryptographically secure
+    /// RNG
+    #[cfg(feature = "rand_core")]
+    pub fn generate_key<R: RngCore + CryptoRng>(rng: &mut R) -> [u8; 32] {
+        let mut key = [0u8; 32];
+        rng.fill_bytes(&mut key);
+        key
+    }
+}
+
+impl<C: GcmSivCipher> GcmSiv<C> {
+    /// Encrypt the given plaintext in-place, replacing it with the ciphertext
+    /// followed by the tag. Requires a buffer with 16-bytes additional space
+    /// at the end, as with `Siv::seal_in_place`.
+    ///
+    /// The associated data items are an optional associated data string
+    /// followed by the 12-byte nonce, e.g. `&[header, nonce]` or `&[nonce]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `plaintext.len()` is less than `BLOCK_SIZE`.
+    /// Panics if the associated data items aren't as described above, or if
+    /// the plaintext or associated data are longer than `MAX_LENGTH`.
+    pub fn seal_in_place<I, T>(&mut self, associated_data: I, plaintext: &mut [u8])
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        if plaintext.len() < BLOCK_SIZE {
+            panic!("plaintext buffer too small to hold GCM-SIV tag!");
+        }
+
+        let len = plaintext.len().checked_sub(BLOCK_SIZE).unwrap();
+        let tag = self.seal_in_place_detached(associated_data, &mut plaintext[..len]);
+        plaintext[len..].copy_from_slice(&tag);
+    }
+
+    /// Encrypt the given plaintext in-place without any additional space,
+    /// returning the tag separately
+    ///
+    /// # Panics
+    ///
+    /// Panics if the associated data items aren't as described in
+    /// `seal_in_place`, or if the plaintext or associated data are longer
+    /// than `MAX_LENGTH`.
+    pub fn seal_in_place_detached<I, T>(
+        &mut self,
+        associated_data: I,
+        plaintext: &mut [u8],
+    ) -> [u8; BLOCK_SIZE]
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let (ad, nonce) = split_nonce(associated_data);
+        let ad = ad.as_ref().map(|ad| ad.as_ref()).unwrap_or(&[]);
+
+        let tag = self.seal_with_nonce(nonce_ref(nonce.as_ref()), ad, plaintext);
+        *array_ref!(tag.as_ref(), 0, BLOCK_SIZE)
+    }
+
+    /// Encrypt the given plaintext, returning the tag and ciphertext
+    /// separately
+    ///
+    /// # Panics
+    ///
+    /// Panics if the associated data items aren't as described in
+    /// `seal_in_place`, or if the plaintext or associated data are longer
+    /// than `MAX_LENGTH`.
+    #[cfg(feature = "std")]
+    pub fn seal_detached<I, T>(
+        &mut self,
+        associated_data: I,
+        plaintext: &[u8],
+    ) -> ([u8; BLOCK_SIZE], Vec<u8>)
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let mut ciphertext = plaintext.to_vec();
+        let tag = self.seal_in_place_detached(associated_data, &mut ciphertext);
+
+        (tag, ciphertext)
+    }
+
+    /// Decrypt the given ciphertext (followed by the tag) in-place, returning
+    /// the authenticated plaintext
+    ///
+    /// The associated data items are as described in `seal_in_place`.
+    /// Returns `Error::Authentication` if the message is too short to contain a
+    /// tag, is longer than `MAX_LENGTH`, or fails authentication.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the associated data items aren't as described in
+    /// `seal_in_place`.
+    pub fn open_in_place<'a, I, T>(
+        &mut self,
+        associated_data: I,
+        ciphertext: &'a mut [u8],
+    ) -> Result<&'a [u8], Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        self.open(associated_data, ciphertext)
+    }
+
+    /// Decrypt a message (ciphertext followed by the tag) in-place, leaving
+    /// just the authenticated plaintext in the buffer
+    ///
+    /// Returns `Error::Authentication` if the message is too short to contain a
+    /// tag or fails authentication, in which case the buffer is zeroed so no
+    /// unauthenticated plaintext is left behind.
+    #[cfg(feature = "std")]
+    pub fn open_in_place_vec<I, T>(
+        &mut self,
+        associated_data: I,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let len = match self.open(associated_data, buffer) {
+            Ok(plaintext) => plaintext.len(),
+            Err(e) => {
+                buffer.as_mut_slice().zeroize();
+                return Err(e);
+            }
+        };
+
+        buffer.truncate(len);
+        Ok(())
+    }
+
+    /// Decrypt a ciphertext whose tag is stored separately (e.g. produced by
+    /// `seal_detached`), authenticating it against the tag
+    ///
+    /// Returns `Error::Authentication` if the ciphertext fails authentication.
+    #[cfg(feature = "std")]
+    pub fn open_detached<I, T>(
+        &mut self,
+        tag: &[u8; BLOCK_SIZE],
+        associated_data: I,
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let (ad, nonce) = split_nonce(associated_data);
+        let ad = ad.as_ref().map(|ad| ad.as_ref()).unwrap_or(&[]);
+        let mut plaintext = ciphertext.to_vec();
+        self.open_detached_in_place(tag, nonce_ref(nonce.as_ref()), ad, &mut plaintext)?;
+        Ok(plaintext)
+    }
+
+    /// Decrypt and authenticate as described in `open_in_place`
+    fn open<'a, I, T>(
+        &self,
+        associated_data: I,
+        ciphertext: &'a mut [u8],
+    ) -> Result<&'a [u8], Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let (ad, nonce) = split_nonce(associated_data);
+        let ad = ad.as_ref().map(|ad| ad.as_ref()).unwrap_or(&[]);
+
+        if ciphertext.len() < BLOCK_SIZE {
+            return Err(Error::Authentication);
+        }
+
+        let len = ciphertext.len().checked_sub(BLOCK_SIZE).unwrap();
+        let tag = *array_ref!(ciphertext, len, BLOCK_SIZE);
+
+        self.open_detached_in_place(&tag, nonce_ref(nonce.as_ref()), ad, &mut ciphertext[..len])?;
+        Ok(&ciphertext[..len])
+    }
+
+    /// Encrypt the plaintext in-place with the given nonce and associated
+    /// data, returning the tag
+    fn seal_with_nonce(&self, nonce: &[u8; NONCE_SIZE], ad: &[u8], plaintext: &mut [u8]) -> Block {
+        if ad.len() as u64 > MAX_LENGTH || plaintext.len() as u64 > MAX_LENGTH {
+            panic!("AES-GCM-SIV inputs are limited to 2^36 bytes");
+        }
+
+        let (mut polyval, cipher) = self.derive_keys(nonce);
+        let tag = compute_tag(&mut polyval, &cipher, nonce, ad, plaintext);
+        transform(&cipher, &tag, plaintext);
+
+        tag
+    }
+
+    /// Decrypt the given ciphertext in-place and authenticate it against the
+    /// given tag. On failure the ciphertext is re-encrypted, so no
+    /// unauthenticated plaintext is left behind.
+    fn open_detached_in_place(
+        &self,
+        tag: &[u8; BLOCK_SIZE],
+        nonce: &[u8; NONCE_SIZE],
+        ad: &[u8],
+        ciphertext: &mut [u8],
+    ) -> Result<(), Error> {
+        if ad.len() as u64 > MAX_LENGTH || ciphertext.len() as u64 > MAX_LENGTH {
+            return Err(Error::Authentication);
+        }
+
+        let (mut polyval, cipher) = self.derive_keys(nonce);
+        let expected_tag = Block::from(*tag);
+
+        transform(&cipher, &expected_tag, ciphertext);
+        let actual_tag = compute_tag(&mut polyval, &cipher, nonce, ad, ciphertext);
+
+        if !bool::from(actual_tag.ct_eq(&expected_tag)) {
+            // Re-encrypt the decrypted plaintext to avoid revealing it
+            transform(&cipher, &expected_tag, ciphertext);
+            return Err(Error::Authentication);
+        }
+
+        Ok(())
+    }
+
+    /// Derive the per-nonce POLYVAL key and encryption cipher: each is built
+    /// from the first 8 bytes of encrypting consecutive blocks consisting of
+    /// a 32-bit little endian counter followed by the nonce
+    fn derive_keys(&self, nonce: &[u8; NONCE_SIZE]) -> (Polyval, C) {
+        let mut blocks = Block8::new();
+        let count = 2 + C::KEY_SIZE / 8;
+
+        for (i, block) in blocks.as_blocks_mut()[..count].iter_mut().enumerate() {
+            let bytes: &mut [u8] = block.as_mut();
+            LittleEndian::write_u32(&mut bytes[..4], i as u32);
+            bytes[4..].copy_from_slice(nonce);
+        }
+
+        self.key_generating_cipher.encrypt8(&mut blocks);
+
+        let mut auth_key = Block::new();
+        let mut enc_key = [0u8; 32];
+
+        for (i, block) in blocks.as_blocks()[..count].iter().enumerate() {
+            let half = &block.as_ref()[..8];
+
+            if i < 2 {
+                let bytes: &mut [u8] = auth_key.as_mut();
+                bytes[i * 8..(i + 1) * 8].copy_from_slice(half);
+            } else {
+                enc_key[(i - 2) * 8..(i - 1) * 8].copy_from_slice(half);
+            }
+        }
+
+        let cipher = C::from_key_slice(&enc_key[..C::KEY_SIZE]);
+        enc_key.as_mut().zeroize();
+
+        (Polyval::new(auth_key), cipher)
+    }
+}
+
+/// Split the associated data items into the optional associated data
+/// string and the nonce, which must be the last item
+fn split_nonce<I, T>(associated_data: I) -> (Option<T>, T)
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<[u8]>,
+{
+    let mut ad = None;
+    let mut nonce = None;
+
+    for item in associated_data {
+        if ad.is_some() {
+            panic!("AES-GCM-SIV takes at most one associated data item before the nonce");
+        }
+
+        ad = nonce.take();
+        nonce = Some(item);
+    }
+
+    (ad, nonce.expect("AES-GCM-SIV requires a nonce"))
+}
+
+/// Check the nonce is the right length
+fn nonce_ref(nonce: &[u8]) -> &[u8; NONCE_SIZE] {
+    if nonce.len() != NONCE_SIZE {
+        panic!("AES-GCM-SIV nonces must be {} bytes", NONCE_SIZE);
+    }
+
+    array_ref!(nonce, 0, NONCE_SIZE)
+}
+
+/// Compute the tag: POLYVAL over the associated data, plaintext and their
+/// bit lengths, XORed with the nonce, with the top bit cleared, encrypted
+fn compute_tag<C: BlockCipher>(
+    polyval: &mut Polyval,
+    cipher: &C,
+    nonce: &[u8; NONCE_SIZE],
+    ad: &[u8],
+    plaintext: &[u8],
+) -> Block {
+    let mut lengths = Block::new();
+
+    {
+        let bytes: &mut [u8] = lengths.as_mut();
+        LittleEndian::write_u64(&mut bytes[..8], ad.len() as u64 * 8);
+        LittleEndian::write_u64(&mut bytes[8..], plaintext.len() as u64 * 8);
+    }
+
+    polyval.update_padded(ad);
+    polyval.update_padded(plaintext);
+    polyval.update(&[lengths]);
+
+    let mut tag = polyval.finish();
+
+    {
+        let bytes: &mut [u8] = tag.as_mut();
+
+        for (byte, n) in bytes.iter_mut().zip(nonce.iter()) {
+            *byte ^= n;
+        }
+
+        bytes[BLOCK_SIZE - 1] &= 0x7f;
+    }
+
+    cipher.encrypt(&mut tag);
+    tag
+}
+
+/// Encrypt/decrypt the given data in-place using CTR mode with a 32-bit
+/// little endian counter, starting from the tag with its top bit set
+fn transform<C: BlockCipher>(cipher: &C, tag: &Block, data: &mut [u8]) {
+    let mut counter = tag.clone();
+
+    {
+        let bytes: &mut [u8] = counter.as_mut();
+        bytes[BLOCK_SIZE - 1] |= 0x80;
+    }
+
+    // RFC 8452 counters wrap around rather than overflowing, and messages
+    // are limited to `MAX_LENGTH` so keystream is never reused
+    Ctr::with_counter_mode(cipher.clone(), &counter, CounterMode::LittleEndian32)
+        .apply_keystream(data)
+        .expect("GCM-SIV counters never overflow");
+}
+
+/// The RustCrypto AEAD traits use RFC 8452's 12-byte nonces, 16-byte tags,
+/// and ciphertext-then-tag layout, so `GcmSiv` interoperates with other
+/// implementations of them.
+#[cfg(feature = "aead")]
+impl<C: GcmSivCipher> AeadCore for GcmSiv<C> {
+    type NonceSize = U12;
+    type TagSize = U16;
+    type CiphertextOverhead = U0;
+}
+
+#[cfg(feature = "aead")]
+impl NewAead for GcmSiv<Aes128> {
+    type KeySize = U16;
+
+    #[inline]
+    fn new(key: &Key<Self>) -> Self {
+        Self::new(array_ref!(key, 0, 16))
+    }
+}
+
+#[cfg(feature = "aead")]
+impl NewAead for GcmSiv<Aes256> {
+    type KeySize = U32;
+
+    #[inline]
+    fn new(key: &Key<Self>) -> Self {
+        Self::new(array_ref!(key, 0, 32))
+    }
+}
+
+#[cfg(feature = "aead")]
+impl<C: GcmSivCipher> AeadInPlace for GcmSiv<C> {
+    fn encrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<aead::Tag<Self>, aead::Error> {
+        if associated_data.len() as u64 > MAX_LENGTH || buffer.len() as u64 > MAX_LENGTH {
+            return Err(aead::Error);
+        }
+
+        let tag = self.seal_with_nonce(nonce_ref(nonce), associated_data, buffer);
+        Ok(GenericArray::clone_from_slice(tag.as_ref()))
+    }
+
+    fn decrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+        tag: &aead::Tag<Self>,
+    ) -> Result<(), aead::Error> {
+        let tag = array_ref!(tag, 0, BLOCK_SIZE);
+
+        self.open_detached_in_place(tag, nonce_ref(nonce), associated_data, buffer)
+            .map_err(|_| aead::Error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Aes128GcmSiv, Aes256GcmSiv};
+    use internals::{Aes128, Aes256, Block, BlockCipher};
+
+    const NONCE: [u8; 12] = [3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+    /// Check a derived cipher against one created with the expected key
+    fn assert_same_cipher<C: BlockCipher>(actual: &C, expected: &C) {
+        let mut actual_block = Block::new();
+        let mut expected_block = Block::new();
+        actual.encrypt(&mut actual_block);
+        expected.encrypt(&mut expected_block);
+        assert_eq!(actual_block.as_ref(), expected_block.as_ref());
+    }
+
+    /// RFC 8452 Appendix C.1: per-nonce key derivation with AES-128
+    #[test]
+    fn derive_keys_aes128() {
+        let mut key = [0u8; 16];
+        key[0] = 1;
+
+        let (mut polyval, cipher) = Aes128GcmSiv::new(&key).derive_keys(&NONCE);
+
+        assert_same_cipher(
+            &cipher,
+            &Aes128::new(&[
+                0x40, 0x04, 0xa0, 0xdc, 0xd8, 0x62, 0xf2, 0xa5,
+                0x73, 0x60, 0x21, 0x9d, 0x2d, 0x44, 0xef, 0x6c,
+            ]),
+        );
+
+        // The derived authentication key d9b360279694941ac5dbc6987ada7377 is
+        // the one used by the POLYVAL example in RFC 8452 Appendix C.1
+        polyval.update_padded(&[1, 0, 0, 0, 0, 0, 0, 0]);
+        polyval.update_padded(&[0, 0, 0, 0, 0, 0, 0, 0, 0x40, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            polyval.finish().as_ref(),
+            &[
+                0xeb, 0x93, 0xb7, 0x74, 0x09, 0x62, 0xc5, 0xe4,
+                0x9d, 0x2a, 0x90, 0xa7, 0xdc, 0x5c, 0xec, 0x74,
+            ]
+        );
+    }
+
+    /// RFC 8452 Appendix C.2: per-nonce key derivation with AES-256
+    #[test]
+    fn derive_keys_aes256() {
+        let mut key = [0u8; 32];
+        key[0] = 1;
+
+        let (_, cipher) = Aes256GcmSiv::new(&key).derive_keys(&NONCE);
+
+        assert_same_cipher(
+            &cipher,
+            &Aes256::new(&[
+                0xb9, 0x14, 0xf4, 0x74, 0x2b, 0xe9, 0xe1, 0xd7,
+                0xa2, 0xf8, 0x4a, 0xdd, 0xbf, 0x96, 0xde, 0xc3,
+                0x45, 0x6e, 0x3c, 0x6c, 0x05, 0xec, 0xc1, 0x57,
+                0xcd, 0xbf, 0x07, 0x00, 0xfe, 0xda, 0xd2, 0x22,
+            ]),
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_many_associated_data_items() {
+        let mut buffer = [0u8; 16];
+        Aes128GcmSiv::new(&[0u8; 16]).seal_in_place(&[&b"a"[..], b"b", &NONCE], &mut buffer);
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrong_nonce_size() {
+        let mut buffer = [0u8; 16];
+        Aes128GcmSiv::new(&[0u8; 16]).seal_in_place(&[&[0u8; 16]], &mut buffer);
+    }
+}
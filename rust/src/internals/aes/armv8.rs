@@ -0,0 +1,96 @@
+//! `internals/aes/armv8.rs`: AES implementation using the ARMv8 Cryptography
+//! Extensions
+//!
+//! All functions here are `unsafe` as they must only be called on CPUs which
+//! support the AES instructions. `Backend::detect()` is responsible for
+//! checking that.
+
+use super::soft;
+use core::arch::aarch64::*;
+use internals::{Block, Block8, BLOCK8_COUNT, BLOCK_SIZE};
+
+/// Expand a 128, 192, or 256-bit key into the given round keys, using `AESE`
+/// to perform the SubWord step
+#[target_feature(enable = "aes")]
+pub unsafe fn expand_key(key: &[u8], round_keys: &mut [Block]) {
+    soft::expand_key_with(key, round_keys, sub_word);
+}
+
+/// Apply the S-box to each byte of a key schedule word
+///
+/// Only ever called from `expand_key`, whose caller has checked the CPU
+/// supports the AES instructions
+fn sub_word(word: &mut [u8; 4]) {
+    unsafe { sub_word_aese(word) }
+}
+
+/// Broadcast the word into every column so `AESE`'s ShiftRows step leaves it
+/// unchanged, leaving just SubBytes (the round key is zero)
+#[target_feature(enable = "aes")]
+unsafe fn sub_word_aese(word: &mut [u8; 4]) {
+    let mut bytes = [0u8; BLOCK_SIZE];
+    for column in bytes.chunks_mut(4) {
+        column.copy_from_slice(word);
+    }
+
+    let state = vaeseq_u8(vld1q_u8(bytes.as_ptr()), vdupq_n_u8(0));
+    vst1q_u8(bytes.as_mut_ptr(), state);
+
+    word.copy_from_slice(&bytes[..4]);
+}
+
+/// Encrypt a block in-place using the given expanded round keys
+///
+/// `AESE` performs AddRoundKey, ShiftRows, and SubBytes, so each round key is
+/// applied one step earlier than in FIPS-197 and the last is XORed in after
+/// the final round.
+#[target_feature(enable = "aes")]
+pub unsafe fn encrypt(round_keys: &[Block], block: &mut Block) {
+    let rounds = round_keys.len() - 1;
+    let mut state = load(block);
+
+    for round_key in &round_keys[..rounds - 1] {
+        state = vaesmcq_u8(vaeseq_u8(state, load(round_key)));
+    }
+
+    state = vaeseq_u8(state, load(&round_keys[rounds - 1]));
+    store(block, veorq_u8(state, load(&round_keys[rounds])));
+}
+
+/// Encrypt eight blocks in-place, interleaving them so the AES units can
+/// pipeline the rounds of independent blocks
+#[target_feature(enable = "aes")]
+pub unsafe fn encrypt8(round_keys: &[Block], blocks: &mut Block8) {
+    let rounds = round_keys.len() - 1;
+    let mut state = [vdupq_n_u8(0); BLOCK8_COUNT];
+
+    for (s, block) in state.iter_mut().zip(blocks.as_blocks().iter()) {
+        *s = load(block);
+    }
+
+    for round_key in &round_keys[..rounds - 1] {
+        let round_key = load(round_key);
+        for s in state.iter_mut() {
+            *s = vaesmcq_u8(vaeseq_u8(*s, round_key));
+        }
+    }
+
+    let round_key = load(&round_keys[rounds - 1]);
+    let last_key = load(&round_keys[rounds]);
+    for (s, block) in state.iter().zip(blocks.as_blocks_mut().iter_mut()) {
+        store(block, veorq_u8(vaeseq_u8(*s, round_key), last_key));
+    }
+}
+
+/// Load a block into a SIMD register
+#[inline(always)]
+unsafe fn load(block: &Block) -> uint8x16_t {
+    vld1q_u8(block.as_ref().as_ptr())
+}
+
+/// Store a SIMD register into a block
+#[inline(always)]
+unsafe fn store(block: &mut Block, value: uint8x16_t) {
+    let bytes: &mut [u8] = block.as_mut();
+    vst1q_u8(bytes.as_mut_ptr(), value)
+}
@@ -0,0 +1,421 @@
+//! `internals/aes/bitsliced.rs`: Constant-time bitsliced software AES
+//!
+//! The state is transposed into eight bit planes, where plane `b` holds bit
+//! `b` of every byte. Each 16-bit lane of a plane holds one block, with bit `i`
+//! of the lane corresponding to byte `i` of the block. `u16` planes encrypt a
+//! single block, while `u128` planes encrypt eight blocks at once.
+//!
+//! SubBytes computes the multiplicative inverse in GF(2^8) as `x^254` using
+//! bitsliced multiplications, and the other steps become fixed permutations
+//! of the planes, so there are no secret-dependent branches or memory accesses.
+
+use super::soft;
+use core::ops::{BitAnd, BitOr, BitXor, Not, Shl, Shr};
+use internals::{Block, Block8, BLOCK_SIZE};
+
+/// Bits per AES state byte, i.e. number of bit planes
+const PLANES: usize = 8;
+
+/// Bits per lane: one for each byte of a block
+const LANE_BITS: usize = BLOCK_SIZE;
+
+/// Bit planes of a bitsliced state
+type Planes<W> = [W; PLANES];
+
+/// A machine word holding one or more 16-bit lanes of a bit plane
+trait Lanes:
+    Copy
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + BitXor<Output = Self>
+    + Not<Output = Self>
+    + Shl<u32, Output = Self>
+    + Shr<u32, Output = Self>
+{
+    /// Replicate a 16-bit pattern across every lane
+    fn splat(lane: u16) -> Self;
+}
+
+impl Lanes for u16 {
+    #[inline(always)]
+    fn splat(lane: u16) -> Self {
+        lane
+    }
+}
+
+impl Lanes for u128 {
+    #[inline(always)]
+    fn splat(lane: u16) -> Self {
+        u128::from(lane) * 0x0001_0001_0001_0001_0001_0001_0001_0001
+    }
+}
+
+/// Expand a 128, 192, or 256-bit key into the given round keys, using the
+/// bitsliced S-box so the key schedule is constant-time too
+pub fn expand_key(key: &[u8], round_keys: &mut [Block]) {
+    soft::expand_key_with(key, round_keys, sub_word);
+}
+
+/// Encrypt a block in-place using the given expanded round keys
+pub fn encrypt(round_keys: &[Block], block: &mut Block) {
+    let mut state = pack(block);
+    encrypt_planes(round_keys, &mut state);
+    unpack(&state, block);
+}
+
+/// Encrypt eight blocks in-place in a single pass using 128-bit planes
+pub fn encrypt8(round_keys: &[Block], blocks: &mut Block8) {
+    let mut state = [0u128; PLANES];
+
+    for (lane, block) in blocks.as_blocks().iter().enumerate() {
+        let planes = pack(block);
+        for (s, p) in state.iter_mut().zip(planes.iter()) {
+            *s |= u128::from(*p) << (lane * LANE_BITS);
+        }
+    }
+
+    encrypt_planes(round_keys, &mut state);
+
+    for (lane, block) in blocks.as_blocks_mut().iter_mut().enumerate() {
+        let mut planes = [0u16; PLANES];
+        for (p, s) in planes.iter_mut().zip(state.iter()) {
+            *p = (*s >> (lane * LANE_BITS)) as u16;
+        }
+        unpack(&planes, block);
+    }
+}
+
+/// Apply the S-box to each byte of a key schedule word
+fn sub_word(word: &mut [u8; 4]) {
+    let mut block = Block::new();
+    {
+        let bytes: &mut [u8] = block.as_mut();
+        bytes[..4].copy_from_slice(word);
+    }
+
+    let mut planes = pack(&block);
+    sub_bytes(&mut planes);
+    unpack(&planes, &mut block);
+
+    word.copy_from_slice(&block.as_ref()[..4]);
+}
+
+/// Run the AES rounds over a bitsliced state
+#[inline]
+fn encrypt_planes<W: Lanes>(round_keys: &[Block], state: &mut Planes<W>) {
+    let rounds = round_keys.len() - 1;
+
+    add_round_key(state, &round_keys[0]);
+
+    for round_key in &round_keys[1..rounds] {
+        sub_bytes(state);
+        shift_rows(state);
+        mix_columns(state);
+        add_round_key(state, round_key);
+    }
+
+    sub_bytes(state);
+    shift_rows(state);
+    add_round_key(state, &round_keys[rounds]);
+}
+
+/// Transpose a block into 16-bit bit planes
+#[inline]
+fn pack(block: &Block) -> Planes<u16> {
+    let mut planes = [0u16; PLANES];
+
+    for (i, &byte) in block.as_ref().iter().enumerate() {
+        for (b, plane) in planes.iter_mut().enumerate() {
+            *plane |= u16::from((byte >> b) & 1) << i;
+        }
+    }
+
+    planes
+}
+
+/// Transpose 16-bit bit planes back into a block
+#[inline]
+fn unpack(planes: &Planes<u16>, block: &mut Block) {
+    let bytes: &mut [u8] = block.as_mut();
+
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = 0;
+        for (b, plane) in planes.iter().enumerate() {
+            *byte |= (((*plane >> i) & 1) as u8) << b;
+        }
+    }
+}
+
+/// XOR a round key, replicated across every lane, into the state
+#[inline]
+fn add_round_key<W: Lanes>(state: &mut Planes<W>, round_key: &Block) {
+    let key = pack(round_key);
+
+    for (s, k) in state.iter_mut().zip(key.iter()) {
+        *s = *s ^ W::splat(*k);
+    }
+}
+
+/// Apply the S-box to every byte of the state: invert in GF(2^8), then apply
+/// the affine transformation (FIPS-197 5.1.1)
+#[inline]
+fn sub_bytes<W: Lanes>(state: &mut Planes<W>) {
+    let x = *state;
+
+    // x^254 = x^-1, via the addition chain 2, 3, 6, 12, 14, 15, 30, 60, 120, 240
+    let x2 = gf_square(&x);
+    let x3 = gf_mul(&x2, &x);
+    let x6 = gf_square(&x3);
+    let x12 = gf_square(&x6);
+    let x14 = gf_mul(&x12, &x2);
+    let x15 = gf_mul(&x12, &x3);
+    let x30 = gf_square(&x15);
+    let x60 = gf_square(&x30);
+    let x120 = gf_square(&x60);
+    let x240 = gf_square(&x120);
+    let inv = gf_mul(&x240, &x14);
+
+    for i in 0..PLANES {
+        state[i] = inv[i] ^ inv[(i + 4) % PLANES] ^ inv[(i + 5) % PLANES]
+            ^ inv[(i + 6) % PLANES] ^ inv[(i + 7) % PLANES];
+    }
+
+    // XOR with the constant 0x63
+    for &i in &[0, 1, 5, 6] {
+        state[i] = !state[i];
+    }
+}
+
+/// Multiply two bitsliced GF(2^8) elements
+#[inline]
+fn gf_mul<W: Lanes>(a: &Planes<W>, b: &Planes<W>) -> Planes<W> {
+    let zero = W::splat(0);
+    let mut product = [zero; 2 * PLANES - 1];
+
+    for i in 0..PLANES {
+        for j in 0..PLANES {
+            product[i + j] = product[i + j] ^ (a[i] & b[j]);
+        }
+    }
+
+    gf_reduce(&mut product)
+}
+
+/// Square a bitsliced GF(2^8) element, which is linear in characteristic 2
+#[inline]
+fn gf_square<W: Lanes>(a: &Planes<W>) -> Planes<W> {
+    let zero = W::splat(0);
+    let mut product = [zero; 2 * PLANES - 1];
+
+    for i in 0..PLANES {
+        product[2 * i] = a[i];
+    }
+
+    gf_reduce(&mut product)
+}
+
+/// Reduce a polynomial product modulo x^8 + x^4 + x^3 + x + 1
+#[inline]
+fn gf_reduce<W: Lanes>(product: &mut [W; 2 * PLANES - 1]) -> Planes<W> {
+    for k in (PLANES..2 * PLANES - 1).rev() {
+        let high = product[k];
+        product[k - 8] = product[k - 8] ^ high;
+        product[k - 7] = product[k - 7] ^ high;
+        product[k - 5] = product[k - 5] ^ high;
+        product[k - 4] = product[k - 4] ^ high;
+    }
+
+    *array_ref!(product, 0, PLANES)
+}
+
+/// Rotate row `r` of every lane left by `r` columns. Byte `4c + r` holds
+/// row `r` of column `c`, so this permutes bits within each 16-bit lane.
+#[inline]
+fn shift_rows<W: Lanes>(state: &mut Planes<W>) {
+    for plane in state.iter_mut() {
+        let x = *plane;
+
+        *plane = (x & W::splat(0x1111))
+            | ((x & W::splat(0x2220)) >> 4) | ((x & W::splat(0x0002)) << 12)
+            | ((x & W::splat(0x4400)) >> 8) | ((x & W::splat(0x0044)) << 8)
+            | ((x & W::splat(0x8000)) >> 12) | ((x & W::splat(0x0888)) << 4);
+    }
+}
+
+/// Multiply each column by the MixColumns polynomial (FIPS-197 5.1.3):
+/// `out[r] = 2 * (a[r] ^ a[r + 1]) ^ a[r + 1] ^ a[r + 2] ^ a[r + 3]`
+#[inline]
+fn mix_columns<W: Lanes>(state: &mut Planes<W>) {
+    let mut r1 = *state;
+    let mut r2 = *state;
+    let mut r3 = *state;
+    let mut sum = *state;
+
+    for i in 0..PLANES {
+        r1[i] = rotate_rows(state[i], 1);
+        r2[i] = rotate_rows(state[i], 2);
+        r3[i] = rotate_rows(state[i], 3);
+        sum[i] = state[i] ^ r1[i];
+    }
+
+    let doubled = xtime(&sum);
+
+    for i in 0..PLANES {
+        state[i] = doubled[i] ^ r1[i] ^ r2[i] ^ r3[i];
+    }
+}
+
+/// Replace row `r` of every column with row `r + n` (mod 4), i.e. rotate
+/// each 4-bit column of every lane right by `n` bits
+#[inline(always)]
+fn rotate_rows<W: Lanes>(x: W, n: u32) -> W {
+    let low = (1u16 << n) - 1;
+    let low_mask = low * 0x1111;
+    ((x & W::splat(!low_mask)) >> n) | ((x & W::splat(low_mask)) << (4 - n))
+}
+
+/// Multiply a bitsliced element by x in GF(2^8)
+#[inline]
+fn xtime<W: Lanes>(a: &Planes<W>) -> Planes<W> {
+    [
+        a[7],
+        a[0] ^ a[7],
+        a[1],
+        a[2] ^ a[7],
+        a[3] ^ a[7],
+        a[4],
+        a[5],
+        a[6],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encrypt, expand_key, pack, sub_bytes, unpack};
+    use super::super::{soft, AES128_ROUND_KEYS};
+    use core::ptr;
+    use internals::{Block, BLOCK_SIZE};
+    use internals::test_rng::XorShift64;
+    use std::time::Instant;
+    use std::vec::Vec;
+
+    /// Number of timing measurements taken by each timing test
+    const TIMING_SAMPLES: usize = 200_000;
+
+    /// Welch's t-statistic above which timings are considered to depend on
+    /// the input (the threshold dudect uses for "definitely not constant time")
+    const TIMING_T_THRESHOLD: f64 = 10.0;
+
+    #[test]
+    fn sbox_matches_table() {
+        for chunk in 0..16 {
+            let mut bytes = [0u8; 16];
+            for (i, byte) in bytes.iter_mut().enumerate() {
+                *byte = (chunk * 16 + i) as u8;
+            }
+            let block = Block::from(bytes);
+
+            let mut planes = pack(&block);
+            sub_bytes(&mut planes);
+
+            let mut actual = Block::new();
+            unpack(&planes, &mut actual);
+
+            for (i, &byte) in actual.as_ref().iter().enumerate() {
+                assert_eq!(byte, soft::SBOX[bytes[i] as usize]);
+            }
+        }
+    }
+
+    #[test]
+    fn pack_round_trip() {
+        let block = Block::from([
+            0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40,
+            0x80, 0xff, 0x5a, 0xa5, 0x3c, 0xc3, 0x69, 0x96,
+        ]);
+
+        let mut actual = Block::new();
+        unpack(&pack(&block), &mut actual);
+        assert_eq!(actual.as_ref(), block.as_ref());
+    }
+
+    /// Time `op` on one fixed input and on random inputs, interleaved in a
+    /// random order, and return Welch's t-statistic between the two classes
+    /// (the "fixed vs random" test from dudect)
+    fn fixed_vs_random_t<F: FnMut(&mut Block)>(mut op: F) -> f64 {
+        let mut rng = XorShift64::new(0x2545_f491_4f6c_dd1d);
+
+        let fixed = Block::from([0x42u8; BLOCK_SIZE]);
+        let mut samples = Vec::with_capacity(TIMING_SAMPLES);
+
+        for _ in 0..TIMING_SAMPLES {
+            let random_class = rng.next_u64() & 1 == 1;
+
+            let mut input = if random_class {
+                rng.block()
+            } else {
+                fixed.clone()
+            };
+
+            let start = Instant::now();
+            op(&mut input);
+            let elapsed = start.elapsed();
+
+            // Keep the operation from being optimized away
+            unsafe { ptr::read_volatile(&input) };
+
+            let nanos = elapsed.as_secs() as f64 * 1e9 + f64::from(elapsed.subsec_nanos());
+            samples.push((random_class, nanos));
+        }
+
+        // Discard outliers (e.g. interrupts) above the 90th percentile
+        let mut sorted: Vec<f64> = samples.iter().map(|&(_, t)| t).collect();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let cutoff = sorted[sorted.len() * 9 / 10];
+
+        let mut n = [0f64; 2];
+        let mut sum = [0f64; 2];
+        let mut sum_sq = [0f64; 2];
+
+        for &(class, t) in samples.iter().filter(|&&(_, t)| t <= cutoff) {
+            let class = class as usize;
+            n[class] += 1.0;
+            sum[class] += t;
+            sum_sq[class] += t * t;
+        }
+
+        let mean = [sum[0] / n[0], sum[1] / n[1]];
+        let var = [
+            sum_sq[0] / n[0] - mean[0] * mean[0],
+            sum_sq[1] / n[1] - mean[1] * mean[1],
+        ];
+
+        (mean[0] - mean[1]) / (var[0] / n[0] + var[1] / n[1]).sqrt()
+    }
+
+    // Timing tests are statistical and sensitive to the machine they run on,
+    // so they're ignored by default. Run them with optimizations:
+    // `cargo test --release -- --ignored timing`
+
+    #[test]
+    #[ignore]
+    fn encrypt_timing_is_independent_of_plaintext() {
+        let mut round_keys: [Block; AES128_ROUND_KEYS] = Default::default();
+        expand_key(&[0x24u8; 16], &mut round_keys);
+
+        let t = fixed_vs_random_t(|block| encrypt(&round_keys, block));
+        assert!(t.abs() < TIMING_T_THRESHOLD, "t = {}", t);
+    }
+
+    #[test]
+    #[ignore]
+    fn key_expansion_timing_is_independent_of_key() {
+        let t = fixed_vs_random_t(|block| {
+            let mut round_keys: [Block; AES128_ROUND_KEYS] = Default::default();
+            expand_key(block.as_ref(), &mut round_keys);
+            block.copy_from_block(&round_keys[AES128_ROUND_KEYS - 1]);
+        });
+
+        assert!(t.abs() < TIMING_T_THRESHOLD, "t = {}", t);
+    }
+}
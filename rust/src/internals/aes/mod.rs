@@ -0,0 +1,801 @@
+//! `internals/aes`: The Advanced Encryption Standard block cipher
+//!
+//! On x86/x86_64 CPUs with AES-NI, or aarch64 CPUs with the ARMv8
+//! Cryptography Extensions, this uses the AES instructions, which are fast
+//! and constant-time. Other CPUs fall back to a portable software
+//! implementation: bitsliced and constant-time by default, or table-based
+//! (faster, but open to cache-timing attacks) with the `table-software-aes`
+//! feature. The backend is selected when the
+//! cipher is keyed: at runtime with the `std` feature, or from the
+//! compile-time target features (e.g. `-C target-feature=+aes`) otherwise.
+//!
+//! Building with `RUSTFLAGS="--cfg miscreant_force_soft_aes"` disables the
+//! AES instructions, so the test suite can exercise the software fallback on
+//! CPUs which have them.
+//!
+//! Key expansion happens once, in `new`. Cloning a cipher copies its expanded
+//! key schedule (and selected backend) instead of re-deriving it, which makes
+//! clones a cheap way to reuse one key across many constructions. Each copy
+//! is a separate secret: it's only wiped when that copy is dropped, so the
+//! schedule stays in memory until the last clone goes away.
+
+#[cfg(target_arch = "aarch64")]
+mod armv8;
+mod bitsliced;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod ni;
+mod soft;
+
+use super::{Block, Block8, BlockCipher};
+use core::fmt;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// Number of round keys for AES-128 (10 rounds plus the initial key)
+const AES128_ROUND_KEYS: usize = 11;
+
+/// Number of round keys for AES-192 (12 rounds plus the initial key)
+const AES192_ROUND_KEYS: usize = 13;
+
+/// Number of round keys for AES-256 (14 rounds plus the initial key)
+const AES256_ROUND_KEYS: usize = 15;
+
+/// Largest number of round keys of any supported key size
+const MAX_ROUND_KEYS: usize = AES256_ROUND_KEYS;
+
+/// Implementation used to perform AES operations
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Backend {
+    /// Portable table-based software implementation
+    #[cfg_attr(not(feature = "table-software-aes"), allow(dead_code))]
+    Soft,
+
+    /// Portable constant-time bitsliced software implementation
+    #[cfg_attr(feature = "table-software-aes", allow(dead_code))]
+    Bitsliced,
+
+    /// Intel AES-NI instructions
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    Ni,
+
+    /// ARMv8 Cryptography Extensions
+    #[cfg(target_arch = "aarch64")]
+    Armv8,
+}
+
+impl Backend {
+    /// Select the backend for newly keyed ciphers
+    #[inline]
+    fn select() -> Self {
+        if cfg!(miscreant_force_soft_aes) {
+            Backend::software()
+        } else {
+            Backend::detect()
+        }
+    }
+
+    /// Select the fastest backend supported by the current CPU
+    #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "std"))]
+    fn detect() -> Self {
+        if is_x86_feature_detected!("aes") {
+            Backend::Ni
+        } else {
+            Backend::software()
+        }
+    }
+
+    /// Select AES-NI if the crate was compiled with it enabled
+    #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), not(feature = "std")))]
+    fn detect() -> Self {
+        if cfg!(target_feature = "aes") {
+            Backend::Ni
+        } else {
+            Backend::software()
+        }
+    }
+
+    /// Select the fastest backend supported by the current CPU
+    #[cfg(all(target_arch = "aarch64", feature = "std"))]
+    fn detect() -> Self {
+        if is_aarch64_feature_detected!("aes") {
+            Backend::Armv8
+        } else {
+            Backend::software()
+        }
+    }
+
+    /// Select the ARMv8 backend if the crate was compiled with it enabled
+    #[cfg(all(target_arch = "aarch64", not(feature = "std")))]
+    fn detect() -> Self {
+        if cfg!(target_feature = "aes") {
+            Backend::Armv8
+        } else {
+            Backend::software()
+        }
+    }
+
+    /// No hardware backends are available on this architecture
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    fn detect() -> Self {
+        Backend::software()
+    }
+
+    /// Software backend selected by the crate features
+    #[cfg(not(feature = "table-software-aes"))]
+    fn software() -> Self {
+        Backend::Bitsliced
+    }
+
+    /// Software backend selected by the crate features
+    #[cfg(feature = "table-software-aes")]
+    fn software() -> Self {
+        Backend::Soft
+    }
+
+    /// Encrypt a block in-place using the given expanded round keys
+    #[inline]
+    fn encrypt(self, round_keys: &[Block], block: &mut Block) {
+        match self {
+            Backend::Soft => soft::encrypt(round_keys, block),
+            Backend::Bitsliced => bitsliced::encrypt(round_keys, block),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Ni => unsafe { ni::encrypt(round_keys, block) },
+            #[cfg(target_arch = "aarch64")]
+            Backend::Armv8 => unsafe { armv8::encrypt(round_keys, block) },
+        }
+    }
+
+    /// Encrypt eight blocks in-place using the given expanded round keys
+    #[inline]
+    fn encrypt8(self, round_keys: &[Block], blocks: &mut Block8) {
+        match self {
+            Backend::Soft => for block in blocks.as_blocks_mut().iter_mut() {
+                soft::encrypt(round_keys, block);
+            },
+            Backend::Bitsliced => bitsliced::encrypt8(round_keys, blocks),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Ni => unsafe { ni::encrypt8(round_keys, blocks) },
+            #[cfg(target_arch = "aarch64")]
+            Backend::Armv8 => unsafe { armv8::encrypt8(round_keys, blocks) },
+        }
+    }
+}
+
+/// AES with a 128-bit key
+#[derive(Clone)]
+pub struct Aes128 {
+    round_keys: [Block; AES128_ROUND_KEYS],
+    backend: Backend,
+}
+
+impl Aes128 {
+    /// Create a new AES-128 cipher instance from the given key
+    #[inline]
+    pub fn new(key: &[u8; 16]) -> Self {
+        Self::with_backend(key, Backend::select())
+    }
+
+    /// Create a new AES-128 cipher instance which always uses the portable
+    /// software implementation, e.g. to compare it against the AES instructions
+    #[inline]
+    pub fn new_software(key: &[u8; 16]) -> Self {
+        Self::with_backend(key, Backend::software())
+    }
+
+    /// Create a new AES-128 cipher instance using a specific backend
+    fn with_backend(key: &[u8; 16], backend: Backend) -> Self {
+        let mut round_keys: [Block; AES128_ROUND_KEYS] = Default::default();
+
+        match backend {
+            Backend::Soft => soft::expand_key(key, &mut round_keys),
+            Backend::Bitsliced => bitsliced::expand_key(key, &mut round_keys),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Ni => unsafe { ni::expand_key128(key, &mut round_keys) },
+            #[cfg(target_arch = "aarch64")]
+            Backend::Armv8 => unsafe { armv8::expand_key(key, &mut round_keys) },
+        }
+
+        Self {
+            round_keys: round_keys,
+            backend: backend,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Aes128 {
+    /// Expanded round keys, so tests can check they've been wiped
+    pub fn round_keys(&self) -> &[Block] {
+        &self.round_keys
+    }
+}
+
+impl Zeroize for Aes128 {
+    /// Wipe the expanded AES-128 key schedule
+    fn zeroize(&mut self) {
+        for round_key in self.round_keys.iter_mut() {
+            round_key.zeroize();
+        }
+    }
+}
+
+impl fmt::Debug for Aes128 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Aes128(...redacted...)")
+    }
+}
+
+impl Drop for Aes128 {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
+impl ZeroizeOnDrop for Aes128 {}
+
+impl BlockCipher for Aes128 {
+    const KEY_SIZE: usize = 16;
+
+    /// Encrypt an AES block in-place
+    #[inline]
+    fn encrypt(&self, block: &mut Block) {
+        self.backend.encrypt(&self.round_keys, block)
+    }
+
+    /// Encrypt 8 AES blocks in-place in parallel
+    #[inline]
+    fn encrypt8(&self, blocks: &mut Block8) {
+        self.backend.encrypt8(&self.round_keys, blocks)
+    }
+}
+
+/// AES with a 192-bit key
+#[derive(Clone)]
+pub struct Aes192 {
+    round_keys: [Block; AES192_ROUND_KEYS],
+    backend: Backend,
+}
+
+impl Aes192 {
+    /// Create a new AES-192 cipher instance from the given key
+    #[inline]
+    pub fn new(key: &[u8; 24]) -> Self {
+        Self::with_backend(key, Backend::select())
+    }
+
+    /// Create a new AES-192 cipher instance which always uses the portable
+    /// software implementation, e.g. to compare it against the AES instructions
+    #[inline]
+    pub fn new_software(key: &[u8; 24]) -> Self {
+        Self::with_backend(key, Backend::software())
+    }
+
+    /// Create a new AES-192 cipher instance using a specific backend
+    fn with_backend(key: &[u8; 24], backend: Backend) -> Self {
+        let mut round_keys: [Block; AES192_ROUND_KEYS] = Default::default();
+
+        match backend {
+            Backend::Soft => soft::expand_key(key, &mut round_keys),
+            Backend::Bitsliced => bitsliced::expand_key(key, &mut round_keys),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Ni => unsafe { ni::expand_key192(key, &mut round_keys) },
+            #[cfg(target_arch = "aarch64")]
+            Backend::Armv8 => unsafe { armv8::expand_key(key, &mut round_keys) },
+        }
+
+        Self {
+            round_keys: round_keys,
+            backend: backend,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Aes192 {
+    /// Expanded round keys, so tests can check they've been wiped
+    pub fn round_keys(&self) -> &[Block] {
+        &self.round_keys
+    }
+}
+
+impl Zeroize for Aes192 {
+    /// Wipe the expanded AES-192 key schedule
+    fn zeroize(&mut self) {
+        for round_key in self.round_keys.iter_mut() {
+            round_key.zeroize();
+        }
+    }
+}
+
+impl fmt::Debug for Aes192 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Aes192(...redacted...)")
+    }
+}
+
+impl Drop for Aes192 {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
+impl ZeroizeOnDrop for Aes192 {}
+
+impl BlockCipher for Aes192 {
+    const KEY_SIZE: usize = 24;
+
+    /// Encrypt an AES block in-place
+    #[inline]
+    fn encrypt(&self, block: &mut Block) {
+        self.backend.encrypt(&self.round_keys, block)
+    }
+
+    /// Encrypt 8 AES blocks in-place in parallel
+    #[inline]
+    fn encrypt8(&self, blocks: &mut Block8) {
+        self.backend.encrypt8(&self.round_keys, blocks)
+    }
+}
+
+/// AES with a 256-bit key
+#[derive(Clone)]
+pub struct Aes256 {
+    round_keys: [Block; AES256_ROUND_KEYS],
+    backend: Backend,
+}
+
+impl Aes256 {
+    /// Create a new AES-256 cipher instance from the given key
+    #[inline]
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self::with_backend(key, Backend::select())
+    }
+
+    /// Create a new AES-256 cipher instance which always uses the portable
+    /// software implementation, e.g. to compare it against the AES instructions
+    #[inline]
+    pub fn new_software(key: &[u8; 32]) -> Self {
+        Self::with_backend(key, Backend::software())
+    }
+
+    /// Create a new AES-256 cipher instance using a specific backend
+    fn with_backend(key: &[u8; 32], backend: Backend) -> Self {
+        let mut round_keys: [Block; AES256_ROUND_KEYS] = Default::default();
+
+        match backend {
+            Backend::Soft => soft::expand_key(key, &mut round_keys),
+            Backend::Bitsliced => bitsliced::expand_key(key, &mut round_keys),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Ni => unsafe { ni::expand_key256(key, &mut round_keys) },
+            #[cfg(target_arch = "aarch64")]
+            Backend::Armv8 => unsafe { armv8::expand_key(key, &mut round_keys) },
+        }
+
+        Self {
+            round_keys: round_keys,
+            backend: backend,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Aes256 {
+    /// Expanded round keys, so tests can check they've been wiped
+    pub fn round_keys(&self) -> &[Block] {
+        &self.round_keys
+    }
+}
+
+impl Zeroize for Aes256 {
+    /// Wipe the expanded AES-256 key schedule
+    fn zeroize(&mut self) {
+        for round_key in self.round_keys.iter_mut() {
+            round_key.zeroize();
+        }
+    }
+}
+
+impl fmt::Debug for Aes256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Aes256(...redacted...)")
+    }
+}
+
+impl Drop for Aes256 {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
+impl ZeroizeOnDrop for Aes256 {}
+
+impl BlockCipher for Aes256 {
+    const KEY_SIZE: usize = 32;
+
+    /// Encrypt an AES block in-place
+    #[inline]
+    fn encrypt(&self, block: &mut Block) {
+        self.backend.encrypt(&self.round_keys, block)
+    }
+
+    /// Encrypt 8 AES blocks in-place in parallel
+    #[inline]
+    fn encrypt8(&self, blocks: &mut Block8) {
+        self.backend.encrypt8(&self.round_keys, blocks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Aes128, Aes192, Aes256, Backend};
+    use core::{mem, ptr};
+    use internals::{Block, Block8, BlockCipher, BLOCK8_SIZE, BLOCK_SIZE};
+    use internals::test_rng::XorShift64;
+    use zeroize::Zeroize;
+
+    /// Call the given function with every backend supported by the current CPU
+    fn each_backend<F: FnMut(Backend)>(mut f: F) {
+        f(Backend::Soft);
+
+        f(Backend::Bitsliced);
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("aes") {
+                f(Backend::Ni);
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            if is_aarch64_feature_detected!("aes") {
+                f(Backend::Armv8);
+            }
+        }
+    }
+
+    /// FIPS-197 Appendix B and C.1 AES-128 examples: key, plaintext, ciphertext
+    static FIPS197_AES128_EXAMPLES: &[([u8; 16], [u8; 16], [u8; 16])] = &[
+        (
+            [
+                0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6,
+                0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c,
+            ],
+            [
+                0x32, 0x43, 0xf6, 0xa8, 0x88, 0x5a, 0x30, 0x8d,
+                0x31, 0x31, 0x98, 0xa2, 0xe0, 0x37, 0x07, 0x34,
+            ],
+            [
+                0x39, 0x25, 0x84, 0x1d, 0x02, 0xdc, 0x09, 0xfb,
+                0xdc, 0x11, 0x85, 0x97, 0x19, 0x6a, 0x0b, 0x32,
+            ],
+        ),
+        (
+            [
+                0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+                0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+            ],
+            [
+                0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77,
+                0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff,
+            ],
+            [
+                0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30,
+                0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4, 0xc5, 0x5a,
+            ],
+        ),
+    ];
+
+    /// FIPS-197 Appendix C.2 AES-192 example: key, plaintext, ciphertext
+    static FIPS197_AES192_EXAMPLES: &[([u8; 24], [u8; 16], [u8; 16])] = &[(
+        [
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+            0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+            0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17,
+        ],
+        [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77,
+            0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff,
+        ],
+        [
+            0xdd, 0xa9, 0x7c, 0xa4, 0x86, 0x4c, 0xdf, 0xe0,
+            0x6e, 0xaf, 0x70, 0xa0, 0xec, 0x0d, 0x71, 0x91,
+        ],
+    )];
+
+    /// FIPS-197 Appendix C.3 AES-256 example: key, plaintext, ciphertext
+    static FIPS197_AES256_EXAMPLES: &[([u8; 32], [u8; 16], [u8; 16])] = &[(
+        [
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+            0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+            0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17,
+            0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f,
+        ],
+        [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77,
+            0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff,
+        ],
+        [
+            0x8e, 0xa2, 0xb7, 0xca, 0x51, 0x67, 0x45, 0xbf,
+            0xea, 0xfc, 0x49, 0x90, 0x4b, 0x49, 0x60, 0x89,
+        ],
+    )];
+
+    /// Check the batched encryption path matches eight sequential encryptions
+    fn check_encrypt8<C: BlockCipher>(cipher: &C) {
+        let mut blocks = Block8::new();
+        for (i, block) in blocks.as_blocks_mut().iter_mut().enumerate() {
+            *block = Block::from([i as u8; BLOCK_SIZE]);
+        }
+
+        let mut expected = blocks.clone();
+        for block in expected.as_blocks_mut().iter_mut() {
+            cipher.encrypt(block);
+        }
+
+        cipher.encrypt8(&mut blocks);
+
+        let actual: &[u8; BLOCK8_SIZE] = blocks.as_ref();
+        let expected: &[u8; BLOCK8_SIZE] = expected.as_ref();
+        assert_eq!(&actual[..], &expected[..]);
+    }
+
+    fn check_encrypt_blocks<C: BlockCipher>(cipher: &C) {
+        // Cover both full groups of eight and leftover single blocks
+        for count in 0..20 {
+            let mut blocks: [Block; 20] = Default::default();
+
+            for (i, block) in blocks.iter_mut().enumerate() {
+                *block = Block::from([i as u8; BLOCK_SIZE]);
+            }
+
+            let mut expected = blocks.clone();
+            for block in expected[..count].iter_mut() {
+                cipher.encrypt(block);
+            }
+
+            cipher.encrypt_blocks(&mut blocks[..count]);
+
+            for (actual, expected) in blocks.iter().zip(expected.iter()) {
+                assert_eq!(actual.as_ref(), expected.as_ref(), "{} blocks", count);
+            }
+        }
+    }
+
+    #[test]
+    fn aes128_encrypt_blocks() {
+        check_encrypt_blocks(&Aes128::new(&[0x42u8; 16]));
+    }
+
+    #[test]
+    fn aes192_encrypt_blocks() {
+        check_encrypt_blocks(&Aes192::new(&[0x42u8; 24]));
+    }
+
+    #[test]
+    fn aes256_encrypt_blocks() {
+        check_encrypt_blocks(&Aes256::new(&[0x42u8; 32]));
+    }
+
+    #[test]
+    fn aes128_encrypt8() {
+        check_encrypt8(&Aes128::new(&[0x42u8; 16]));
+    }
+
+    #[test]
+    fn aes192_encrypt8() {
+        check_encrypt8(&Aes192::new(&[0x42u8; 24]));
+    }
+
+    #[test]
+    fn aes256_encrypt8() {
+        check_encrypt8(&Aes256::new(&[0x42u8; 32]));
+    }
+
+    #[test]
+    fn aes128_zeroize_on_drop() {
+        let mut aes = mem::ManuallyDrop::new(Aes128::new(&[0x42u8; 16]));
+
+        unsafe {
+            ptr::drop_in_place(&mut *aes);
+            assert!(key_schedule_is_zero(aes.round_keys()));
+        }
+    }
+
+    #[test]
+    fn aes192_zeroize_on_drop() {
+        let mut aes = mem::ManuallyDrop::new(Aes192::new(&[0x42u8; 24]));
+
+        unsafe {
+            ptr::drop_in_place(&mut *aes);
+            assert!(key_schedule_is_zero(aes.round_keys()));
+        }
+    }
+
+    #[test]
+    fn aes256_zeroize_on_drop() {
+        let mut aes = mem::ManuallyDrop::new(Aes256::new(&[0x42u8; 32]));
+
+        unsafe {
+            ptr::drop_in_place(&mut *aes);
+            assert!(key_schedule_is_zero(aes.round_keys()));
+        }
+    }
+
+    #[test]
+    fn clone_copies_key_schedule() {
+        let aes = Aes128::new(&[0x42u8; 16]);
+        let mut copy = aes.clone();
+        assert_eq!(copy.backend, aes.backend);
+        check_round_keys_match(aes.round_keys(), copy.round_keys());
+
+        // Wiping a clone leaves the original's schedule intact
+        copy.zeroize();
+        assert!(key_schedule_is_zero(copy.round_keys()));
+        assert!(!key_schedule_is_zero(aes.round_keys()));
+    }
+
+    fn key_schedule_is_zero(round_keys: &[Block]) -> bool {
+        round_keys.iter().all(|key| key.as_ref() == &[0u8; BLOCK_SIZE])
+    }
+
+    #[test]
+    fn fips197_examples() {
+        each_backend(|backend| {
+            for &(ref key, plaintext, ciphertext) in FIPS197_AES128_EXAMPLES {
+                let mut block = Block::from(plaintext);
+                Aes128::with_backend(key, backend).encrypt(&mut block);
+                assert_eq!(block.as_ref(), &ciphertext[..], "{:?}", backend);
+            }
+
+            for &(ref key, plaintext, ciphertext) in FIPS197_AES192_EXAMPLES {
+                let mut block = Block::from(plaintext);
+                Aes192::with_backend(key, backend).encrypt(&mut block);
+                assert_eq!(block.as_ref(), &ciphertext[..], "{:?}", backend);
+            }
+
+            for &(ref key, plaintext, ciphertext) in FIPS197_AES256_EXAMPLES {
+                let mut block = Block::from(plaintext);
+                Aes256::with_backend(key, backend).encrypt(&mut block);
+                assert_eq!(block.as_ref(), &ciphertext[..], "{:?}", backend);
+            }
+        });
+    }
+
+    #[test]
+    fn new_selects_backend() {
+        let expected = if cfg!(miscreant_force_soft_aes) {
+            Backend::software()
+        } else {
+            Backend::detect()
+        };
+
+        assert_eq!(Aes128::new(&[0u8; 16]).backend, expected);
+        assert_eq!(Aes192::new(&[0u8; 24]).backend, expected);
+        assert_eq!(Aes256::new(&[0u8; 32]).backend, expected);
+    }
+
+    #[test]
+    fn fips197_key_expansion() {
+        each_backend(|backend| {
+            // FIPS-197 Appendix A.1
+            let aes = Aes128::with_backend(&FIPS197_AES128_EXAMPLES[0].0, backend);
+            assert_eq!(
+                aes.round_keys[10].as_ref(),
+                &[
+                    0xd0, 0x14, 0xf9, 0xa8, 0xc9, 0xee, 0x25, 0x89,
+                    0xe1, 0x3f, 0x0c, 0xc8, 0xb6, 0x63, 0x0c, 0xa6,
+                ][..],
+                "{:?}",
+                backend
+            );
+
+            // FIPS-197 Appendix A.2
+            let aes = Aes192::with_backend(
+                &[
+                    0x8e, 0x73, 0xb0, 0xf7, 0xda, 0x0e, 0x64, 0x52,
+                    0xc8, 0x10, 0xf3, 0x2b, 0x80, 0x90, 0x79, 0xe5,
+                    0x62, 0xf8, 0xea, 0xd2, 0x52, 0x2c, 0x6b, 0x7b,
+                ],
+                backend,
+            );
+            assert_eq!(
+                aes.round_keys[12].as_ref(),
+                &[
+                    0xe9, 0x8b, 0xa0, 0x6f, 0x44, 0x8c, 0x77, 0x3c,
+                    0x8e, 0xcc, 0x72, 0x04, 0x01, 0x00, 0x22, 0x02,
+                ][..],
+                "{:?}",
+                backend
+            );
+
+            // FIPS-197 Appendix A.3
+            let aes = Aes256::with_backend(
+                &[
+                    0x60, 0x3d, 0xeb, 0x10, 0x15, 0xca, 0x71, 0xbe,
+                    0x2b, 0x73, 0xae, 0xf0, 0x85, 0x7d, 0x77, 0x81,
+                    0x1f, 0x35, 0x2c, 0x07, 0x3b, 0x61, 0x08, 0xd7,
+                    0x2d, 0x98, 0x10, 0xa3, 0x09, 0x14, 0xdf, 0xf4,
+                ],
+                backend,
+            );
+            assert_eq!(
+                aes.round_keys[14].as_ref(),
+                &[
+                    0xfe, 0x48, 0x90, 0xd1, 0xe6, 0x18, 0x8d, 0x0b,
+                    0x04, 0x6d, 0xf3, 0x44, 0x70, 0x6c, 0x63, 0x1e,
+                ][..],
+                "{:?}",
+                backend
+            );
+        });
+    }
+
+    #[test]
+    fn backends_match_table_based() {
+        each_backend(|backend| {
+            if backend != Backend::Soft {
+                check_backend_matches_table_based(backend);
+            }
+        });
+    }
+
+    /// Compare a backend's key schedules and output against the table-based
+    /// software implementation using random keys and blocks
+    fn check_backend_matches_table_based(backend: Backend) {
+        let mut rng = XorShift64::new(0xd1b5_4a32_d192_ed03);
+
+        for _ in 0..64 {
+            let mut key = [0u8; 32];
+            let mut blocks = Block8::new();
+            rng.fill(&mut key);
+
+            {
+                let bytes: &mut [u8; BLOCK8_SIZE] = blocks.as_mut();
+                rng.fill(bytes);
+            }
+
+            let expected = Aes128::with_backend(array_ref!(key, 0, 16), Backend::Soft);
+            let actual = Aes128::with_backend(array_ref!(key, 0, 16), backend);
+            check_ciphers_match(&expected, &actual, &blocks);
+            check_round_keys_match(&expected.round_keys, &actual.round_keys);
+
+            let expected = Aes192::with_backend(array_ref!(key, 0, 24), Backend::Soft);
+            let actual = Aes192::with_backend(array_ref!(key, 0, 24), backend);
+            check_ciphers_match(&expected, &actual, &blocks);
+            check_round_keys_match(&expected.round_keys, &actual.round_keys);
+
+            let expected = Aes256::with_backend(&key, Backend::Soft);
+            let actual = Aes256::with_backend(&key, backend);
+            check_ciphers_match(&expected, &actual, &blocks);
+            check_round_keys_match(&expected.round_keys, &actual.round_keys);
+        }
+    }
+
+    /// Check two ciphers produce identical output for single and batched blocks
+    fn check_ciphers_match<C: BlockCipher>(expected: &C, actual: &C, blocks: &Block8) {
+        for block in blocks.as_blocks().iter() {
+            let mut expected_block = block.clone();
+            let mut actual_block = block.clone();
+            expected.encrypt(&mut expected_block);
+            actual.encrypt(&mut actual_block);
+            assert_eq!(actual_block.as_ref(), expected_block.as_ref());
+        }
+
+        let mut expected_blocks = blocks.clone();
+        let mut actual_blocks = blocks.clone();
+        expected.encrypt8(&mut expected_blocks);
+        actual.encrypt8(&mut actual_blocks);
+
+        let expected_bytes: &[u8; BLOCK8_SIZE] = expected_blocks.as_ref();
+        let actual_bytes: &[u8; BLOCK8_SIZE] = actual_blocks.as_ref();
+        assert_eq!(&actual_bytes[..], &expected_bytes[..]);
+    }
+
+    /// Check two key schedules are identical. AES-192 is worth checking in
+    /// particular, as its round keys straddle consecutive expansion steps.
+    fn check_round_keys_match(expected: &[Block], actual: &[Block]) {
+        for (expected_key, actual_key) in expected.iter().zip(actual.iter()) {
+            assert_eq!(actual_key.as_ref(), expected_key.as_ref());
+        }
+    }
+}
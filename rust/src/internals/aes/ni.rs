@@ -0,0 +1,186 @@
+//! `internals/aes/ni.rs`: AES implementation using Intel AES-NI instructions
+//!
+//! All functions here are `unsafe` as they must only be called on CPUs which
+//! support AES-NI. `Backend::detect()` is responsible for checking that.
+
+use internals::{Block, Block8, BLOCK8_COUNT};
+
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+/// Expand a 128-bit key into 11 round keys
+#[target_feature(enable = "aes")]
+pub unsafe fn expand_key128(key: &[u8; 16], round_keys: &mut [Block; 11]) {
+    let mut k = _mm_loadu_si128(key.as_ptr() as *const __m128i);
+    store(&mut round_keys[0], k);
+
+    macro_rules! expand_round {
+        ($round:expr, $rcon:expr) => {
+            k = expand_step(k, _mm_shuffle_epi32(_mm_aeskeygenassist_si128(k, $rcon), 0xff));
+            store(&mut round_keys[$round], k);
+        };
+    }
+
+    expand_round!(1, 0x01);
+    expand_round!(2, 0x02);
+    expand_round!(3, 0x04);
+    expand_round!(4, 0x08);
+    expand_round!(5, 0x10);
+    expand_round!(6, 0x20);
+    expand_round!(7, 0x40);
+    expand_round!(8, 0x80);
+    expand_round!(9, 0x1b);
+    expand_round!(10, 0x36);
+}
+
+/// Expand a 192-bit key into 13 round keys
+///
+/// Each expansion step produces six words, i.e. one and a half round keys, so
+/// round keys alternately straddle the output of two consecutive steps.
+#[target_feature(enable = "aes")]
+pub unsafe fn expand_key192(key: &[u8; 24], round_keys: &mut [Block; 13]) {
+    // k1 holds words 0-3 of the current step's output and k2 words 4-5
+    let mut k1 = _mm_loadu_si128(key.as_ptr() as *const __m128i);
+    let mut k2 = _mm_loadl_epi64(key[16..].as_ptr() as *const __m128i);
+    store(&mut round_keys[0], k1);
+
+    macro_rules! expand_step192 {
+        ($rcon:expr) => {
+            k1 = expand_step(k1, _mm_shuffle_epi32(_mm_aeskeygenassist_si128(k2, $rcon), 0x55));
+
+            let broadcast = _mm_shuffle_epi32(k1, 0xff);
+            k2 = _mm_xor_si128(_mm_xor_si128(k2, _mm_slli_si128(k2, 4)), broadcast);
+        };
+    }
+
+    macro_rules! expand_round_pair {
+        ($round:expr, $rcon1:expr, $rcon2:expr) => {
+            let prev = k2;
+            expand_step192!($rcon1);
+            store(&mut round_keys[$round], unpack_lo(prev, k1));
+            store(&mut round_keys[$round + 1], unpack_hi_lo(k1, k2));
+
+            expand_step192!($rcon2);
+            store(&mut round_keys[$round + 2], k1);
+        };
+    }
+
+    expand_round_pair!(1, 0x01, 0x02);
+    expand_round_pair!(4, 0x04, 0x08);
+    expand_round_pair!(7, 0x10, 0x20);
+
+    let prev = k2;
+    expand_step192!(0x40);
+    store(&mut round_keys[10], unpack_lo(prev, k1));
+    store(&mut round_keys[11], unpack_hi_lo(k1, k2));
+
+    // Only the first four words of the final step are needed
+    k1 = expand_step(k1, _mm_shuffle_epi32(_mm_aeskeygenassist_si128(k2, 0x80), 0x55));
+    store(&mut round_keys[12], k1);
+}
+
+/// Combine the low 64 bits of `a` with the low 64 bits of `b`
+#[inline(always)]
+unsafe fn unpack_lo(a: __m128i, b: __m128i) -> __m128i {
+    _mm_unpacklo_epi64(a, b)
+}
+
+/// Combine the high 64 bits of `a` with the low 64 bits of `b`
+#[inline(always)]
+unsafe fn unpack_hi_lo(a: __m128i, b: __m128i) -> __m128i {
+    _mm_unpacklo_epi64(_mm_unpackhi_epi64(a, a), b)
+}
+
+/// Expand a 256-bit key into 15 round keys
+#[target_feature(enable = "aes")]
+pub unsafe fn expand_key256(key: &[u8; 32], round_keys: &mut [Block; 15]) {
+    let mut k1 = _mm_loadu_si128(key.as_ptr() as *const __m128i);
+    let mut k2 = _mm_loadu_si128(key[16..].as_ptr() as *const __m128i);
+    store(&mut round_keys[0], k1);
+    store(&mut round_keys[1], k2);
+
+    macro_rules! expand_round {
+        ($round:expr, $rcon:expr) => {
+            k1 = expand_step(k1, _mm_shuffle_epi32(_mm_aeskeygenassist_si128(k2, $rcon), 0xff));
+            store(&mut round_keys[$round], k1);
+
+            // Odd round keys use SubWord without RotWord or the round constant
+            k2 = expand_step(k2, _mm_shuffle_epi32(_mm_aeskeygenassist_si128(k1, 0x00), 0xaa));
+            store(&mut round_keys[$round + 1], k2);
+        };
+    }
+
+    expand_round!(2, 0x01);
+    expand_round!(4, 0x02);
+    expand_round!(6, 0x04);
+    expand_round!(8, 0x08);
+    expand_round!(10, 0x10);
+    expand_round!(12, 0x20);
+
+    k1 = expand_step(k1, _mm_shuffle_epi32(_mm_aeskeygenassist_si128(k2, 0x40), 0xff));
+    store(&mut round_keys[14], k1);
+}
+
+/// XOR the running sums of the previous round key's words with the
+/// broadcast output of `aeskeygenassist`
+#[inline(always)]
+unsafe fn expand_step(key: __m128i, assist: __m128i) -> __m128i {
+    let mut key = key;
+    key = _mm_xor_si128(key, _mm_slli_si128(key, 4));
+    key = _mm_xor_si128(key, _mm_slli_si128(key, 4));
+    key = _mm_xor_si128(key, _mm_slli_si128(key, 4));
+    _mm_xor_si128(key, assist)
+}
+
+/// Encrypt a block in-place using the given expanded round keys
+#[target_feature(enable = "aes")]
+pub unsafe fn encrypt(round_keys: &[Block], block: &mut Block) {
+    let rounds = round_keys.len() - 1;
+    let mut state = _mm_xor_si128(load(block), load(&round_keys[0]));
+
+    for round_key in &round_keys[1..rounds] {
+        state = _mm_aesenc_si128(state, load(round_key));
+    }
+
+    store(block, _mm_aesenclast_si128(state, load(&round_keys[rounds])));
+}
+
+/// Encrypt eight blocks in-place, interleaving them so the AES units can
+/// pipeline the rounds of independent blocks
+#[target_feature(enable = "aes")]
+pub unsafe fn encrypt8(round_keys: &[Block], blocks: &mut Block8) {
+    let rounds = round_keys.len() - 1;
+    let mut state = [_mm_setzero_si128(); BLOCK8_COUNT];
+
+    let round_key = load(&round_keys[0]);
+    for (s, block) in state.iter_mut().zip(blocks.as_blocks().iter()) {
+        *s = _mm_xor_si128(load(block), round_key);
+    }
+
+    for round_key in &round_keys[1..rounds] {
+        let round_key = load(round_key);
+        for s in state.iter_mut() {
+            *s = _mm_aesenc_si128(*s, round_key);
+        }
+    }
+
+    let round_key = load(&round_keys[rounds]);
+    for (s, block) in state.iter().zip(blocks.as_blocks_mut().iter_mut()) {
+        store(block, _mm_aesenclast_si128(*s, round_key));
+    }
+}
+
+/// Load an aligned block into a SIMD register
+#[inline(always)]
+unsafe fn load(block: &Block) -> __m128i {
+    _mm_load_si128(block.as_ref().as_ptr() as *const __m128i)
+}
+
+/// Store a SIMD register into an aligned block
+#[inline(always)]
+unsafe fn store(block: &mut Block, value: __m128i) {
+    let bytes: &mut [u8] = block.as_mut();
+    _mm_store_si128(bytes.as_mut_ptr() as *mut __m128i, value)
+}
@@ -0,0 +1,222 @@
+//! `internals/aes/soft.rs`: Portable software implementation of AES
+//!
+//! This is a straightforward byte-oriented implementation of FIPS-197 used on
+//! CPUs without AES instructions. It looks up the S-box by secret-dependent
+//! indices and is therefore not protected against cache-timing attacks.
+
+use super::MAX_ROUND_KEYS;
+use internals::{Block, BLOCK_SIZE};
+use zeroize::Zeroize;
+
+/// Size of an AES word (i.e. a column of the state) in bytes
+const WORD_SIZE: usize = 4;
+
+/// The AES S-box (FIPS-197 Figure 7)
+pub static SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+/// Expand a 128, 192, or 256-bit key into the given round keys (FIPS-197 5.2)
+pub fn expand_key(key: &[u8], round_keys: &mut [Block]) {
+    expand_key_with(key, round_keys, sub_word);
+}
+
+/// Expand a key into the given round keys using the given SubWord function,
+/// allowing other software implementations to share the key schedule logic
+pub fn expand_key_with(key: &[u8], round_keys: &mut [Block], sub_word: fn(&mut [u8; WORD_SIZE])) {
+    let nk = key.len() / WORD_SIZE;
+    let total_words = round_keys.len() * (BLOCK_SIZE / WORD_SIZE);
+    debug_assert!(nk == 4 || nk == 6 || nk == 8, "invalid AES key size");
+    debug_assert!(round_keys.len() == nk + 7, "wrong number of round keys");
+
+    let mut schedule = [0u8; MAX_ROUND_KEYS * BLOCK_SIZE];
+    schedule[..key.len()].copy_from_slice(key);
+
+    let mut rcon = 1u8;
+
+    for i in nk..total_words {
+        let mut temp = *array_ref!(schedule, (i - 1) * WORD_SIZE, WORD_SIZE);
+
+        if i % nk == 0 {
+            // RotWord, SubWord, and XOR with the round constant
+            temp = [temp[1], temp[2], temp[3], temp[0]];
+            sub_word(&mut temp);
+            temp[0] ^= rcon;
+            rcon = xtime(rcon);
+        } else if nk > 6 && i % nk == 4 {
+            sub_word(&mut temp);
+        }
+
+        for j in 0..WORD_SIZE {
+            schedule[i * WORD_SIZE + j] = schedule[(i - nk) * WORD_SIZE + j] ^ temp[j];
+        }
+    }
+
+    for (i, round_key) in round_keys.iter_mut().enumerate() {
+        *round_key = Block::from(*array_ref!(schedule, i * BLOCK_SIZE, BLOCK_SIZE));
+    }
+
+    schedule.as_mut().zeroize();
+}
+
+/// Encrypt a block in-place using the given expanded round keys
+pub fn encrypt(round_keys: &[Block], block: &mut Block) {
+    let rounds = round_keys.len() - 1;
+
+    block.xor_block(&round_keys[0]);
+
+    for round_key in &round_keys[1..rounds] {
+        {
+            let state: &mut [u8; BLOCK_SIZE] = block.as_mut();
+            sub_bytes(state);
+            shift_rows(state);
+            mix_columns(state);
+        }
+        block.xor_block(round_key);
+    }
+
+    {
+        let state: &mut [u8; BLOCK_SIZE] = block.as_mut();
+        sub_bytes(state);
+        shift_rows(state);
+    }
+    block.xor_block(&round_keys[rounds]);
+}
+
+/// Substitute every byte of a key schedule word using the S-box
+#[inline]
+fn sub_word(word: &mut [u8; WORD_SIZE]) {
+    for byte in word.iter_mut() {
+        *byte = SBOX[*byte as usize];
+    }
+}
+
+/// Substitute every byte of the state using the S-box
+#[inline]
+fn sub_bytes(state: &mut [u8; BLOCK_SIZE]) {
+    for byte in state.iter_mut() {
+        *byte = SBOX[*byte as usize];
+    }
+}
+
+/// Cyclically shift row `r` of the (column-major) state left by `r` bytes
+#[inline]
+fn shift_rows(state: &mut [u8; BLOCK_SIZE]) {
+    let input = *state;
+
+    for col in 0..4 {
+        for row in 0..4 {
+            state[col * 4 + row] = input[((col + row) % 4) * 4 + row];
+        }
+    }
+}
+
+/// Multiply each column of the state by the fixed MixColumns polynomial
+#[inline]
+fn mix_columns(state: &mut [u8; BLOCK_SIZE]) {
+    for column in state.chunks_mut(4) {
+        let a = [column[0], column[1], column[2], column[3]];
+        let all = a[0] ^ a[1] ^ a[2] ^ a[3];
+
+        column[0] ^= all ^ xtime(a[0] ^ a[1]);
+        column[1] ^= all ^ xtime(a[1] ^ a[2]);
+        column[2] ^= all ^ xtime(a[2] ^ a[3]);
+        column[3] ^= all ^ xtime(a[3] ^ a[0]);
+    }
+}
+
+/// Multiply by x in GF(2^8) without data-dependent branches
+#[inline]
+fn xtime(x: u8) -> u8 {
+    (x << 1) ^ (0u8.wrapping_sub(x >> 7) & 0x1b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand_key, mix_columns, shift_rows, xtime};
+    use internals::Block;
+
+    #[test]
+    fn xtime_examples() {
+        // FIPS-197 4.2.1
+        assert_eq!(xtime(0x57), 0xae);
+        assert_eq!(xtime(0xae), 0x47);
+        assert_eq!(xtime(0x47), 0x8e);
+        assert_eq!(xtime(0x8e), 0x07);
+    }
+
+    #[test]
+    fn shift_rows_example() {
+        let mut state = [0u8; 16];
+        for (i, byte) in state.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+
+        shift_rows(&mut state);
+        assert_eq!(
+            state,
+            [0, 5, 10, 15, 4, 9, 14, 3, 8, 13, 2, 7, 12, 1, 6, 11]
+        );
+    }
+
+    #[test]
+    fn mix_columns_example() {
+        // FIPS-197 Appendix B, round 1
+        let mut state = [
+            0xd4, 0xbf, 0x5d, 0x30, 0xe0, 0xb4, 0x52, 0xae,
+            0xb8, 0x41, 0x11, 0xf1, 0x1e, 0x27, 0x98, 0xe5,
+        ];
+
+        mix_columns(&mut state);
+        assert_eq!(
+            state,
+            [
+                0x04, 0x66, 0x81, 0xe5, 0xe0, 0xcb, 0x19, 0x9a,
+                0x48, 0xf8, 0xd3, 0x7a, 0x28, 0x06, 0x26, 0x4c,
+            ]
+        );
+    }
+
+    #[test]
+    fn aes192_key_expansion_example() {
+        // FIPS-197 Appendix A.2: 13 round keys for the 12 rounds of AES-192
+        let key = [
+            0x8e, 0x73, 0xb0, 0xf7, 0xda, 0x0e, 0x64, 0x52, 0xc8, 0x10, 0xf3, 0x2b,
+            0x80, 0x90, 0x79, 0xe5, 0x62, 0xf8, 0xea, 0xd2, 0x52, 0x2c, 0x6b, 0x7b,
+        ];
+
+        let mut round_keys: [Block; 13] = Default::default();
+        expand_key(&key, &mut round_keys);
+
+        assert_eq!(round_keys[0].as_ref(), &key[..16]);
+        assert_eq!(
+            round_keys[1].as_ref(),
+            &[
+                0x62, 0xf8, 0xea, 0xd2, 0x52, 0x2c, 0x6b, 0x7b,
+                0xfe, 0x0c, 0x91, 0xf7, 0x24, 0x02, 0xf5, 0xa5,
+            ][..]
+        );
+        assert_eq!(
+            round_keys[12].as_ref(),
+            &[
+                0xe9, 0x8b, 0xa0, 0x6f, 0x44, 0x8c, 0x77, 0x3c,
+                0x8e, 0xcc, 0x72, 0x04, 0x01, 0x00, 0x22, 0x02,
+            ][..]
+        );
+    }
+}
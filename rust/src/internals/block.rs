@@ -2,48 +2,204 @@
 //!
 //! Special-cased for AES's 128-bit block size
 
-use super::xor;
-use core::{intrinsics, mem, ptr};
-use subtle::{self, CTEq, Mask};
+use core::{mem, ptr, str};
+use core::convert::TryFrom;
+use core::fmt;
+use error::Error;
+use super::hex;
+use zeroize::Zeroize;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
 
 /// All constructions are presently specialized to a 128-bit block size (i.e. the AES block size)
 pub const SIZE: usize = 16;
 
+/// Number of blocks in a `Block8`
+pub const BLOCK8_COUNT: usize = 8;
+
+/// Size of a `Block8` in bytes
+pub const BLOCK8_SIZE: usize = SIZE * BLOCK8_COUNT;
+
+/// Length of a block encoded as hexadecimal
+pub const HEX_SIZE: usize = SIZE * 2;
+
+/// Error converting a slice into a block
+///
+/// Carries the length of the offending slice so callers can log it
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct BlockError {
+    len: usize,
+}
+
+impl BlockError {
+    /// Length of the slice which couldn't be converted into a block
+    #[inline]
+    pub fn slice_len(&self) -> usize {
+        self.len
+    }
+}
+
+impl fmt::Display for BlockError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "slice is the wrong size for a block (size: {})", self.len)
+    }
+}
+
+impl From<BlockError> for Error {
+    #[inline]
+    fn from(err: BlockError) -> Error {
+        Error::BlockSize { len: err.len }
+    }
+}
+
 /// A block acceptable to pass to a block cipher (i.e. memory aligned)
-#[derive(Clone, Default)]
+#[derive(Clone)]
 #[repr(align(16))]
 pub struct Block([u8; SIZE]);
 
 impl Block {
     /// Create a new block, initialized to zero
-    pub fn new() -> Block {
+    #[inline]
+    pub const fn new() -> Block {
         Block([0u8; SIZE])
     }
 
-    /// XOR the other block into this one
+    /// Create a new block from the given bytes. Usable in `const`/`static`
+    /// items, e.g. for test vectors
+    #[inline]
+    pub const fn from_bytes(bytes: [u8; SIZE]) -> Block {
+        Block(bytes)
+    }
+
+    /// Create a new block from a slice which must be exactly `SIZE` bytes
+    ///
+    /// Returns a `BlockError` if the slice is any other length
+    #[inline]
+    pub fn try_from_slice(buf: &[u8]) -> Result<Block, BlockError> {
+        if buf.len() != SIZE {
+            return Err(BlockError { len: buf.len() });
+        }
+
+        Ok(Block(*array_ref!(buf, 0, SIZE)))
+    }
+
+    /// Create a new block from a slice of up to `SIZE` bytes, filling any
+    /// remaining space at the end of the block with zeroes
+    ///
+    /// Returns a `BlockError` if the slice is larger than `SIZE`
+    #[inline]
+    pub fn from_slice_zero_padded(buf: &[u8]) -> Result<Block, BlockError> {
+        let len = buf.len();
+
+        if len > SIZE {
+            return Err(BlockError { len: len });
+        }
+
+        let mut block = Block::new();
+        block.0[..len].copy_from_slice(buf);
+        Ok(block)
+    }
+
+    /// Create a new block from a slice of less than `SIZE` bytes, applying
+    /// 10* bit padding: a single 1 bit (i.e. `0x80`) followed by zeroes
+    ///
+    /// Returns a `BlockError` if the slice is `SIZE` bytes or larger
+    #[inline]
+    pub fn from_slice_with_bit_padding(buf: &[u8]) -> Result<Block, BlockError> {
+        let len = buf.len();
+
+        if len >= SIZE {
+            return Err(BlockError { len: len });
+        }
+
+        let mut block = Block::new();
+        block.0[..len].copy_from_slice(buf);
+        block.0[len] = 0x80;
+        Ok(block)
+    }
+
+    /// Decode a block from a hexadecimal string (in either case)
+    ///
+    /// Runs in constant time with respect to the contents of the string, so
+    /// it's safe to use on key material. Returns `Error::HexLength` if the
+    /// string isn't `HEX_SIZE` characters, or `Error::HexCharacter` if it
+    /// contains anything other than hex digits.
+    pub fn from_hex(hex: &str) -> Result<Block, Error> {
+        let mut block = Block::new();
+        hex::decode(hex.as_bytes(), &mut block.0)?;
+        Ok(block)
+    }
+
+    /// Encode this block as lowercase hexadecimal into the given buffer,
+    /// returning it as a string. Runs in constant time
+    pub fn to_hex<'a>(&self, out: &'a mut [u8; HEX_SIZE]) -> &'a str {
+        hex::encode(&self.0, out);
+        str::from_utf8(out).expect("hex is valid UTF-8")
+    }
+
+    /// XOR a block-sized slice into this one
+    ///
+    /// Panics if `other` isn't exactly `SIZE` bytes. Prefer `xor_block` or
+    /// `xor_array` when the size is already known.
     #[inline]
     pub fn xor_in_place<T>(&mut self, other: T)
     where
         T: AsRef<[u8]>,
     {
-        // TODO: find a way to eliminate this assertion with type safety
         assert_eq!(
             other.as_ref().len(),
             SIZE,
             "xor_in_place works on block-sized slices"
         );
 
-        let other_ref: &[u8; SIZE] = array_ref!(other.as_ref(), 0, SIZE);
+        self.xor_array(array_ref!(other.as_ref(), 0, SIZE));
+    }
+
+    /// XOR the other block into this one
+    #[inline]
+    pub fn xor_block(&mut self, other: &Block) {
+        // Both blocks are aligned, so operate on them directly as u128s
+        let x: &mut u128 = unsafe { mem::transmute(&mut self.0) };
+        let y: &u128 = unsafe { mem::transmute(&other.0) };
+
+        *x ^= *y;
+    }
 
-        if (other_ref.as_ptr() as usize) % SIZE == 0 {
-            // If the other value is aligned we can XOR directly
-            let x: &mut u128 = unsafe { mem::transmute(&mut self.0) };
-            let y: &u128 = unsafe { mem::transmute(other_ref) };
+    /// XOR a block-sized array into this block
+    #[inline]
+    pub fn xor_array(&mut self, other: &[u8; SIZE]) {
+        // The other value may not be aligned, so read it as an unaligned u128
+        // and XOR it into our own (always aligned) storage
+        let x: &mut u128 = unsafe { mem::transmute(&mut self.0) };
+        let y: u128 = unsafe { ptr::read_unaligned(other.as_ptr() as *const u128) };
 
-            *x ^= *y;
-        } else {
-            // Fall back on a slower method if unaligned
-            xor::in_place(&mut self.0, other.as_ref());
+        *x ^= y;
+    }
+
+    /// XOR the other block with this one, returning the result as a new block
+    /// and leaving this block unmodified
+    #[inline]
+    pub fn xor<T>(&self, other: T) -> Block
+    where
+        T: AsRef<[u8]>,
+    {
+        let mut result = self.clone();
+        result.xor_in_place(other);
+        result
+    }
+
+    /// Select `a` if `choice` is 0, or `b` if `choice` is 1, in constant time
+    #[inline]
+    pub fn conditional_select(a: &Block, b: &Block, choice: Choice) -> Block {
+        let mut result = a.clone();
+        result.conditional_assign(b, choice);
+        result
+    }
+
+    /// Overwrite this block with `other` if `choice` is 1, in constant time
+    #[inline]
+    pub fn conditional_assign(&mut self, other: &Block, choice: Choice) {
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            a.conditional_assign(b, choice);
         }
     }
 
@@ -53,53 +209,102 @@ impl Block {
     #[inline]
     pub fn copy_from_block(&mut self, other: &Block) {
         assert_ne!(self.0.as_ptr(), other.0.as_ptr(), "can't copy self");
-        unsafe { ptr::copy_nonoverlapping(&other.0, &mut self.0, SIZE) }
+        unsafe { ptr::copy_nonoverlapping(other.0.as_ptr(), self.0.as_mut_ptr(), SIZE) }
     }
 
     /// Zero out the contents of the block
+    ///
+    /// This is implemented with `zeroize`, which uses volatile writes followed
+    /// by a compiler fence, so the compiler can't remove it as a dead store
+    /// even when the block is about to be dropped. All internal wiping (e.g.
+    /// resetting MACs, `Drop` impls) goes through it.
     #[inline]
     pub fn clear(&mut self) {
-        unsafe {
-            // TODO: use a crate that provides this (e.g. clear_on_drop) instead of intrinsics
-            intrinsics::volatile_set_memory(self.0.as_mut_ptr(), 0, SIZE)
-        }
+        self.zeroize()
     }
 
     /// Performs a doubling operation as defined in the CMAC and SIV papers
+    ///
+    /// On x86_64 this uses SSE2, which is part of the baseline instruction set
+    /// for that architecture and therefore needs no runtime detection.
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
     #[inline]
     pub fn dbl(&mut self) {
-        // Use a verified constant time assembly implementation from dbl.asm
-        // which was generated from the reference implementation in dbl.rs
-        unsafe {
-            asm!(include_str!("dbl.asm")
-            :
-            : "rdi"(&self.0)
-            : "rax", "rcx", "rdx", "eax", "memory"
-            : "intel"
-            )
-        }
+        unsafe { sse2::dbl(&mut self.0) }
+    }
+
+    /// Performs a doubling operation as defined in the CMAC and SIV papers
+    #[cfg(not(all(target_arch = "x86_64", target_feature = "sse2")))]
+    #[inline]
+    pub fn dbl(&mut self) {
+        self.dbl_scalar()
+    }
+
+    /// Portable doubling implementation.
+    ///
+    /// The reduction is applied using a mask computed from the high bit of the
+    /// block (via an arithmetic shift) so there are no data-dependent branches.
+    /// Since blocks are 16-byte aligned, this operates on them as a `u128`.
+    #[cfg_attr(all(target_arch = "x86_64", target_feature = "sse2"), allow(dead_code))]
+    #[inline]
+    fn dbl_scalar(&mut self) {
+        let x: &mut u128 = unsafe { mem::transmute(&mut self.0) };
+        let input = u128::from_be(*x);
+        let mask = ((input as i128) >> 127) as u128;
+        *x = ((input << 1) ^ (mask & 0x87)).to_be();
+    }
+
+    /// Performs the inverse of `dbl`, i.e. multiplies the block by the
+    /// inverse of x in GF(2^128), as used by PMAC. Branch-free: the
+    /// reduction is applied using a mask computed from the low bit
+    #[inline]
+    pub fn dbl_inv(&mut self) {
+        let x: &mut u128 = unsafe { mem::transmute(&mut self.0) };
+        let input = u128::from_be(*x);
+        let mask = 0u128.wrapping_sub(input & 1);
+        *x = ((input >> 1) ^ (mask & ((1 << 127) | 0x43))).to_be();
+    }
+
+    /// Increment the block as a 128-bit big endian counter (as used by the
+    /// CTR mode in SIV), wrapping around to zero once it's all `0xFF` bytes
+    #[inline]
+    pub fn increment_be(&mut self) {
+        let x: &mut u128 = unsafe { mem::transmute(&mut self.0) };
+        *x = u128::from_be(*x).wrapping_add(1).to_be();
+    }
+
+    /// Increment the first 4 bytes of the block as a 32-bit little endian
+    /// counter (as used by AES-GCM-SIV), wrapping around to zero once they're
+    /// all `0xFF` bytes. The remaining 12 bytes are left untouched
+    #[inline]
+    pub fn increment_le32(&mut self) {
+        // Blocks are 16-byte aligned, so the first 4 bytes are suitably
+        // aligned to be accessed as a `u32`
+        let x: &mut u32 = unsafe { &mut *(self.0.as_mut_ptr() as *mut u32) };
+        *x = u32::from_le(*x).wrapping_add(1).to_le();
     }
 }
 
 impl From<[u8; SIZE]> for Block {
     #[inline]
     fn from(buf: [u8; SIZE]) -> Block {
-        Block(buf)
+        Block::from_bytes(buf)
     }
 }
 
-impl<'a> From<&'a [u8]> for Block {
+impl Default for Block {
     #[inline]
-    fn from(buf: &[u8]) -> Block {
-        let len = buf.len();
+    fn default() -> Block {
+        Block::new()
+    }
+}
 
-        if len > SIZE {
-            panic!("slice is too large for block! (size: {})", buf.len());
-        }
+impl<'a> TryFrom<&'a [u8]> for Block {
+    type Error = BlockError;
 
-        let mut block = Block::new();
-        block.0[..len].copy_from_slice(buf);
-        block
+    #[inline]
+    fn try_from(buf: &[u8]) -> Result<Block, BlockError> {
+        Block::try_from_slice(buf)
     }
 }
 
@@ -110,6 +315,13 @@ impl AsRef<[u8]> for Block {
     }
 }
 
+impl AsMut<[u8]> for Block {
+    #[inline]
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut self.0[..]
+    }
+}
+
 impl AsMut<[u8; SIZE]> for Block {
     #[inline]
     fn as_mut(&mut self) -> &mut [u8; SIZE] {
@@ -117,23 +329,246 @@ impl AsMut<[u8; SIZE]> for Block {
     }
 }
 
+impl fmt::Debug for Block {
+    #[cfg(not(feature = "danger-debug"))]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Block(...redacted...)")
+    }
+
+    #[cfg(feature = "danger-debug")]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Block(")?;
+
+        for byte in self.0.iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+
+        write!(f, ")")
+    }
+}
+
+impl Zeroize for Block {
+    #[inline]
+    fn zeroize(&mut self) {
+        self.0.zeroize()
+    }
+}
+
 impl Drop for Block {
     #[inline]
     fn drop(&mut self) {
-        self.clear()
+        self.zeroize()
+    }
+}
+
+impl ConstantTimeEq for Block {
+    #[inline]
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+/// Is the given pointer aligned to a block boundary?
+#[inline]
+fn is_aligned(ptr: *const u8) -> bool {
+    (ptr as usize) % mem::align_of::<Block>() == 0
+}
+
+/// A zero-copy, block-aligned view of `SIZE` bytes of caller memory
+#[derive(Copy, Clone)]
+pub struct BlockRef<'a>(&'a [u8; SIZE]);
+
+impl<'a> BlockRef<'a> {
+    /// Create a view of the given slice, returning `None` unless it's exactly
+    /// `SIZE` bytes and aligned to a block boundary
+    #[inline]
+    pub fn new(buf: &'a [u8]) -> Option<Self> {
+        if buf.len() != SIZE || !is_aligned(buf.as_ptr()) {
+            return None;
+        }
+
+        Some(BlockRef(array_ref!(buf, 0, SIZE)))
+    }
+
+    /// Borrow the referenced memory as a `Block`
+    #[inline]
+    pub fn as_block(&self) -> &'a Block {
+        // Alignment and size were checked in `BlockRef::new`
+        unsafe { mem::transmute(self.0) }
+    }
+}
+
+impl<'a> AsRef<[u8]> for BlockRef<'a> {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.0
+    }
+}
+
+/// A zero-copy, block-aligned mutable view of `SIZE` bytes of caller memory
+pub struct BlockMut<'a>(&'a mut [u8; SIZE]);
+
+impl<'a> BlockMut<'a> {
+    /// Create a mutable view of the given slice, returning `None` unless it's
+    /// exactly `SIZE` bytes and aligned to a block boundary
+    #[inline]
+    pub fn new(buf: &'a mut [u8]) -> Option<Self> {
+        if buf.len() != SIZE || !is_aligned(buf.as_ptr()) {
+            return None;
+        }
+
+        Some(BlockMut(array_mut_ref!(buf, 0, SIZE)))
+    }
+
+    /// Mutably borrow the referenced memory as a `Block`
+    #[inline]
+    pub fn as_block_mut(&mut self) -> &mut Block {
+        // Alignment and size were checked in `BlockMut::new`
+        unsafe { mem::transmute(&mut *self.0) }
+    }
+}
+
+impl<'a> AsRef<[u8]> for BlockMut<'a> {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        &self.0[..]
     }
 }
 
-impl CTEq for Block {
+impl<'a> AsMut<[u8; SIZE]> for BlockMut<'a> {
     #[inline]
-    fn ct_eq(&self, other: &Self) -> Mask {
-        subtle::arrays_equal(&self.0, &other.0)
+    fn as_mut(&mut self) -> &mut [u8; SIZE] {
+        self.0
+    }
+}
+
+/// SSE2 implementation of dbl()
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+mod sse2 {
+    use super::SIZE;
+    use core::arch::x86_64::*;
+
+    /// Double a big endian block in GF(2^128) without data-dependent branches
+    #[inline]
+    pub unsafe fn dbl(block: &mut [u8; SIZE]) {
+        let x = _mm_load_si128(block.as_ptr() as *const __m128i);
+
+        // The high bit of each byte, shifted down to the low bit
+        let carries = _mm_and_si128(_mm_srli_epi16(x, 7), _mm_set1_epi8(1));
+
+        // Shift each byte left, carrying in the high bit of the next byte
+        let shifted = _mm_or_si128(_mm_add_epi8(x, x), _mm_srli_si128(carries, 1));
+
+        // Expand the high bit of the block into a mask for the reduction polynomial
+        let msb = _mm_slli_si128(carries, 15);
+        let reduction = _mm_and_si128(
+            _mm_sub_epi8(_mm_setzero_si128(), msb),
+            _mm_set1_epi8(0x87u8 as i8),
+        );
+
+        _mm_store_si128(
+            block.as_mut_ptr() as *mut __m128i,
+            _mm_xor_si128(shifted, reduction),
+        );
+    }
+}
+
+/// Eight blocks which can be passed to a block cipher in a single call,
+/// allowing implementations to pipeline them (e.g. with AES-NI)
+///
+/// `repr(C)` guarantees this has the same layout as `[Block; BLOCK8_COUNT]`
+#[derive(Clone, Default)]
+#[repr(C, align(16))]
+pub struct Block8([Block; BLOCK8_COUNT]);
+
+impl Block8 {
+    /// Create a new set of eight blocks, initialized to zero
+    pub fn new() -> Block8 {
+        Block8::default()
+    }
+
+    /// XOR a 128-byte slice into these blocks
+    #[inline]
+    pub fn xor_in_place<T>(&mut self, other: T)
+    where
+        T: AsRef<[u8]>,
+    {
+        assert_eq!(
+            other.as_ref().len(),
+            BLOCK8_SIZE,
+            "xor_in_place works on Block8-sized slices"
+        );
+
+        for (i, block) in self.0.iter_mut().enumerate() {
+            block.xor_array(array_ref!(other.as_ref(), i * SIZE, SIZE));
+        }
+    }
+
+    /// Treat eight contiguous blocks (e.g. part of a larger slice of blocks)
+    /// as a `Block8`, without copying
+    #[inline]
+    pub fn from_blocks_mut(blocks: &mut [Block; BLOCK8_COUNT]) -> &mut Block8 {
+        let ptr: *mut [Block; BLOCK8_COUNT] = blocks;
+        unsafe { &mut *(ptr as *mut Block8) }
+    }
+
+    /// Borrow the individual blocks
+    #[inline]
+    pub fn as_blocks(&self) -> &[Block; BLOCK8_COUNT] {
+        &self.0
+    }
+
+    /// Mutably borrow the individual blocks
+    #[inline]
+    pub fn as_blocks_mut(&mut self) -> &mut [Block; BLOCK8_COUNT] {
+        &mut self.0
+    }
+
+    /// Zero out the contents of all eight blocks
+    #[inline]
+    pub fn clear(&mut self) {
+        self.zeroize()
+    }
+}
+
+impl AsRef<[u8; BLOCK8_SIZE]> for Block8 {
+    #[inline]
+    fn as_ref(&self) -> &[u8; BLOCK8_SIZE] {
+        // Blocks are contiguous and exactly SIZE bytes each
+        unsafe { mem::transmute(&self.0) }
+    }
+}
+
+impl AsMut<[u8; BLOCK8_SIZE]> for Block8 {
+    #[inline]
+    fn as_mut(&mut self) -> &mut [u8; BLOCK8_SIZE] {
+        // Blocks are contiguous and exactly SIZE bytes each
+        unsafe { mem::transmute(&mut self.0) }
+    }
+}
+
+impl fmt::Debug for Block8 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.0.iter()).finish()
+    }
+}
+
+impl Zeroize for Block8 {
+    #[inline]
+    fn zeroize(&mut self) {
+        for block in self.0.iter_mut() {
+            block.zeroize();
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Block;
+    use super::{Block, Block8, BlockMut, BlockRef, BLOCK8_SIZE, HEX_SIZE, SIZE};
+    use error::Error;
+    use core::{mem, ptr};
+    use internals::test_rng::XorShift64;
+    use subtle::{Choice, ConstantTimeEq};
 
     #[test]
     fn test_xor_in_place() {
@@ -151,4 +586,473 @@ mod tests {
             b"\x9a\x64\x22\xb7\xdd\x16\xde\x9d\xf6\x24\x94\xc8\xf3\xfd\x6e\xd1"
         );
     }
+
+    #[test]
+    fn test_try_from_slice() {
+        let buf = [0x42u8; SIZE + 1];
+
+        assert_eq!(
+            Block::try_from_slice(&buf[..SIZE - 1]).err().unwrap().slice_len(),
+            SIZE - 1
+        );
+        assert_eq!(
+            Block::try_from_slice(&buf[..SIZE]).unwrap().as_ref(),
+            &buf[..SIZE]
+        );
+        assert_eq!(
+            Block::try_from_slice(&buf[..SIZE + 1]).err().unwrap().slice_len(),
+            SIZE + 1
+        );
+    }
+
+    #[test]
+    fn test_block_error_into_error_keeps_len() {
+        /// Convert via `?`, as a caller returning `Error` would
+        fn convert(buf: &[u8]) -> Result<Block, Error> {
+            Ok(Block::try_from_slice(buf)?)
+        }
+
+        let buf = [0x42u8; SIZE + 3];
+        assert_eq!(convert(&buf).err(), Some(Error::BlockSize { len: SIZE + 3 }));
+        assert_eq!(convert(&buf[..0]).err(), Some(Error::BlockSize { len: 0 }));
+    }
+
+    #[test]
+    fn test_from_slice_zero_padded() {
+        let buf = [0x42u8; SIZE + 1];
+
+        let block = Block::from_slice_zero_padded(&buf[..SIZE - 1]).unwrap();
+        assert_eq!(&block.as_ref()[..SIZE - 1], &buf[..SIZE - 1]);
+        assert_eq!(block.as_ref()[SIZE - 1], 0);
+
+        assert_eq!(
+            Block::from_slice_zero_padded(&buf[..SIZE]).unwrap().as_ref(),
+            &buf[..SIZE]
+        );
+        assert_eq!(
+            Block::from_slice_zero_padded(&buf[..SIZE + 1]).err().unwrap().slice_len(),
+            SIZE + 1
+        );
+    }
+
+    #[test]
+    fn test_zeroize_on_drop() {
+        let mut block = mem::ManuallyDrop::new(Block::from([0x42u8; SIZE]));
+
+        unsafe {
+            ptr::drop_in_place(&mut *block);
+            let block_ptr: *const Block = &*block;
+            let bytes = ptr::read(block_ptr as *const [u8; SIZE]);
+            assert_eq!(bytes, [0u8; SIZE]);
+        }
+    }
+
+    #[test]
+    fn test_block8_xor_in_place() {
+        let mut input = [0u8; BLOCK8_SIZE];
+        for (i, byte) in input.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+
+        let mut blocks = Block8::new();
+        for (i, block) in blocks.as_blocks_mut().iter_mut().enumerate() {
+            *block = Block::from([0xA5u8 ^ (i as u8); SIZE]);
+        }
+
+        let mut expected = blocks.clone();
+        for (i, block) in expected.as_blocks_mut().iter_mut().enumerate() {
+            block.xor_in_place(&input[i * SIZE..(i + 1) * SIZE]);
+        }
+
+        blocks.xor_in_place(&input[..]);
+
+        let actual: &[u8; BLOCK8_SIZE] = blocks.as_ref();
+        let expected: &[u8; BLOCK8_SIZE] = expected.as_ref();
+        assert_eq!(&actual[..], &expected[..]);
+    }
+
+    #[test]
+    fn test_dbl() {
+        // Subkey generation example from RFC 4493 Section 4 (CMAC paper vectors)
+        let mut block = Block::from(
+            *b"\x7d\xf7\x6b\x0c\x1a\xb8\x99\xb3\x3e\x42\xf0\x47\xb9\x1b\x54\x6f",
+        );
+
+        block.dbl();
+        assert_eq!(
+            block.as_ref(),
+            b"\xfb\xee\xd6\x18\x35\x71\x33\x66\x7c\x85\xe0\x8f\x72\x36\xa8\xde"
+        );
+
+        block.dbl();
+        assert_eq!(
+            block.as_ref(),
+            b"\xf7\xdd\xac\x30\x6a\xe2\x66\xcc\xf9\x0b\xc1\x1e\xe4\x6d\x51\x3b"
+        );
+    }
+
+    #[test]
+    fn test_xor() {
+        let block1 = Block::from(
+            *b"\x17\xcc\xf7\xf7\xa1\x8c\xbc\x3d\x8d\xad\0\xf1\xc9\x79\x9f\xba",
+        );
+
+        let block2 = Block::from(
+            *b"\x8d\xa8\xd5\x40\x7c\x9a\x62\xa0\x7b\x89\x94\x39\x3a\x84\xf1\x6b",
+        );
+
+        let result = block1.xor(&block2);
+        assert_eq!(
+            result.as_ref(),
+            b"\x9a\x64\x22\xb7\xdd\x16\xde\x9d\xf6\x24\x94\xc8\xf3\xfd\x6e\xd1"
+        );
+        assert_eq!(
+            block1.as_ref(),
+            b"\x17\xcc\xf7\xf7\xa1\x8c\xbc\x3d\x8d\xad\0\xf1\xc9\x79\x9f\xba"
+        );
+    }
+
+    /// Byte-at-a-time reference implementation of dbl()
+    fn dbl_reference(value: &mut [u8; SIZE]) {
+        let carry = value[0] >> 7;
+
+        for i in 0..(SIZE - 1) {
+            value[i] = (value[i] << 1) | (value[i + 1] >> 7);
+        }
+
+        value[SIZE - 1] = (value[SIZE - 1] << 1) ^ (carry * 0x87);
+    }
+
+    /// Check both `dbl()` (which may be SIMD-accelerated) and the portable
+    /// `dbl_scalar()` against the reference implementation
+    fn check_dbl(input: [u8; SIZE]) {
+        let mut expected = input;
+        dbl_reference(&mut expected);
+
+        let mut block = Block::from(input);
+        block.dbl();
+        assert_eq!(block.as_ref(), &expected);
+
+        let mut block = Block::from(input);
+        block.dbl_scalar();
+        assert_eq!(block.as_ref(), &expected);
+    }
+
+    #[test]
+    fn test_dbl_edge_cases() {
+        check_dbl([0u8; SIZE]);
+        check_dbl([0xFFu8; SIZE]);
+
+        let mut msb_set = [0u8; SIZE];
+        msb_set[0] = 0x80;
+        check_dbl(msb_set);
+
+        let mut lsb_set = [0u8; SIZE];
+        lsb_set[SIZE - 1] = 0x01;
+        check_dbl(lsb_set);
+    }
+
+    #[test]
+    fn test_dbl_inv_edge_cases() {
+        let mut block = Block::from(
+            *b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\x01",
+        );
+        block.dbl_inv();
+        assert_eq!(
+            block.as_ref(),
+            b"\x80\0\0\0\0\0\0\0\0\0\0\0\0\0\0\x43"
+        );
+
+        let mut block = Block::from([0xFFu8; SIZE]);
+        block.dbl_inv();
+        assert_eq!(
+            block.as_ref(),
+            b"\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xbc"
+        );
+
+        let mut block = Block::from(
+            *b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\x87",
+        );
+        block.dbl_inv();
+        assert_eq!(
+            block.as_ref(),
+            b"\x80\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0"
+        );
+    }
+
+    #[test]
+    fn test_dbl_inv_inverts_dbl() {
+        let mut rng = XorShift64::new(0x9e37_79b9_7f4a_7c15);
+
+        for _ in 0..4096 {
+            let mut input = [0u8; SIZE];
+            rng.fill(&mut input);
+
+            let mut block = Block::from(input);
+            block.dbl();
+            block.dbl_inv();
+            assert_eq!(block.as_ref(), &input);
+
+            block.dbl_inv();
+            block.dbl();
+            assert_eq!(block.as_ref(), &input);
+        }
+    }
+
+    #[test]
+    fn test_dbl_matches_reference() {
+        let mut rng = XorShift64::new(0x2545_f491_4f6c_dd1d);
+
+        for _ in 0..4096 {
+            let mut input = [0u8; SIZE];
+            rng.fill(&mut input);
+            check_dbl(input);
+        }
+    }
+
+    /// Generic helper which binds to the slice-returning `AsMut` impl
+    fn fill<T: AsMut<[u8]>>(mut buf: T, value: u8) -> T {
+        for byte in buf.as_mut().iter_mut() {
+            *byte = value;
+        }
+        buf
+    }
+
+    #[test]
+    fn test_as_mut_slice() {
+        let block = fill(Block::new(), 0x42);
+        assert_eq!(block.as_ref(), &[0x42u8; SIZE]);
+    }
+
+    #[test]
+    fn test_xor_in_place_unaligned() {
+        let mut buf = [0u8; SIZE * 3];
+        for (i, byte) in buf.iter_mut().enumerate() {
+            *byte = (i as u8).wrapping_mul(0x1d);
+        }
+
+        for offset in 0..SIZE {
+            let other = &buf[offset..offset + SIZE];
+
+            let mut block = Block::from([0xA5u8; SIZE]);
+            block.xor_in_place(other);
+
+            for (i, byte) in block.as_ref().iter().enumerate() {
+                assert_eq!(*byte, 0xA5 ^ other[i], "offset: {}", offset);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ct_eq() {
+        let block1 = Block::from([0x42u8; SIZE]);
+        let block2 = Block::from([0x42u8; SIZE]);
+        assert_eq!(block1.ct_eq(&block2).unwrap_u8(), 1);
+
+        let mut bytes = [0x42u8; SIZE];
+        bytes[SIZE - 1] ^= 1;
+        let block3 = Block::from(bytes);
+        assert_eq!(block1.ct_eq(&block3).unwrap_u8(), 0);
+    }
+
+    #[test]
+    fn test_conditional_select() {
+        let a = Block::from([0xAAu8; SIZE]);
+        let b = Block::from([0xBBu8; SIZE]);
+
+        assert_eq!(
+            Block::conditional_select(&a, &b, Choice::from(0)).as_ref(),
+            a.as_ref()
+        );
+        assert_eq!(
+            Block::conditional_select(&a, &b, Choice::from(1)).as_ref(),
+            b.as_ref()
+        );
+
+        let mut c = a.clone();
+        c.conditional_assign(&b, Choice::from(0));
+        assert_eq!(c.as_ref(), a.as_ref());
+        c.conditional_assign(&b, Choice::from(1));
+        assert_eq!(c.as_ref(), b.as_ref());
+    }
+
+    #[test]
+    fn test_from_slice_with_bit_padding() {
+        let buf = [0x42u8; SIZE];
+
+        let block = Block::from_slice_with_bit_padding(&[]).unwrap();
+        assert_eq!(block.as_ref(), b"\x80\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0");
+
+        let block = Block::from_slice_with_bit_padding(&buf[..1]).unwrap();
+        assert_eq!(block.as_ref(), b"\x42\x80\0\0\0\0\0\0\0\0\0\0\0\0\0\0");
+
+        let block = Block::from_slice_with_bit_padding(&buf[..SIZE - 1]).unwrap();
+        assert_eq!(&block.as_ref()[..SIZE - 1], &buf[..SIZE - 1]);
+        assert_eq!(block.as_ref()[SIZE - 1], 0x80);
+
+        assert_eq!(
+            Block::from_slice_with_bit_padding(&buf).err().unwrap().slice_len(),
+            SIZE
+        );
+    }
+
+    #[test]
+    fn test_block_ref_alignment() {
+        let storage = Block8::new();
+        let bytes: &[u8; BLOCK8_SIZE] = storage.as_ref();
+
+        assert!(BlockRef::new(&bytes[..SIZE]).is_some());
+        assert!(BlockRef::new(&bytes[SIZE..SIZE * 2]).is_some());
+        assert!(BlockRef::new(&bytes[1..SIZE + 1]).is_none());
+        assert!(BlockRef::new(&bytes[..SIZE - 1]).is_none());
+    }
+
+    #[test]
+    fn test_block_mut_alignment() {
+        let mut storage = Block8::new();
+        let bytes: &mut [u8; BLOCK8_SIZE] = storage.as_mut();
+
+        {
+            let mut block = BlockMut::new(&mut bytes[..SIZE]).unwrap();
+            block.as_block_mut().xor_in_place(&[0x42u8; SIZE]);
+        }
+
+        assert_eq!(&bytes[..SIZE], &[0x42u8; SIZE]);
+        assert!(BlockMut::new(&mut bytes[1..SIZE + 1]).is_none());
+    }
+
+    #[test]
+    fn increment_be_carries_across_bytes() {
+        for i in 0..SIZE {
+            // Fill the trailing `i` bytes with 0xFF so incrementing carries
+            // into byte `SIZE - 1 - i`
+            let mut bytes = [0x42u8; SIZE];
+            for b in &mut bytes[SIZE - i..] {
+                *b = 0xFF;
+            }
+
+            let mut expected = bytes;
+            expected[SIZE - 1 - i] += 1;
+            for b in &mut expected[SIZE - i..] {
+                *b = 0;
+            }
+
+            let mut block = Block::from(bytes);
+            block.increment_be();
+            assert_eq!(block.as_ref(), &expected, "carry into byte {}", SIZE - 1 - i);
+        }
+    }
+
+    #[test]
+    fn increment_be_wraps() {
+        let mut block = Block::new();
+        block.increment_be();
+        assert_eq!(block.as_ref(), *b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\x01");
+
+        let mut block = Block::from([0xFFu8; SIZE]);
+        block.increment_be();
+        assert_eq!(block.as_ref(), &[0u8; SIZE]);
+    }
+
+    #[test]
+    fn increment_le32_carries_across_bytes() {
+        for i in 0..4 {
+            // Fill the leading `i` bytes with 0xFF so incrementing carries
+            // into byte `i`
+            let mut bytes = [0x42u8; SIZE];
+            for b in &mut bytes[..i] {
+                *b = 0xFF;
+            }
+
+            let mut expected = bytes;
+            expected[i] += 1;
+            for b in &mut expected[..i] {
+                *b = 0;
+            }
+
+            let mut block = Block::from(bytes);
+            block.increment_le32();
+            assert_eq!(block.as_ref(), &expected, "carry into byte {}", i);
+        }
+    }
+
+    #[test]
+    fn increment_le32_wraps() {
+        let mut block = Block::from([0xFFu8; SIZE]);
+        block.increment_le32();
+
+        // Only the 32-bit counter wraps, the rest of the block is untouched
+        assert_eq!(&block.as_ref()[..4], &[0u8; 4]);
+        assert_eq!(&block.as_ref()[4..], &[0xFFu8; SIZE - 4]);
+    }
+
+    #[test]
+    fn test_xor_block_and_array_match_xor_in_place() {
+        let a = *b"\x17\xcc\xf7\xf7\xa1\x8c\xbc\x3d\x8d\xad\0\xf1\xc9\x79\x9f\xba";
+        let b = *b"\x8d\xa8\xd5\x40\x7c\x9a\x62\xa0\x7b\x89\x94\x39\x3a\x84\xf1\x6b";
+
+        let mut expected = Block::from(a);
+        expected.xor_in_place(&b[..]);
+
+        let mut block = Block::from(a);
+        block.xor_block(&Block::from(b));
+        assert_eq!(block.as_ref(), expected.as_ref());
+
+        let mut block = Block::from(a);
+        block.xor_array(&b);
+        assert_eq!(block.as_ref(), expected.as_ref());
+    }
+
+    #[test]
+    fn test_hex_vectors() {
+        let block = Block::from_hex("bb1d6929e95937287fa37d129b756746").unwrap();
+        assert_eq!(
+            block.as_ref(),
+            b"\xbb\x1d\x69\x29\xe9\x59\x37\x28\x7f\xa3\x7d\x12\x9b\x75\x67\x46"
+        );
+
+        let mut out = [0u8; HEX_SIZE];
+        assert_eq!(block.to_hex(&mut out), "bb1d6929e95937287fa37d129b756746");
+
+        let upper = Block::from_hex("BB1D6929E95937287FA37D129B756746").unwrap();
+        assert_eq!(upper.as_ref(), block.as_ref());
+
+        assert_eq!(
+            Block::new().to_hex(&mut out),
+            "00000000000000000000000000000000"
+        );
+    }
+
+    #[test]
+    fn test_hex_errors() {
+        assert_eq!(Block::from_hex("").err(), Some(Error::HexLength));
+        assert_eq!(
+            Block::from_hex("bb1d6929e95937287fa37d129b75674").err(),
+            Some(Error::HexLength)
+        );
+        assert_eq!(
+            Block::from_hex("bb1d6929e95937287fa37d129b7567466").err(),
+            Some(Error::HexLength)
+        );
+        assert_eq!(
+            Block::from_hex("bb1d6929e95937287fa37d129b75674g").err(),
+            Some(Error::HexCharacter)
+        );
+        assert_eq!(
+            Block::from_hex("0xbb1d6929e95937287fa37d129b7567").err(),
+            Some(Error::HexCharacter)
+        );
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let mut rng = XorShift64::new(0x0123_4567_89ab_cdef);
+        let mut out = [0u8; HEX_SIZE];
+
+        for _ in 0..1024 {
+            let block = rng.block();
+            let decoded = Block::from_hex(block.to_hex(&mut out)).unwrap();
+            assert_eq!(decoded.as_ref(), block.as_ref());
+        }
+    }
 }
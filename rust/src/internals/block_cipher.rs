@@ -1,14 +1,52 @@
 //! `internals/block_cipher.rs`: Trait for encrypting with different block ciphers.
 
-use super::Block;
+use super::{Block, Block8, BlockMut, BLOCK8_COUNT};
+use zeroize::Zeroize;
 
 /// Common interface to a block cipher's raw block function
 ///
-/// We implement only the encryption function as that's all that AES-SIV depends on
-pub trait BlockCipher: Clone {
+/// We implement only the encryption function as that's all that AES-SIV depends on.
+/// Ciphers must be able to wipe their expanded key schedule via `Zeroize`.
+pub trait BlockCipher: Clone + Zeroize {
     /// Size of the key used by this cipher (in bytes)
     const KEY_SIZE: usize;
 
     /// Encrypt a block
     fn encrypt(&self, block: &mut Block);
+
+    /// Encrypt a block-aligned view of caller memory in-place without copying
+    #[inline]
+    fn encrypt_ref(&self, block: &mut BlockMut) {
+        self.encrypt(block.as_block_mut())
+    }
+
+    /// Encrypt eight blocks independently. Ciphers which can pipeline
+    /// multiple blocks should override the default sequential implementation.
+    #[inline]
+    fn encrypt8(&self, blocks: &mut Block8) {
+        for block in blocks.as_blocks_mut().iter_mut() {
+            self.encrypt(block);
+        }
+    }
+
+    /// Encrypt any number of blocks independently, passing them to `encrypt8`
+    /// eight at a time so ciphers which can pipeline blocks benefit from it
+    ///
+    /// Each block is encrypted on its own under the same key, exactly as in
+    /// ECB mode: the result is identical to calling `encrypt` on every block
+    /// in order, and no state is chained between blocks. Modes like CTR and
+    /// PMAC, whose block cipher inputs are independent, can use this to batch
+    /// their calls.
+    #[inline]
+    fn encrypt_blocks(&self, blocks: &mut [Block]) {
+        for chunk in blocks.chunks_mut(BLOCK8_COUNT) {
+            if chunk.len() == BLOCK8_COUNT {
+                self.encrypt8(Block8::from_blocks_mut(array_mut_ref!(chunk, 0, BLOCK8_COUNT)));
+            } else {
+                for block in chunk.iter_mut() {
+                    self.encrypt(block);
+                }
+            }
+        }
+    }
 }
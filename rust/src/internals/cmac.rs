@@ -1,11 +1,177 @@
 //! `internals/cmac.rs`: Cipher-based Message Authentication Code
 
-use super::{Block, BlockCipher, BLOCK_SIZE};
+use super::{Aes128, Aes192, Aes256, Block, BlockCipher, Tag, BLOCK_SIZE};
+#[cfg(feature = "std")]
+use super::{Block8, BLOCK8_COUNT};
+use super::mac::Mac;
 use super::xor;
+#[cfg(feature = "std")]
+use core::cmp;
+use core::fmt;
+#[cfg(feature = "crypto-mac")]
+use crypto_mac::{self, Key, Output};
+#[cfg(feature = "crypto-mac")]
+use crypto_mac::generic_array::GenericArray;
+#[cfg(feature = "crypto-mac")]
+use crypto_mac::generic_array::typenum::{U16, U24, U32};
+use error::Error;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::vec::Vec;
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
-type Tag = Block;
+/// Minimum size of a truncated tag accepted by `Cmac::verify`
+pub const MIN_TAG_SIZE: usize = 8;
+
+/// A block of all zeroes
+const ZERO_BLOCK: &[u8; BLOCK_SIZE] = &[0u8; BLOCK_SIZE];
+
+/// A CMAC key: the block cipher along with its precomputed subkeys
+///
+/// Deriving the subkeys costs a block encryption, so keeping a `CmacKey`
+/// around makes it cheap to create many `Cmac` instances with the same key.
+#[derive(Clone)]
+pub struct CmacKey<C: BlockCipher> {
+    cipher: C,
+    subkey1: Block,
+    subkey2: Block,
+}
+
+impl<C: BlockCipher> CmacKey<C> {
+    /// Create a new CMAC key with the given cipher, deriving its subkeys
+    #[inline]
+    pub fn new(cipher: C) -> Self {
+        let (subkey1, subkey2) = derive_subkeys(&cipher);
+
+        Self {
+            cipher: cipher,
+            subkey1: subkey1,
+            subkey2: subkey2,
+        }
+    }
+
+    /// Create a fresh CMAC instance using this key. The cipher and subkeys
+    /// are cloned, so the key can keep being used afterwards.
+    #[inline]
+    pub fn mac(&self) -> Cmac<C> {
+        Cmac {
+            cipher: self.cipher.clone(),
+            subkey1: self.subkey1.clone(),
+            subkey2: self.subkey2.clone(),
+            state: Block::new(),
+            state_pos: 0,
+            finished: false,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<C: BlockCipher> CmacKey<C> {
+    /// Compute the CMACs of eight independent messages side by side, passing
+    /// their blocks to the cipher together
+    fn mac8(&self, messages: &[&[u8]; BLOCK8_COUNT]) -> Block8 {
+        // Number of blocks in each message, where an empty message is a
+        // single (padded) block
+        let mut lengths = [0usize; BLOCK8_COUNT];
+        for (n, msg) in lengths.iter_mut().zip(messages.iter()) {
+            *n = cmp::max(1, (msg.len() + BLOCK_SIZE - 1) / BLOCK_SIZE);
+        }
+
+        let rounds = *lengths.iter().max().unwrap();
+        let mut state = Block8::new();
+
+        // Every block except the last is XORed into the state and encrypted.
+        // Lanes whose messages have no blocks left this round keep their
+        // previous state.
+        for round in 0..(rounds - 1) {
+            let previous = state.clone();
+
+            for (lane, block) in state.as_blocks_mut().iter_mut().enumerate() {
+                if round + 1 < lengths[lane] {
+                    block.xor_array(array_ref!(messages[lane], round * BLOCK_SIZE, BLOCK_SIZE));
+                }
+            }
+
+            self.cipher.encrypt8(&mut state);
+
+            for (lane, block) in state.as_blocks_mut().iter_mut().enumerate() {
+                if round + 1 >= lengths[lane] {
+                    block.copy_from_block(&previous.as_blocks()[lane]);
+                }
+            }
+        }
+
+        for (lane, block) in state.as_blocks_mut().iter_mut().enumerate() {
+            let last = &messages[lane][(lengths[lane] - 1) * BLOCK_SIZE..];
+
+            if last.len() == BLOCK_SIZE {
+                block.xor_array(array_ref!(last, 0, BLOCK_SIZE));
+                block.xor_block(&self.subkey1);
+            } else {
+                block.xor_block(&Block::from_slice_with_bit_padding(last).unwrap());
+                block.xor_block(&self.subkey2);
+            }
+        }
+
+        self.cipher.encrypt8(&mut state);
+        state
+    }
+}
+
+#[cfg(test)]
+impl<C: BlockCipher> CmacKey<C> {
+    /// The cipher and subkeys, so tests can check they've been wiped
+    pub fn key_material(&self) -> (&C, &Block, &Block) {
+        (&self.cipher, &self.subkey1, &self.subkey2)
+    }
+}
+
+impl<C: BlockCipher> Zeroize for CmacKey<C> {
+    /// Wipe the cipher's key schedule and the subkeys
+    fn zeroize(&mut self) {
+        self.cipher.zeroize();
+        self.subkey1.zeroize();
+        self.subkey2.zeroize();
+    }
+}
+
+impl<C: BlockCipher> fmt::Debug for CmacKey<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CmacKey(...redacted...)")
+    }
+}
+
+impl<C: BlockCipher> Drop for CmacKey<C> {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
+impl<C: BlockCipher> ZeroizeOnDrop for CmacKey<C> {}
+
+/// Derive the two CMAC subkeys by encrypting the zero block and doubling
+/// (RFC 4493 Section 2.3)
+#[inline]
+fn derive_subkeys<C: BlockCipher>(cipher: &C) -> (Block, Block) {
+    let mut subkey1 = Block::new();
+    cipher.encrypt(&mut subkey1);
+    subkey1.dbl();
+
+    let mut subkey2 = subkey1.clone();
+    subkey2.dbl();
+
+    (subkey1, subkey2)
+}
 
 /// Cipher-based Message Authentication Code
+///
+/// Use a `CmacKey` to create several instances without re-deriving subkeys.
+///
+/// Cloning a `Cmac` forks its state (including the `finished` flag), e.g. to
+/// MAC a shared prefix once and then branch for several different suffixes.
+#[derive(Clone)]
 pub struct Cmac<C: BlockCipher> {
     cipher: C,
     subkey1: Block,
@@ -19,12 +185,7 @@ impl<C: BlockCipher> Cmac<C> {
     /// Create a new CMAC instance with the given cipher
     #[inline]
     pub fn new(cipher: C) -> Self {
-        let mut subkey1 = Block::new();
-        cipher.encrypt(&mut subkey1);
-        subkey1.dbl();
-
-        let mut subkey2 = subkey1.clone();
-        subkey2.dbl();
+        let (subkey1, subkey2) = derive_subkeys(&cipher);
 
         Self {
             subkey1: subkey1,
@@ -36,6 +197,32 @@ impl<C: BlockCipher> Cmac<C> {
         }
     }
 
+    /// Compute the CMACs of many independent messages (e.g. small records),
+    /// returning their tags in order
+    ///
+    /// CMAC is sequential within a message, so batches of eight messages are
+    /// computed side by side with their blocks encrypted together using
+    /// `BlockCipher::encrypt8`. Messages left over after the last full batch
+    /// are computed one at a time.
+    #[cfg(feature = "std")]
+    pub fn compute_batch(key: &CmacKey<C>, messages: &[&[u8]]) -> Vec<Tag> {
+        let mut tags = Vec::with_capacity(messages.len());
+
+        for batch in messages.chunks(BLOCK8_COUNT) {
+            if batch.len() == BLOCK8_COUNT {
+                let blocks = key.mac8(array_ref!(batch, 0, BLOCK8_COUNT));
+                tags.extend(blocks.as_blocks().iter().map(|block| Tag::from(block.clone())));
+            } else {
+                for msg in batch {
+                    let tag = key.mac().chain_update(msg).and_then(|mut mac| mac.finish());
+                    tags.push(tag.expect("new MAC isn't finished"));
+                }
+            }
+        }
+
+        tags
+    }
+
     /// Reset a CMAC instance back to its initial state
     #[inline]
     pub fn reset(&mut self) {
@@ -44,23 +231,39 @@ impl<C: BlockCipher> Cmac<C> {
         self.finished = false;
     }
 
+    /// Switch to a new cipher (e.g. to rotate keys), reusing this instance
+    ///
+    /// The old key schedule, subkeys, and MAC state are wiped before the new
+    /// subkeys are derived, leaving the MAC reset under the new key.
+    pub fn rekey(&mut self, cipher: C) {
+        self.zeroize();
+        self.cipher = cipher;
+
+        let (subkey1, subkey2) = derive_subkeys(&self.cipher);
+        self.subkey1 = subkey1;
+        self.subkey2 = subkey2;
+    }
+
     /// Update the CMAC state with the given message
     ///
-    /// Panics if we're already in a finished state (must reset before reusing)
-    pub fn update(&mut self, msg: &[u8]) {
+    /// Returns `Error::AlreadyFinished` if the MAC has already been finished
+    /// (it must be reset before reusing)
+    pub fn update(&mut self, msg: &[u8]) -> Result<(), Error> {
         if self.finished {
-            panic!("already finished");
+            return Err(Error::AlreadyFinished);
         }
 
         let mut msg_pos: usize = 0;
         let mut msg_len: usize = msg.len();
         let remaining = BLOCK_SIZE - self.state_pos;
 
-        if msg_len > remaining {
-            xor::in_place(
-                &mut self.state.as_mut()[self.state_pos..],
-                &msg[..remaining],
-            );
+        // Only a partially filled state needs topping up a byte at a time.
+        // When it's empty, whole blocks go straight to the aligned loop below.
+        if self.state_pos > 0 && msg_len > remaining {
+            {
+                let state: &mut [u8] = self.state.as_mut();
+                xor::in_place(&mut state[self.state_pos..], &msg[..remaining]);
+            }
 
             msg_len = msg_len.checked_sub(remaining).expect("underflow");
             msg_pos = msg_pos.checked_add(remaining).expect("overflow");
@@ -70,9 +273,7 @@ impl<C: BlockCipher> Cmac<C> {
         }
 
         while msg_len > BLOCK_SIZE {
-            self.state.xor_in_place(
-                array_ref!(msg, msg_pos, BLOCK_SIZE),
-            );
+            self.state.xor_array(array_ref!(msg, msg_pos, BLOCK_SIZE));
 
             msg_len = msg_len.checked_sub(BLOCK_SIZE).expect("underflow");
             msg_pos = msg_pos.checked_add(BLOCK_SIZE).expect("overflow");
@@ -83,36 +284,1049 @@ impl<C: BlockCipher> Cmac<C> {
         if msg_len > 0 {
             let state_end = self.state_pos.checked_add(msg_len).expect("overflow");
 
+            let state: &mut [u8] = self.state.as_mut();
             xor::in_place(
-                &mut self.state.as_mut()[self.state_pos..state_end],
+                &mut state[self.state_pos..state_end],
                 &msg[msg_pos..msg_pos.checked_add(msg_len).expect("overflow")],
             );
 
             self.state_pos = self.state_pos.checked_add(msg_len).expect("overflow");
         }
+
+        Ok(())
+    }
+
+    /// Update the CMAC state with the given message, consuming and returning
+    /// the MAC so calls can be chained, e.g.
+    /// `Aes128Cmac::new_from_key(&key).chain_update(header)?.chain_update(body)?`
+    ///
+    /// Returns `Error::AlreadyFinished` if the MAC has already been finished
+    /// (it must be reset before reusing)
+    #[inline]
+    pub fn chain_update(mut self, msg: &[u8]) -> Result<Self, Error> {
+        self.update(msg)?;
+        Ok(self)
+    }
+
+    /// Update the CMAC state with the entire contents of the given reader,
+    /// reading until it reaches EOF
+    ///
+    /// Data is read in block-sized chunks and passed to `update`, whose
+    /// internal buffering already handles reads which return less than a
+    /// full block. I/O errors (other than `Interrupted`, which is retried)
+    /// are returned to the caller.
+    ///
+    /// Returns an error of kind `Other` if the MAC has already been finished
+    /// (it must be reset before reusing)
+    #[cfg(feature = "std")]
+    pub fn update_from_reader<R: io::Read>(&mut self, reader: &mut R) -> io::Result<()> {
+        if self.finished {
+            return Err(io::Error::new(io::ErrorKind::Other, "already finished"));
+        }
+
+        let mut chunk = [0u8; BLOCK_SIZE];
+
+        loop {
+            match reader.read(&mut chunk) {
+                Ok(0) => return Ok(()),
+                Ok(n) => self.update(&chunk[..n]).expect("not finished"),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
     }
 
     /// Finish computing CMAC, returning the computed tag
     ///
-    /// Panics if we're already in a finished state (must reset before reusing)
-    pub fn finish(&mut self) -> Tag {
+    /// Returns `Error::AlreadyFinished` if the MAC has already been finished
+    /// (it must be reset before reusing)
+    #[inline]
+    pub fn finish(&mut self) -> Result<Tag, Error> {
+        self.finish_block().map(Tag::from)
+    }
+
+    /// Finish computing CMAC, returning the computed tag, and reset this
+    /// instance so it's immediately ready to compute another MAC
+    ///
+    /// Panics if the MAC has already been finished with `finish` (it must be
+    /// reset first)
+    pub fn finish_reset(&mut self) -> Tag {
+        let tag = self.finish().expect("already finished");
+        self.reset();
+        tag
+    }
+
+    /// Finish computing CMAC, returning the raw output block (e.g. for S2V)
+    #[inline]
+    fn finish_block(&mut self) -> Result<Block, Error> {
+        let mut out = [0u8; BLOCK_SIZE];
+        self.finish_into(&mut out)?;
+        Ok(Block::from(out))
+    }
+
+    /// Finish computing CMAC, writing the tag directly into `out`
+    ///
+    /// The internal state is wiped afterward, so the only copy of the tag is
+    /// the one in caller memory.
+    ///
+    /// Returns `Error::AlreadyFinished` if the MAC has already been finished
+    /// (it must be reset before reusing)
+    pub fn finish_into(&mut self, out: &mut [u8; BLOCK_SIZE]) -> Result<(), Error> {
         if self.finished {
-            panic!("already finished");
+            return Err(Error::AlreadyFinished);
         }
 
         if self.state_pos == BLOCK_SIZE {
-            self.state.xor_in_place(&self.subkey1);
+            self.state.xor_block(&self.subkey1);
         } else {
-            self.state.xor_in_place(&self.subkey2);
+            self.state.xor_block(&self.subkey2);
         };
 
         if self.state_pos < BLOCK_SIZE {
-            self.state.as_mut()[self.state_pos] ^= 0x80;
+            // The partial message block has already been XORed into the
+            // state, so we only need to XOR in the padding itself
+            let padding = Block::from_slice_with_bit_padding(&ZERO_BLOCK[..self.state_pos]).unwrap();
+            self.state.xor_block(&padding);
         }
 
         self.cipher.encrypt(&mut self.state);
         self.finished = true;
 
-        self.state.clone()
+        out.copy_from_slice(self.state.as_ref());
+        self.state.zeroize();
+
+        Ok(())
+    }
+
+    /// Finish computing CMAC and compare the result against the expected tag
+    /// in constant time
+    ///
+    /// The expected tag may be truncated to anywhere from `MIN_TAG_SIZE` to
+    /// `BLOCK_SIZE` bytes, in which case only that many leading bytes of the
+    /// computed tag are compared.
+    ///
+    /// Returns `Error::TagMismatch` if the tags don't match or the expected
+    /// tag is the wrong size, or `Error::AlreadyFinished` if the MAC has
+    /// already been finished (it must be reset before reusing)
+    pub fn verify(&mut self, expected: &[u8]) -> Result<(), Error> {
+        let actual = self.finish()?;
+
+        if expected.len() < MIN_TAG_SIZE {
+            return Err(Error::TagMismatch);
+        }
+
+        if bool::from(truncated_ct_eq(&actual, expected)) {
+            Ok(())
+        } else {
+            Err(Error::TagMismatch)
+        }
+    }
+
+    /// Finish computing CMAC, writing the leading `out.len()` bytes of the
+    /// tag into `out`. The tag itself is computed in full: truncation only
+    /// affects the output. `verify_truncated` accepts the same lengths.
+    ///
+    /// Returns `Error::BlockSize` (without finishing) if `out` is empty or
+    /// longer than `BLOCK_SIZE`, or `Error::AlreadyFinished` if the MAC has
+    /// already been finished (it must be reset before reusing)
+    pub fn finish_truncated(&mut self, out: &mut [u8]) -> Result<(), Error> {
+        if out.is_empty() || out.len() > BLOCK_SIZE {
+            return Err(Error::BlockSize { len: out.len() });
+        }
+
+        let tag = self.finish()?;
+        out.copy_from_slice(&tag.as_ref()[..out.len()]);
+        Ok(())
+    }
+
+    /// Finish computing CMAC and compare its leading `expected.len()` bytes
+    /// against a truncated tag in constant time. Unlike `verify`, this accepts
+    /// tags shorter than `MIN_TAG_SIZE` for interop with legacy protocols.
+    ///
+    /// Returns `Error::TagMismatch` if the tags don't match or the expected
+    /// tag is empty or longer than `BLOCK_SIZE`, or `Error::AlreadyFinished`
+    /// if the MAC has already been finished (it must be reset before reusing)
+    pub fn verify_truncated(&mut self, expected: &[u8]) -> Result<(), Error> {
+        let actual = self.finish()?;
+
+        if bool::from(truncated_ct_eq(&actual, expected)) {
+            Ok(())
+        } else {
+            Err(Error::TagMismatch)
+        }
+    }
+}
+
+/// Compare the leading `expected.len()` bytes of a tag against `expected` in
+/// constant time. Empty tags and tags longer than `BLOCK_SIZE` never match
+#[inline]
+fn truncated_ct_eq(tag: &Tag, expected: &[u8]) -> Choice {
+    let len = expected.len();
+
+    if len == 0 || len > BLOCK_SIZE {
+        return Choice::from(0);
+    }
+
+    tag.as_ref()[..len].ct_eq(expected)
+}
+
+/// CMAC with AES-128
+pub type Aes128Cmac = Cmac<Aes128>;
+
+/// CMAC with AES-192
+pub type Aes192Cmac = Cmac<Aes192>;
+
+/// CMAC with AES-256
+pub type Aes256Cmac = Cmac<Aes256>;
+
+impl Aes128Cmac {
+    /// Create a new AES-128-CMAC instance from a 16-byte key
+    #[inline]
+    pub fn new_from_key(key: &[u8; 16]) -> Self {
+        Cmac::new(Aes128::new(key))
+    }
+}
+
+impl Aes192Cmac {
+    /// Create a new AES-192-CMAC instance from a 24-byte key
+    #[inline]
+    pub fn new_from_key(key: &[u8; 24]) -> Self {
+        Cmac::new(Aes192::new(key))
+    }
+}
+
+impl Aes256Cmac {
+    /// Create a new AES-256-CMAC instance from a 32-byte key
+    #[inline]
+    pub fn new_from_key(key: &[u8; 32]) -> Self {
+        Cmac::new(Aes256::new(key))
+    }
+}
+
+/// Compute the CMAC of the given message in a single step
+#[inline]
+pub fn cmac<C: BlockCipher>(cipher: C, msg: &[u8]) -> Tag {
+    let mut mac = Cmac::new(cipher);
+    mac.update(msg).expect("new MAC isn't finished");
+    mac.finish().expect("new MAC isn't finished")
+}
+
+impl<C: BlockCipher> Mac<C> for Cmac<C> {
+    type Key = CmacKey<C>;
+
+    #[inline]
+    fn new_key(cipher: C) -> CmacKey<C> {
+        CmacKey::new(cipher)
+    }
+
+    #[inline]
+    fn from_key(key: &CmacKey<C>) -> Self {
+        key.mac()
+    }
+
+    #[inline]
+    fn reset(&mut self) {
+        Cmac::reset(self)
+    }
+
+    #[inline]
+    fn update(&mut self, msg: &[u8]) {
+        Cmac::update(self, msg).expect("already finished")
+    }
+
+    #[inline]
+    fn finish(&mut self) -> Block {
+        self.finish_block().expect("already finished")
+    }
+
+    #[inline]
+    fn finish_reset(&mut self) -> Block {
+        let tag = Mac::finish(self);
+        Cmac::reset(self);
+        tag
+    }
+}
+
+#[cfg(feature = "crypto-mac")]
+impl<C: BlockCipher> crypto_mac::Mac for Cmac<C> {
+    type OutputSize = U16;
+
+    /// Panics if the MAC was already finished via the inherent `finish` method
+    #[inline]
+    fn update(&mut self, data: &[u8]) {
+        Cmac::update(self, data).expect("already finished")
+    }
+
+    #[inline]
+    fn reset(&mut self) {
+        Cmac::reset(self)
+    }
+
+    #[inline]
+    fn finalize(mut self) -> Output<Self> {
+        let tag = self.finish().expect("already finished");
+        Output::new(GenericArray::clone_from_slice(tag.as_ref()))
+    }
+
+    /// Compute the tag and reset the MAC, clearing its finished state so it
+    /// can be reused
+    #[inline]
+    fn finalize_reset(&mut self) -> Output<Self> {
+        let tag = self.finish().expect("already finished");
+        self.reset();
+        Output::new(GenericArray::clone_from_slice(tag.as_ref()))
+    }
+
+    /// Check the tag in constant time, returning an error if it doesn't match.
+    /// Unlike the inherent `verify`, only full-length tags are accepted.
+    fn verify(mut self, tag: &[u8]) -> Result<(), crypto_mac::MacError> {
+        if tag.len() != BLOCK_SIZE {
+            return Err(crypto_mac::MacError);
+        }
+
+        Cmac::verify(&mut self, tag).map_err(|_| crypto_mac::MacError)
+    }
+}
+
+#[cfg(feature = "crypto-mac")]
+impl crypto_mac::NewMac for Cmac<Aes128> {
+    type KeySize = U16;
+
+    #[inline]
+    fn new(key: &Key<Self>) -> Self {
+        Self::new_from_key(array_ref!(key, 0, 16))
+    }
+}
+
+#[cfg(feature = "crypto-mac")]
+impl crypto_mac::NewMac for Cmac<Aes192> {
+    type KeySize = U24;
+
+    #[inline]
+    fn new(key: &Key<Self>) -> Self {
+        Self::new_from_key(array_ref!(key, 0, 24))
+    }
+}
+
+#[cfg(feature = "crypto-mac")]
+impl crypto_mac::NewMac for Cmac<Aes256> {
+    type KeySize = U32;
+
+    #[inline]
+    fn new(key: &Key<Self>) -> Self {
+        Self::new_from_key(array_ref!(key, 0, 32))
+    }
+}
+
+/// Feed data written to the MAC into `update`, e.g. as the destination of
+/// `io::copy`. Once done, call `finish` to obtain the tag.
+#[cfg(feature = "std")]
+impl<C: BlockCipher> io::Write for Cmac<C> {
+    /// Update the CMAC state with the entire buffer
+    ///
+    /// Returns an error of kind `Other` if the MAC has already been finished
+    /// (it must be reset before reusing)
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf)
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "already finished"))?;
+
+        Ok(buf.len())
+    }
+
+    /// Data is never buffered outside the MAC state, so this does nothing
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<C: BlockCipher> Zeroize for Cmac<C> {
+    /// Wipe the cipher's key schedule, the subkeys, and the MAC state
+    fn zeroize(&mut self) {
+        self.cipher.zeroize();
+        self.subkey1.zeroize();
+        self.subkey2.zeroize();
+        self.state.zeroize();
+        self.state_pos.zeroize();
+        self.finished.zeroize();
+    }
+}
+
+impl<C: BlockCipher> fmt::Debug for Cmac<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Cmac(...redacted...)")
+    }
+}
+
+impl<C: BlockCipher> Drop for Cmac<C> {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
+impl<C: BlockCipher> ZeroizeOnDrop for Cmac<C> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{cmac, Aes128Cmac, Aes192Cmac, Cmac, CmacKey, MIN_TAG_SIZE};
+    use error::Error;
+    use core::{cmp, mem, ptr};
+    use internals::{Aes128, Aes192, Block, BLOCK_SIZE};
+    use internals::test_rng::XorShift64;
+    #[cfg(feature = "std")]
+    use proptest::collection::vec;
+    #[cfg(feature = "std")]
+    use proptest::prelude::*;
+    #[cfg(feature = "std")]
+    use std::io;
+    #[cfg(feature = "std")]
+    use std::vec::Vec;
+    use zeroize::Zeroize;
+
+    /// RFC 4493 Section 4 example key
+    static RFC4493_KEY: [u8; 16] = [
+        0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6,
+        0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c,
+    ];
+
+    /// RFC 4493 Section 4 example message, truncated to each example's length
+    static RFC4493_MESSAGE: [u8; 64] = [
+        0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96,
+        0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a,
+        0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c,
+        0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf, 0x8e, 0x51,
+        0x30, 0xc8, 0x1c, 0x46, 0xa3, 0x5c, 0xe4, 0x11,
+        0xe5, 0xfb, 0xc1, 0x19, 0x1a, 0x0a, 0x52, 0xef,
+        0xf6, 0x9f, 0x24, 0x45, 0xdf, 0x4f, 0x9b, 0x17,
+        0xad, 0x2b, 0x41, 0x7b, 0xe6, 0x6c, 0x37, 0x10,
+    ];
+
+    /// NIST SP 800-38B Appendix D.2 AES-192 example key. The examples use the
+    /// same message as RFC 4493.
+    static SP800_38B_AES192_KEY: [u8; 24] = [
+        0x8e, 0x73, 0xb0, 0xf7, 0xda, 0x0e, 0x64, 0x52,
+        0xc8, 0x10, 0xf3, 0x2b, 0x80, 0x90, 0x79, 0xe5,
+        0x62, 0xf8, 0xea, 0xd2, 0x52, 0x2c, 0x6b, 0x7b,
+    ];
+
+    /// NIST SP 800-38B Appendix D.2 example message lengths and their tags
+    static SP800_38B_AES192_TAGS: [(usize, Block); 4] = [
+        (
+            0,
+            Block::from_bytes([
+                0xd1, 0x7d, 0xdf, 0x46, 0xad, 0xaa, 0xcd, 0xe5,
+                0x31, 0xca, 0xc4, 0x83, 0xde, 0x7a, 0x93, 0x67,
+            ]),
+        ),
+        (
+            16,
+            Block::from_bytes([
+                0x9e, 0x99, 0xa7, 0xbf, 0x31, 0xe7, 0x10, 0x90,
+                0x06, 0x62, 0xf6, 0x5e, 0x61, 0x7c, 0x51, 0x84,
+            ]),
+        ),
+        (
+            40,
+            Block::from_bytes([
+                0x8a, 0x1d, 0xe5, 0xbe, 0x2e, 0xb3, 0x1a, 0xad,
+                0x08, 0x9a, 0x82, 0xe6, 0xee, 0x90, 0x8b, 0x0e,
+            ]),
+        ),
+        (
+            64,
+            Block::from_bytes([
+                0xa1, 0xd5, 0xdf, 0x0e, 0xed, 0x79, 0x0f, 0x79,
+                0x4d, 0x77, 0x58, 0x96, 0x59, 0xf3, 0x9a, 0x11,
+            ]),
+        ),
+    ];
+
+    /// RFC 4493 Section 4 example message lengths and their tags
+    static RFC4493_TAGS: [(usize, Block); 4] = [
+        (
+            0,
+            Block::from_bytes([
+                0xbb, 0x1d, 0x69, 0x29, 0xe9, 0x59, 0x37, 0x28,
+                0x7f, 0xa3, 0x7d, 0x12, 0x9b, 0x75, 0x67, 0x46,
+            ]),
+        ),
+        (
+            16,
+            Block::from_bytes([
+                0x07, 0x0a, 0x16, 0xb4, 0x6b, 0x4d, 0x41, 0x44,
+                0xf7, 0x9b, 0xdd, 0x9d, 0xd0, 0x4a, 0x28, 0x7c,
+            ]),
+        ),
+        (
+            40,
+            Block::from_bytes([
+                0xdf, 0xa6, 0x67, 0x47, 0xde, 0x9a, 0xe6, 0x30,
+                0x30, 0xca, 0x32, 0x61, 0x14, 0x97, 0xc8, 0x27,
+            ]),
+        ),
+        (
+            64,
+            Block::from_bytes([
+                0x51, 0xf0, 0xbe, 0xbf, 0x7e, 0x3b, 0x9d, 0x92,
+                0xfc, 0x49, 0x74, 0x17, 0x79, 0x36, 0x3c, 0xfe,
+            ]),
+        ),
+    ];
+
+    #[test]
+    fn zeroize_subkeys_and_state() {
+        let mut cmac = Cmac::new(Aes128::new(&[0x42u8; 16]));
+        cmac.update(b"a partial block").unwrap();
+        cmac.zeroize();
+
+        assert_eq!(cmac.subkey1.as_ref(), &[0u8; BLOCK_SIZE]);
+        assert_eq!(cmac.subkey2.as_ref(), &[0u8; BLOCK_SIZE]);
+        assert_eq!(cmac.state.as_ref(), &[0u8; BLOCK_SIZE]);
+        assert_eq!(cmac.state_pos, 0);
+    }
+
+    #[test]
+    fn cmac_key_zeroize_on_drop() {
+        let mut key = mem::ManuallyDrop::new(CmacKey::new(Aes128::new(&RFC4493_KEY)));
+
+        unsafe {
+            ptr::drop_in_place(&mut *key);
+
+            assert_eq!(key.subkey1.as_ref(), &[0u8; BLOCK_SIZE]);
+            assert_eq!(key.subkey2.as_ref(), &[0u8; BLOCK_SIZE]);
+
+            for round_key in key.cipher.round_keys() {
+                assert_eq!(round_key.as_ref(), &[0u8; BLOCK_SIZE]);
+            }
+        }
+    }
+
+    #[test]
+    fn zeroize_on_drop() {
+        let mut cmac = mem::ManuallyDrop::new(Cmac::new(Aes128::new(&RFC4493_KEY)));
+        cmac.update(&RFC4493_MESSAGE[..40]).unwrap();
+
+        unsafe {
+            ptr::drop_in_place(&mut *cmac);
+
+            assert_eq!(cmac.subkey1.as_ref(), &[0u8; BLOCK_SIZE]);
+            assert_eq!(cmac.subkey2.as_ref(), &[0u8; BLOCK_SIZE]);
+            assert_eq!(cmac.state.as_ref(), &[0u8; BLOCK_SIZE]);
+
+            for round_key in cmac.cipher.round_keys() {
+                assert_eq!(round_key.as_ref(), &[0u8; BLOCK_SIZE]);
+            }
+        }
+    }
+
+    #[test]
+    fn verify_tag() {
+        let mut cmac = Cmac::new(Aes128::new(&[0x42u8; 16]));
+        cmac.update(b"authenticated message").unwrap();
+        let tag = cmac.finish().unwrap();
+
+        cmac.reset();
+        cmac.update(b"authenticated message").unwrap();
+        assert_eq!(cmac.verify(tag.as_ref()), Ok(()));
+    }
+
+    #[test]
+    fn verify_rejects_modified_tag() {
+        let mut cmac = Cmac::new(Aes128::new(&[0x42u8; 16]));
+        cmac.update(b"authenticated message").unwrap();
+        let mut tag = [0u8; BLOCK_SIZE];
+        tag.copy_from_slice(cmac.finish().unwrap().as_ref());
+        tag[BLOCK_SIZE - 1] ^= 1;
+
+        cmac.reset();
+        cmac.update(b"authenticated message").unwrap();
+        assert_eq!(cmac.verify(&tag), Err(Error::TagMismatch));
+    }
+
+    #[test]
+    fn verify_flipped_bits() {
+        let tag = RFC4493_TAGS[3].1.as_ref();
+
+        for len in MIN_TAG_SIZE..(BLOCK_SIZE + 1) {
+            let mut mac = Cmac::new(Aes128::new(&RFC4493_KEY));
+            mac.update(&RFC4493_MESSAGE).unwrap();
+            assert_eq!(mac.verify(&tag[..len]), Ok(()), "{}-byte tag", len);
+
+            for i in 0..len {
+                for bit in 0..8 {
+                    let mut forged = [0u8; BLOCK_SIZE];
+                    forged.copy_from_slice(tag);
+                    forged[i] ^= 1 << bit;
+
+                    mac.reset();
+                    mac.update(&RFC4493_MESSAGE).unwrap();
+                    assert_eq!(
+                        mac.verify(&forged[..len]),
+                        Err(Error::TagMismatch),
+                        "{}-byte tag with byte {} bit {} flipped",
+                        len,
+                        i,
+                        bit
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn verify_rejects_wrong_length_tag() {
+        let mut cmac = Cmac::new(Aes128::new(&[0x42u8; 16]));
+        cmac.update(b"authenticated message").unwrap();
+        let tag = cmac.finish().unwrap();
+
+        for len in &[0, 1, MIN_TAG_SIZE - 1] {
+            cmac.reset();
+            cmac.update(b"authenticated message").unwrap();
+            assert_eq!(cmac.verify(&tag.as_ref()[..*len]), Err(Error::TagMismatch));
+        }
+
+        cmac.reset();
+        cmac.update(b"authenticated message").unwrap();
+        let mut long_tag = [0u8; BLOCK_SIZE + 1];
+        long_tag[..BLOCK_SIZE].copy_from_slice(tag.as_ref());
+        assert_eq!(cmac.verify(&long_tag), Err(Error::TagMismatch));
+    }
+
+    #[test]
+    fn clone_after_unaligned_prefix() {
+        let mut prefix_mac = Cmac::new(Aes128::new(&RFC4493_KEY));
+        prefix_mac.update(&RFC4493_MESSAGE[..23]).unwrap();
+
+        let mut branch1 = prefix_mac.clone();
+        let mut branch2 = prefix_mac.clone();
+        branch1.update(&RFC4493_MESSAGE[23..40]).unwrap();
+        branch2.update(&RFC4493_MESSAGE[23..]).unwrap();
+
+        let expected1 = cmac(Aes128::new(&RFC4493_KEY), &RFC4493_MESSAGE[..40]);
+        let expected2 = cmac(Aes128::new(&RFC4493_KEY), &RFC4493_MESSAGE);
+        assert_eq!(branch1.finish().unwrap().as_ref(), expected1.as_ref());
+        assert_eq!(branch2.finish().unwrap().as_ref(), expected2.as_ref());
+        assert_eq!(expected1.as_ref(), RFC4493_TAGS[2].1.as_ref());
+        assert_eq!(expected2.as_ref(), RFC4493_TAGS[3].1.as_ref());
+
+        // The original is unaffected by its clones, and clones of a finished
+        // MAC are finished too
+        prefix_mac.update(&RFC4493_MESSAGE[23..40]).unwrap();
+        assert_eq!(prefix_mac.finish().unwrap().as_ref(), expected1.as_ref());
+        assert_eq!(prefix_mac.clone().finish().err(), Some(Error::AlreadyFinished));
+    }
+
+    #[test]
+    fn clone_mid_stream() {
+        let key = [0x42u8; 16];
+        let prefix = b"a shared prefix spanning more than one block";
+
+        let mut cmac1 = Cmac::new(Aes128::new(&key));
+        cmac1.update(prefix).unwrap();
+
+        let mut cmac2 = cmac1.clone();
+        cmac1.update(b"first suffix").unwrap();
+        cmac2.update(b"a different second suffix").unwrap();
+
+        let mut reference = Cmac::new(Aes128::new(&key));
+        reference.update(prefix).unwrap();
+        reference.update(b"first suffix").unwrap();
+        assert_eq!(cmac1.finish().unwrap().as_ref(), reference.finish().unwrap().as_ref());
+
+        reference.reset();
+        reference.update(prefix).unwrap();
+        reference.update(b"a different second suffix").unwrap();
+        assert_eq!(cmac2.finish().unwrap().as_ref(), reference.finish().unwrap().as_ref());
+    }
+
+    #[test]
+    fn rfc4493_examples() {
+        for &(len, ref expected) in RFC4493_TAGS.iter() {
+            let tag = cmac(Aes128::new(&RFC4493_KEY), &RFC4493_MESSAGE[..len]);
+            assert_eq!(tag.as_ref(), expected.as_ref(), "message length {}", len);
+        }
+    }
+
+    #[test]
+    fn cmac_key_matches_new() {
+        let key = CmacKey::new(Aes128::new(&RFC4493_KEY));
+        let reference = Cmac::new(Aes128::new(&RFC4493_KEY));
+        assert_eq!(key.subkey1.as_ref(), reference.subkey1.as_ref());
+        assert_eq!(key.subkey2.as_ref(), reference.subkey2.as_ref());
+
+        // Every MAC created from the key starts fresh, even after earlier
+        // ones have been used and finished
+        for _ in 0..2 {
+            for &(len, ref expected) in RFC4493_TAGS.iter() {
+                let mut mac = key.mac();
+                mac.update(&RFC4493_MESSAGE[..len]).unwrap();
+                assert_eq!(mac.finish().unwrap().as_ref(), expected.as_ref());
+
+                let mut reference = Cmac::new(Aes128::new(&RFC4493_KEY));
+                reference.update(&RFC4493_MESSAGE[..len]).unwrap();
+                assert_eq!(reference.finish().unwrap().as_ref(), expected.as_ref());
+            }
+        }
+    }
+
+    #[test]
+    fn chain_update_matches_update() {
+        for &(len, ref expected) in RFC4493_TAGS.iter() {
+            for split in 0..(len + 1) {
+                let (header, body) = RFC4493_MESSAGE[..len].split_at(split);
+
+                let mut mac = Aes128Cmac::new_from_key(&RFC4493_KEY);
+                mac.update(header).unwrap();
+                mac.update(body).unwrap();
+                assert_eq!(mac.finish().unwrap().as_ref(), expected.as_ref());
+
+                let tag = Aes128Cmac::new_from_key(&RFC4493_KEY)
+                    .chain_update(header)
+                    .and_then(|mac| mac.chain_update(body))
+                    .and_then(|mut mac| mac.finish())
+                    .unwrap();
+                assert_eq!(tag.as_ref(), expected.as_ref());
+            }
+        }
+
+        for &(len, ref expected) in SP800_38B_AES192_TAGS.iter() {
+            let tag = Aes192Cmac::new_from_key(&SP800_38B_AES192_KEY)
+                .chain_update(&RFC4493_MESSAGE[..len])
+                .and_then(|mut mac| mac.finish())
+                .unwrap();
+            assert_eq!(tag.as_ref(), expected.as_ref());
+        }
+    }
+
+    #[test]
+    fn chain_update_after_finish() {
+        let mut mac = Aes128Cmac::new_from_key(&RFC4493_KEY);
+        mac.finish().unwrap();
+        assert_eq!(mac.chain_update(b"more").err(), Some(Error::AlreadyFinished));
+    }
+
+    #[cfg(feature = "std")]
+    proptest! {
+        /// Batches of any size, with messages of any length (including
+        /// empty and partial final blocks), give the same tags as `cmac`
+        #[test]
+        fn compute_batch_matches_cmac(messages in vec(vec(any::<u8>(), 0..80), 0..20)) {
+            let key = CmacKey::new(Aes128::new(&RFC4493_KEY));
+            let slices: Vec<&[u8]> = messages.iter().map(|msg| msg.as_slice()).collect();
+
+            let tags = Cmac::compute_batch(&key, &slices);
+            prop_assert_eq!(tags.len(), messages.len());
+
+            for (tag, msg) in tags.iter().zip(slices.iter()) {
+                let expected = cmac(Aes128::new(&RFC4493_KEY), msg);
+                prop_assert_eq!(tag.as_ref(), expected.as_ref());
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn compute_batch_rfc4493_examples() {
+        let key = CmacKey::new(Aes128::new(&RFC4493_KEY));
+        let mut messages: Vec<&[u8]> = Vec::new();
+
+        for i in 0..2 * 8 + 3 {
+            messages.push(&RFC4493_MESSAGE[..RFC4493_TAGS[i % 4].0]);
+        }
+
+        for (i, tag) in Cmac::compute_batch(&key, &messages).iter().enumerate() {
+            assert_eq!(tag.as_ref(), RFC4493_TAGS[i % 4].1.as_ref());
+        }
+    }
+
+    #[test]
+    fn finish_into_wipes_state() {
+        for &(len, ref expected) in RFC4493_TAGS.iter() {
+            let mut mac = Cmac::new(Aes128::new(&RFC4493_KEY));
+            mac.update(&RFC4493_MESSAGE[..len]).unwrap();
+
+            let mut tag = [0u8; BLOCK_SIZE];
+            mac.finish_into(&mut tag).unwrap();
+
+            assert_eq!(&tag[..], expected.as_ref());
+            assert_eq!(mac.state.as_ref(), &[0u8; BLOCK_SIZE]);
+            assert_eq!(mac.finish_into(&mut tag).err(), Some(Error::AlreadyFinished));
+        }
+    }
+
+    #[test]
+    fn finish_reset_back_to_back() {
+        let mut mac = Cmac::new(Aes128::new(&RFC4493_KEY));
+
+        for _ in 0..2 {
+            for &(len, ref expected) in RFC4493_TAGS.iter() {
+                mac.update(&RFC4493_MESSAGE[..len]).unwrap();
+                let tag = mac.finish_reset();
+
+                let mut fresh = Cmac::new(Aes128::new(&RFC4493_KEY));
+                fresh.update(&RFC4493_MESSAGE[..len]).unwrap();
+
+                assert_eq!(tag, fresh.finish().unwrap());
+                assert_eq!(tag.as_ref(), expected.as_ref());
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn finish_reset_panics_if_finished() {
+        let mut mac = Cmac::new(Aes128::new(&RFC4493_KEY));
+        mac.finish().unwrap();
+        mac.finish_reset();
+    }
+
+    #[test]
+    fn rekey_interleaved_keys() {
+        let other_key = [0x42u8; 16];
+        let mut mac = Cmac::new(Aes128::new(&RFC4493_KEY));
+
+        for &(len, ref expected) in RFC4493_TAGS.iter() {
+            // Leave the MAC mid-message before switching keys
+            mac.update(&RFC4493_MESSAGE[..len]).unwrap();
+            mac.rekey(Aes128::new(&other_key));
+            mac.update(&RFC4493_MESSAGE[..len]).unwrap();
+
+            let fresh = cmac(Aes128::new(&other_key), &RFC4493_MESSAGE[..len]);
+            assert_eq!(mac.finish().unwrap().as_ref(), fresh.as_ref());
+
+            // Rekeying also clears the finished state
+            mac.rekey(Aes128::new(&RFC4493_KEY));
+            mac.update(&RFC4493_MESSAGE[..len]).unwrap();
+            assert_eq!(mac.finish().unwrap().as_ref(), expected.as_ref());
+
+            mac.rekey(Aes128::new(&RFC4493_KEY));
+        }
+
+        let fresh = Cmac::new(Aes128::new(&other_key));
+        mac.rekey(Aes128::new(&other_key));
+        assert_eq!(mac.subkey1.as_ref(), fresh.subkey1.as_ref());
+        assert_eq!(mac.subkey2.as_ref(), fresh.subkey2.as_ref());
+    }
+
+    #[test]
+    fn sp800_38b_aes192_examples() {
+        for &(len, ref expected) in SP800_38B_AES192_TAGS.iter() {
+            let tag = cmac(Aes192::new(&SP800_38B_AES192_KEY), &RFC4493_MESSAGE[..len]);
+            assert_eq!(tag.as_ref(), expected.as_ref(), "message length {}", len);
+        }
+    }
+
+    /// Reader which returns at most 3 bytes per call, then an optional error
+    #[cfg(feature = "std")]
+    struct TrickleReader<'a> {
+        data: &'a [u8],
+        error: Option<io::ErrorKind>,
+    }
+
+    #[cfg(feature = "std")]
+    impl<'a> io::Read for TrickleReader<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.data.is_empty() {
+                return match self.error.take() {
+                    Some(kind) => Err(io::Error::new(kind, "trickle")),
+                    None => Ok(0),
+                };
+            }
+
+            let n = *[3, buf.len(), self.data.len()].iter().min().unwrap();
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn update_from_reader_rfc4493_examples() {
+        for &(len, ref expected) in RFC4493_TAGS.iter() {
+            let mut mac = Cmac::new(Aes128::new(&RFC4493_KEY));
+            let mut reader = TrickleReader {
+                data: &RFC4493_MESSAGE[..len],
+                error: Some(io::ErrorKind::Interrupted),
+            };
+
+            mac.update_from_reader(&mut reader).unwrap();
+            assert_eq!(mac.finish().unwrap().as_ref(), expected.as_ref(), "message length {}", len);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn update_from_reader_propagates_errors() {
+        let mut mac = Cmac::new(Aes128::new(&RFC4493_KEY));
+        let mut reader = TrickleReader {
+            data: &RFC4493_MESSAGE,
+            error: Some(io::ErrorKind::UnexpectedEof),
+        };
+
+        let err = mac.update_from_reader(&mut reader).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn io_copy_matches_update() {
+        // Not a multiple of io::copy's buffer size or the block size
+        let mut data = vec![0u8; 3 * 1024 * 1024 + 7];
+        for (i, byte) in data.iter_mut().enumerate() {
+            *byte = (i as u8).wrapping_mul(0x1d) ^ (i >> 8) as u8;
+        }
+
+        let mut expected = Cmac::new(Aes128::new(&RFC4493_KEY));
+        expected.update(&data).unwrap();
+
+        let mut mac = Cmac::new(Aes128::new(&RFC4493_KEY));
+        let copied = io::copy(&mut io::Cursor::new(&data[..]), &mut mac).unwrap();
+        io::Write::flush(&mut mac).unwrap();
+
+        assert_eq!(copied, data.len() as u64);
+        assert_eq!(mac.finish().unwrap().as_ref(), expected.finish().unwrap().as_ref());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn write_after_finish() {
+        let mut mac = Cmac::new(Aes128::new(&RFC4493_KEY));
+        mac.finish().unwrap();
+
+        let err = io::Write::write(&mut mac, &RFC4493_MESSAGE).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn update_from_reader_after_finish() {
+        let mut mac = Cmac::new(Aes128::new(&RFC4493_KEY));
+        mac.finish().unwrap();
+
+        let mut reader = TrickleReader {
+            data: &RFC4493_MESSAGE,
+            error: None,
+        };
+
+        let err = mac.update_from_reader(&mut reader).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+    }
+
+    #[test]
+    fn finish_truncated() {
+        for &(msg_len, ref expected) in RFC4493_TAGS.iter() {
+            for len in 1..(BLOCK_SIZE + 1) {
+                let mut mac = Cmac::new(Aes128::new(&RFC4493_KEY));
+                mac.update(&RFC4493_MESSAGE[..msg_len]).unwrap();
+
+                let mut out = [0u8; BLOCK_SIZE];
+                mac.finish_truncated(&mut out[..len]).unwrap();
+
+                assert_eq!(&out[..len], &expected.as_ref()[..len]);
+                assert_eq!(&out[len..], &[0u8; BLOCK_SIZE][len..]);
+
+                mac.reset();
+                mac.update(&RFC4493_MESSAGE[..msg_len]).unwrap();
+                assert_eq!(mac.verify_truncated(&out[..len]), Ok(()));
+            }
+        }
+    }
+
+    #[test]
+    fn finish_truncated_rejects_invalid_length() {
+        let mut mac = Cmac::new(Aes128::new(&RFC4493_KEY));
+        mac.update(&RFC4493_MESSAGE).unwrap();
+
+        let mut out = [0u8; BLOCK_SIZE + 1];
+        assert_eq!(
+            mac.finish_truncated(&mut out),
+            Err(Error::BlockSize { len: BLOCK_SIZE + 1 })
+        );
+        assert_eq!(mac.finish_truncated(&mut out[..0]), Err(Error::BlockSize { len: 0 }));
+
+        // The MAC wasn't finished, so it can still produce the full tag
+        assert_eq!(mac.finish().unwrap().as_ref(), RFC4493_TAGS[3].1.as_ref());
+    }
+
+    #[test]
+    fn verify_truncated() {
+        let tag = RFC4493_TAGS[3].1.as_ref();
+
+        for len in 1..(BLOCK_SIZE + 1) {
+            let mut mac = Cmac::new(Aes128::new(&RFC4493_KEY));
+            mac.update(&RFC4493_MESSAGE).unwrap();
+            assert_eq!(mac.verify_truncated(&tag[..len]), Ok(()));
+
+            let mut forged = [0u8; BLOCK_SIZE];
+            forged.copy_from_slice(tag);
+            forged[len - 1] ^= 0x80;
+
+            mac.reset();
+            mac.update(&RFC4493_MESSAGE).unwrap();
+            assert_eq!(mac.verify_truncated(&forged[..len]), Err(Error::TagMismatch));
+        }
+
+        let mut mac = Cmac::new(Aes128::new(&RFC4493_KEY));
+        mac.update(&RFC4493_MESSAGE).unwrap();
+        assert_eq!(mac.verify_truncated(&[]), Err(Error::TagMismatch));
+    }
+
+    #[test]
+    fn finish_twice() {
+        let mut mac = Cmac::new(Aes128::new(&RFC4493_KEY));
+        mac.update(&RFC4493_MESSAGE).unwrap();
+        mac.finish().unwrap();
+
+        assert_eq!(mac.finish().err(), Some(Error::AlreadyFinished));
+        assert_eq!(mac.verify(RFC4493_TAGS[3].1.as_ref()), Err(Error::AlreadyFinished));
+        assert_eq!(mac.finish_truncated(&mut [0u8; 8]), Err(Error::AlreadyFinished));
+    }
+
+    #[test]
+    fn update_after_finish() {
+        let mut mac = Cmac::new(Aes128::new(&RFC4493_KEY));
+        mac.finish().unwrap();
+
+        assert_eq!(mac.update(&RFC4493_MESSAGE), Err(Error::AlreadyFinished));
+
+        // Resetting allows the MAC to be reused
+        mac.reset();
+        mac.update(&RFC4493_MESSAGE).unwrap();
+        assert_eq!(mac.finish().unwrap().as_ref(), RFC4493_TAGS[3].1.as_ref());
+    }
+
+    #[test]
+    fn update_chunking_matches_single_shot() {
+        let mut rng = XorShift64::new(0x9e37_79b9_7f4a_7c15);
+        let mut msg = [0u8; 1000];
+        rng.fill(&mut msg);
+
+        for len in 0..msg.len() + 1 {
+            if len > 4 * BLOCK_SIZE && len % 37 != 0 {
+                continue;
+            }
+
+            let expected = cmac(Aes128::new(&RFC4493_KEY), &msg[..len]);
+
+            for _ in 0..8 {
+                let mut mac = Cmac::new(Aes128::new(&RFC4493_KEY));
+                let mut pos = 0;
+
+                while pos < len {
+                    // Favour block multiples so the aligned fast path is hit
+                    let chunk = match rng.next_u64() % 4 {
+                        0 => (rng.next_u64() % 4) as usize * BLOCK_SIZE,
+                        1 => BLOCK_SIZE - (pos % BLOCK_SIZE),
+                        _ => (rng.next_u64() % (3 * BLOCK_SIZE as u64 + 2)) as usize,
+                    };
+                    let end = cmp::min(pos + chunk, len);
+
+                    mac.update(&msg[pos..end]).unwrap();
+                    pos = end;
+                }
+
+                assert_eq!(mac.finish().unwrap().as_ref(), expected.as_ref(), "len={}", len);
+            }
+        }
     }
 }
@@ -1,73 +1,1264 @@
 //! `internals/ctr.rs`: Counter Mode encryption/decryption
 
-use super::{Block, BlockCipher, BLOCK_SIZE};
-use byteorder::{BigEndian, ByteOrder};
+use super::{Aes128, Aes256, Block, Block8, BlockCipher, BlockMut, BLOCK8_SIZE, BLOCK_SIZE};
+use super::xor;
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+#[cfg(feature = "cipher")]
+use cipher::{CipherKey, NewCipher, Nonce, SeekNum, StreamCipher, StreamCipherSeek};
+#[cfg(feature = "cipher")]
+use cipher::errors::{LoopError, OverflowError};
+#[cfg(feature = "cipher")]
+use cipher::generic_array::typenum::{U16, U32};
+use core::fmt;
+use error::Error;
+#[cfg(feature = "std")]
+use std::io;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// How the counter block is incremented between blocks of keystream
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CounterMode {
+    /// The whole block is a 128-bit big endian counter, as described in
+    /// NIST SP 800-38A. This is the mode used internally by SIV, and the
+    /// default. The keystream ends once the counter reaches all `0xFF`
+    /// bytes, rather than wrapping around and repeating.
+    BigEndian,
+
+    /// The first 4 bytes of the block are a 32-bit little endian counter,
+    /// and the remaining 12 bytes are left unchanged. This is the mode used
+    /// by AES-GCM-SIV (RFC 8452), which specifies that the counter wraps
+    /// around to zero.
+    LittleEndian32,
+}
+
+impl Default for CounterMode {
+    fn default() -> CounterMode {
+        CounterMode::BigEndian
+    }
+}
 
 /// Counter Mode encryption/decryption
+///
+/// By default this uses the same big endian counter as SIV, so the keystream
+/// is compatible with it. See `CounterMode` for other conventions.
+#[derive(Clone)]
 pub struct Ctr<C: BlockCipher> {
     cipher: C,
-    buffer: Block,
-    buffer_pos: usize,
+    state: CtrState,
 }
 
+/// AES-128 in CTR mode, by default with a 128-bit big endian counter
+pub type Aes128Ctr = Ctr<Aes128>;
+
+/// AES-256 in CTR mode, by default with a 128-bit big endian counter
+pub type Aes256Ctr = Ctr<Aes256>;
+
 impl<C: BlockCipher> Ctr<C> {
-    /// Create a new CTR instance with the given cipher
+    /// Create a new CTR instance with the given cipher and initial counter
+    /// value (IV), using a big endian counter
     #[inline]
-    pub fn new(cipher: C) -> Self {
+    pub fn new(cipher: C, iv: &Block) -> Self {
+        Self::with_counter_mode(cipher, iv, CounterMode::default())
+    }
+
+    /// Create a new CTR instance with the given cipher, initial counter value
+    /// (IV) and counter mode
+    #[inline]
+    pub fn with_counter_mode(cipher: C, iv: &Block, mode: CounterMode) -> Self {
         Self {
             cipher: cipher,
+            state: CtrState::new(iv, mode),
+        }
+    }
+
+    /// Reset back to the beginning of the keystream
+    pub fn reset(&mut self) {
+        self.state.seek(0);
+    }
+
+    /// Start a new keystream from the given IV, keeping the cipher (and its
+    /// expanded key schedule) so it can be reused across messages
+    pub fn reset_iv(&mut self, iv: &Block) {
+        self.state.iv.copy_from_block(iv);
+        self.reset();
+    }
+
+    /// Seek to the start of the block at the given offset in the keystream
+    /// (i.e. the IV plus `block_offset`)
+    ///
+    /// With a big endian counter, seeking past the end of the keystream
+    /// leaves nothing to process, so any further data will be rejected with
+    /// `Error::CounterOverflow`. The 32-bit little endian counter wraps around.
+    pub fn seek(&mut self, block_offset: u64) {
+        self.state.seek(block_offset);
+    }
+
+    /// Seek to the given byte offset in the keystream, so that subsequent
+    /// calls to `apply_keystream` produce the same output as if every
+    /// preceding byte had been processed
+    pub fn seek_to(&mut self, byte_offset: u64) {
+        self.state.seek_to(&self.cipher, byte_offset);
+    }
+
+    /// Current byte offset in the keystream (i.e. the offset `seek_to` would
+    /// need to be given to return here), modulo 2^64
+    pub fn current_position(&self) -> u64 {
+        self.state.position
+    }
+
+    /// Encrypt/decrypt the given data in-place by XORing it with the
+    /// keystream, picking up where any previous call left off
+    ///
+    /// Returns `Error::CounterOverflow` (leaving `data` untouched) if a big
+    /// endian counter would have to wrap around to process all of it, since
+    /// that would reuse keystream.
+    pub fn apply_keystream(&mut self, data: &mut [u8]) -> Result<(), Error> {
+        self.state.apply_keystream(&self.cipher, data)
+    }
+
+    /// Encrypt/decrypt the given data in-place with the keystream starting
+    /// at `iv`, as a fresh instance with the same cipher and counter mode
+    /// would, leaving this instance's own position untouched
+    ///
+    /// This only needs `&self`, and borrows the cipher rather than copying
+    /// its key schedule. The counter and any buffered keystream live on the
+    /// stack and are zeroized before returning.
+    ///
+    /// Returns `Error::CounterOverflow` under the same conditions as
+    /// `apply_keystream`.
+    pub fn apply_keystream_from_iv(&self, iv: &Block, data: &mut [u8]) -> Result<(), Error> {
+        CtrState::new(iv, self.state.mode).apply_keystream(&self.cipher, data)
+    }
+
+    /// Write the raw keystream into `out`, overwriting its contents and
+    /// advancing the counter exactly as `apply_keystream` would
+    ///
+    /// Returns `Error::CounterOverflow` under the same conditions as
+    /// `apply_keystream`, in which case `out` is zeroed.
+    pub fn keystream(&mut self, out: &mut [u8]) -> Result<(), Error> {
+        for b in out.iter_mut() {
+            *b = 0;
+        }
+
+        self.apply_keystream(out)
+    }
+
+    /// Encrypt/decrypt everything from `reader` until EOF into `writer`,
+    /// `buf_size` bytes at a time, returning the number of bytes processed
+    ///
+    /// This only applies the keystream: the output is unauthenticated and
+    /// malleable, so use it as a building block under SIV or STREAM (which
+    /// authenticate the data), never on its own.
+    ///
+    /// Short reads are handled, `Interrupted` errors are retried, and other
+    /// I/O errors are returned as-is. If the keystream runs out, an error of
+    /// kind `Other` is returned. Either way some output may already have been
+    /// written. The buffer is zeroized before returning.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buf_size` is zero.
+    #[cfg(feature = "std")]
+    pub fn transform<R, W>(
+        &mut self,
+        mut reader: R,
+        mut writer: W,
+        buf_size: usize,
+    ) -> io::Result<u64>
+    where
+        R: io::Read,
+        W: io::Write,
+    {
+        if buf_size == 0 {
+            panic!("buffer size must be nonzero");
+        }
+
+        let mut buf = vec![0u8; buf_size];
+        let result = self.transform_with_buffer(&mut reader, &mut writer, &mut buf);
+        buf.as_mut_slice().zeroize();
+
+        result
+    }
+
+    /// Pump `reader` through the keystream into `writer` as described in
+    /// `transform`, using the given buffer
+    #[cfg(feature = "std")]
+    fn transform_with_buffer<R, W>(
+        &mut self,
+        reader: &mut R,
+        writer: &mut W,
+        buf: &mut [u8],
+    ) -> io::Result<u64>
+    where
+        R: io::Read,
+        W: io::Write,
+    {
+        let mut processed: u64 = 0;
+
+        loop {
+            let n = match reader.read(buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            self.apply_keystream(&mut buf[..n])
+                .map_err(|_| io::Error::new(io::ErrorKind::Other, "CTR counter overflowed"))?;
+
+            writer.write_all(&buf[..n])?;
+            processed = processed.checked_add(n as u64).expect("overflow");
+        }
+
+        writer.flush()?;
+        Ok(processed)
+    }
+}
+
+/// Position in a CTR keystream, kept apart from the cipher so keystream can
+/// be generated with a borrowed cipher (see `Ctr::apply_keystream_from_iv`)
+#[derive(Clone)]
+struct CtrState {
+    mode: CounterMode,
+    iv: Block,
+    counter: Block,
+    buffer: Block,
+    buffer_pos: usize,
+    position: u64,
+    exhausted: bool,
+}
+
+impl CtrState {
+    /// Create a new keystream position at the start of the given IV
+    fn new(iv: &Block, mode: CounterMode) -> Self {
+        Self {
+            mode: mode,
+            iv: iv.clone(),
+            counter: iv.clone(),
             buffer: Block::new(),
             buffer_pos: BLOCK_SIZE,
+            position: 0,
+            exhausted: false,
         }
     }
 
-    /// Reset the internal cipher state back to its initial values
-    pub fn reset(&mut self) {
+    /// Seek to the start of the block at the given offset, as `Ctr::seek`
+    fn seek(&mut self, block_offset: u64) {
+        self.counter.copy_from_block(&self.iv);
+
+        {
+            let counter_bytes: &mut [u8] = self.counter.as_mut();
+
+            match self.mode {
+                CounterMode::BigEndian => {
+                    let counter = BigEndian::read_u128(counter_bytes);
+
+                    match counter.checked_add(block_offset as u128) {
+                        Some(counter) => {
+                            BigEndian::write_u128(counter_bytes, counter);
+                            self.exhausted = false;
+                        }
+                        None => self.exhausted = true,
+                    }
+                }
+                CounterMode::LittleEndian32 => {
+                    // The counter wraps modulo 2^32, so truncation is intended
+                    let counter = LittleEndian::read_u32(&counter_bytes[..4]);
+                    let counter = counter.wrapping_add(block_offset as u32);
+                    LittleEndian::write_u32(&mut counter_bytes[..4], counter);
+                    self.exhausted = false;
+                }
+            }
+        }
+
         self.buffer.clear();
         self.buffer_pos = BLOCK_SIZE;
+        self.position = block_offset.wrapping_mul(BLOCK_SIZE as u64);
     }
 
-    /// Encrypt/decrypt the given data in-place, updating the internal cipher state
-    ///
-    /// Accepts a mutable counter value, which is also updated in-place
-    pub fn transform(&mut self, counter: &mut Block, data: &mut [u8]) {
-        for b in data {
+    /// Seek to the given byte offset, as `Ctr::seek_to`
+    fn seek_to<C: BlockCipher>(&mut self, cipher: &C, byte_offset: u64) {
+        let block_size = BLOCK_SIZE as u64;
+        self.seek(byte_offset / block_size);
+
+        let skip = (byte_offset % block_size) as usize;
+
+        if skip > 0 && !self.exhausted {
+            self.next_keystream_block(cipher);
+            self.buffer_pos = skip;
+        }
+
+        self.position = byte_offset;
+    }
+
+    /// Apply the keystream generated by `cipher`, as `Ctr::apply_keystream`
+    fn apply_keystream<C>(&mut self, cipher: &C, data: &mut [u8]) -> Result<(), Error>
+    where
+        C: BlockCipher,
+    {
+        self.check_remaining(data.len())?;
+        self.position = self.position.wrapping_add(data.len() as u64);
+        let mut data_pos: usize = 0;
+
+        // Use up any keystream left over from a previous call
+        while self.buffer_pos < BLOCK_SIZE && data_pos < data.len() {
+            data[data_pos] ^= self.buffer.as_ref()[self.buffer_pos];
+            self.buffer_pos = self.buffer_pos.checked_add(1).expect("overflow");
+            data_pos = data_pos.checked_add(1).expect("overflow");
+        }
+
+        // Generate keystream eight blocks at a time where possible, so ciphers
+        // which can pipeline multiple blocks (e.g. AES-NI) can do so
+        if data.len() - data_pos >= BLOCK8_SIZE {
+            let mut keystream = Block8::new();
+
+            while data.len() - data_pos >= BLOCK8_SIZE {
+                for block in keystream.as_blocks_mut().iter_mut() {
+                    block.copy_from_block(&self.counter);
+                    self.increment_counter();
+                }
+
+                cipher.encrypt_blocks(keystream.as_blocks_mut());
+
+                for block in keystream.as_blocks().iter() {
+                    let data_end = data_pos.checked_add(BLOCK_SIZE).expect("overflow");
+                    xor_keystream(&mut data[data_pos..data_end], block);
+                    data_pos = data_end;
+                }
+            }
+        }
+
+        // Process remaining whole blocks
+        while data.len() - data_pos >= BLOCK_SIZE {
+            self.next_keystream_block(cipher);
+
+            let data_end = data_pos.checked_add(BLOCK_SIZE).expect("overflow");
+            xor_keystream(&mut data[data_pos..data_end], &self.buffer);
+            data_pos = data_end;
+        }
+
+        for b in &mut data[data_pos..] {
             if self.buffer_pos == BLOCK_SIZE {
-                self.buffer.copy_from_block(counter);
-                self.cipher.encrypt(&mut self.buffer);
+                self.next_keystream_block(cipher);
                 self.buffer_pos = 0;
-                increment_ctr(counter);
             }
 
             *b ^= self.buffer.as_ref()[self.buffer_pos];
             self.buffer_pos = self.buffer_pos.checked_add(1).expect("overflow");
         }
+
+        Ok(())
+    }
+
+    /// Check there's enough keystream left to process `len` more bytes
+    /// without a big endian counter wrapping around
+    fn check_remaining(&self, len: usize) -> Result<(), Error> {
+        if self.mode != CounterMode::BigEndian {
+            return Ok(());
+        }
+
+        let buffered = BLOCK_SIZE - self.buffer_pos;
+        let needed = (len.saturating_sub(buffered) as u128 + (BLOCK_SIZE as u128 - 1)) /
+            BLOCK_SIZE as u128;
+
+        if needed == 0 {
+            return Ok(());
+        }
+
+        if self.exhausted {
+            return Err(Error::CounterOverflow);
+        }
+
+        // Blocks from the current counter up to and including all 0xFF bytes
+        let remaining = (!BigEndian::read_u128(self.counter.as_ref())).saturating_add(1);
+
+        if needed > remaining {
+            return Err(Error::CounterOverflow);
+        }
+
+        Ok(())
+    }
+
+    /// Encrypt the current counter into the keystream buffer and advance it
+    #[inline]
+    fn next_keystream_block<C: BlockCipher>(&mut self, cipher: &C) {
+        self.buffer.copy_from_block(&self.counter);
+        cipher.encrypt(&mut self.buffer);
+        self.increment_counter();
+    }
+
+    /// Advance the counter by one block according to the counter mode
+    #[inline]
+    fn increment_counter(&mut self) {
+        match self.mode {
+            CounterMode::BigEndian => {
+                self.counter.increment_be();
+
+                if self.counter.as_ref() == &[0u8; BLOCK_SIZE] {
+                    self.exhausted = true;
+                }
+            }
+            CounterMode::LittleEndian32 => self.counter.increment_le32(),
+        }
+    }
+}
+
+/// XOR a block of keystream into a block-sized chunk of data, in-place
+/// without copying if the data happens to be aligned
+#[inline]
+fn xor_keystream(data: &mut [u8], keystream: &Block) {
+    let aligned = BlockMut::new(data)
+        .map(|mut block| block.as_block_mut().xor_block(keystream))
+        .is_some();
+
+    if !aligned {
+        xor::in_place(data, keystream.as_ref());
+    }
+}
+
+/// Creates a big endian counter instance, interchangeable with the RustCrypto
+/// `ctr` crate's `Ctr128BE`. The inherent `Ctr::new` takes precedence over
+/// this one, so call it as `NewCipher::new(&key, &iv)`.
+#[cfg(feature = "cipher")]
+impl NewCipher for Ctr<Aes128> {
+    type KeySize = U16;
+    type NonceSize = U16;
+
+    #[inline]
+    fn new(key: &CipherKey<Self>, iv: &Nonce<Self>) -> Self {
+        let iv = Block::from(*array_ref!(iv, 0, BLOCK_SIZE));
+        Ctr::new(Aes128::new(array_ref!(key, 0, 16)), &iv)
+    }
+}
+
+/// Creates a big endian counter instance, as for `Ctr<Aes128>`
+#[cfg(feature = "cipher")]
+impl NewCipher for Ctr<Aes256> {
+    type KeySize = U32;
+    type NonceSize = U16;
+
+    #[inline]
+    fn new(key: &CipherKey<Self>, iv: &Nonce<Self>) -> Self {
+        let iv = Block::from(*array_ref!(iv, 0, BLOCK_SIZE));
+        Ctr::new(Aes256::new(array_ref!(key, 0, 32)), &iv)
+    }
+}
+
+#[cfg(feature = "cipher")]
+impl<C: BlockCipher> StreamCipher for Ctr<C> {
+    /// Returns `LoopError` (leaving `data` untouched) in the cases where
+    /// `Ctr::apply_keystream` returns `Error::CounterOverflow`
+    #[inline]
+    fn try_apply_keystream(&mut self, data: &mut [u8]) -> Result<(), LoopError> {
+        Ctr::apply_keystream(self, data).map_err(|_| LoopError)
+    }
+}
+
+#[cfg(feature = "cipher")]
+impl<C: BlockCipher> StreamCipherSeek for Ctr<C> {
+    fn try_current_pos<T: SeekNum>(&self) -> Result<T, OverflowError> {
+        let block_size = BLOCK_SIZE as u64;
+        let position = self.state.position;
+        let byte = (position % block_size) as u8;
+        T::from_block_byte(position / block_size, byte, BLOCK_SIZE as u8)
+    }
+
+    /// Seek to the given byte offset, as with `Ctr::seek_to`. Returns
+    /// `LoopError` if the offset doesn't fit in 64 bits.
+    fn try_seek<T: SeekNum>(&mut self, pos: T) -> Result<(), LoopError> {
+        let (block, byte): (u64, u8) = pos.to_block_byte(BLOCK_SIZE as u8)
+            .map_err(|_| LoopError)?;
+
+        let byte_offset = block
+            .checked_mul(BLOCK_SIZE as u64)
+            .and_then(|offset| offset.checked_add(u64::from(byte)))
+            .ok_or(LoopError)?;
+
+        self.seek_to(byte_offset);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl<C: BlockCipher> Ctr<C> {
+    /// The cipher, so tests can check its key schedule has been wiped
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+}
+
+impl<C: BlockCipher> Zeroize for Ctr<C> {
+    /// Wipe the cipher's key schedule, the counter, and any buffered keystream
+    fn zeroize(&mut self) {
+        self.cipher.zeroize();
+        self.state.zeroize();
     }
 }
 
-/// Increment a CTR-mode counter. Panics on overflow
-// TODO: use verified asm implementation?
-fn increment_ctr(block: &mut Block) {
-    let counter = BigEndian::read_u128(block.as_ref());
-    BigEndian::write_u128(block.as_mut(), counter.wrapping_add(1));
+impl Zeroize for CtrState {
+    /// Wipe the counter and any buffered keystream
+    fn zeroize(&mut self) {
+        self.iv.zeroize();
+        self.counter.zeroize();
+        self.buffer.zeroize();
+        self.buffer_pos = BLOCK_SIZE;
+        self.position = 0;
+        self.exhausted = false;
+    }
+}
+
+impl Drop for CtrState {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
+impl<C: BlockCipher> fmt::Debug for Ctr<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Ctr(...redacted...)")
+    }
+}
+
+impl<C: BlockCipher> Drop for Ctr<C> {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
 }
 
+impl<C: BlockCipher> ZeroizeOnDrop for Ctr<C> {}
+
 #[cfg(test)]
 mod tests {
-    use super::{Block, BLOCK_SIZE};
-    use super::increment_ctr;
+    use super::{Block, CounterMode, Ctr, BLOCK_SIZE};
+    use core::{mem, ptr};
+    use error::Error;
+    use internals::{Aes128, Aes256, Block8, BLOCK8_SIZE};
+    #[cfg(feature = "std")]
+    use internals::BlockCipher;
+    use internals::test_rng::XorShift64;
+    #[cfg(feature = "std")]
+    use proptest::collection::vec;
+    #[cfg(feature = "std")]
+    use proptest::prelude::*;
+    #[cfg(feature = "std")]
+    use std::io;
+    #[cfg(feature = "std")]
+    use std::vec::Vec;
+    use zeroize::Zeroize;
+
+    /// NIST SP 800-38A F.5.1 CTR-AES128.Encrypt key
+    static SP800_38A_KEY: [u8; 16] = [
+        0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6,
+        0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c,
+    ];
+
+    /// NIST SP 800-38A F.5.1 initial counter block
+    static SP800_38A_COUNTER: Block = Block::from_bytes([
+        0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7,
+        0xf8, 0xf9, 0xfa, 0xfb, 0xfc, 0xfd, 0xfe, 0xff,
+    ]);
+
+    /// NIST SP 800-38A F.5.1 plaintext blocks
+    static SP800_38A_PLAINTEXT: [Block; 4] = [
+        Block::from_bytes([
+            0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96,
+            0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a,
+        ]),
+        Block::from_bytes([
+            0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c,
+            0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf, 0x8e, 0x51,
+        ]),
+        Block::from_bytes([
+            0x30, 0xc8, 0x1c, 0x46, 0xa3, 0x5c, 0xe4, 0x11,
+            0xe5, 0xfb, 0xc1, 0x19, 0x1a, 0x0a, 0x52, 0xef,
+        ]),
+        Block::from_bytes([
+            0xf6, 0x9f, 0x24, 0x45, 0xdf, 0x4f, 0x9b, 0x17,
+            0xad, 0x2b, 0x41, 0x7b, 0xe6, 0x6c, 0x37, 0x10,
+        ]),
+    ];
+
+    /// NIST SP 800-38A F.5.1 ciphertext blocks
+    static SP800_38A_CIPHERTEXT: [Block; 4] = [
+        Block::from_bytes([
+            0x87, 0x4d, 0x61, 0x91, 0xb6, 0x20, 0xe3, 0x26,
+            0x1b, 0xef, 0x68, 0x64, 0x99, 0x0d, 0xb6, 0xce,
+        ]),
+        Block::from_bytes([
+            0x98, 0x06, 0xf6, 0x6b, 0x79, 0x70, 0xfd, 0xff,
+            0x86, 0x17, 0x18, 0x7b, 0xb9, 0xff, 0xfd, 0xff,
+        ]),
+        Block::from_bytes([
+            0x5a, 0xe4, 0xdf, 0x3e, 0xdb, 0xd5, 0xd3, 0x5e,
+            0x5b, 0x4f, 0x09, 0x02, 0x0d, 0xb0, 0x3e, 0xab,
+        ]),
+        Block::from_bytes([
+            0x1e, 0x03, 0x1d, 0xda, 0x2f, 0xbe, 0x03, 0xd1,
+            0x79, 0x21, 0x70, 0xa0, 0xf3, 0x00, 0x9c, 0xee,
+        ]),
+    ];
+
+    /// RFC 8452 AES-GCM-SIV example (AES-128, 40-byte plaintext): the
+    /// derived message encryption key
+    static GCM_SIV_KEY: [u8; 16] = [
+        0x40, 0x04, 0xa0, 0xdc, 0xd8, 0x62, 0xf2, 0xa5,
+        0x73, 0x60, 0x21, 0x9d, 0x2d, 0x44, 0xef, 0x6c,
+    ];
+
+    /// RFC 8452 AES-GCM-SIV example: the tag with its top bit set, used as the
+    /// initial counter block
+    static GCM_SIV_COUNTER: Block = Block::from_bytes([
+        0xca, 0xf2, 0x00, 0x94, 0x2d, 0xb4, 0x74, 0xba,
+        0x0c, 0x28, 0x7d, 0x59, 0x4d, 0x0f, 0x7a, 0x97,
+    ]);
+
+    /// RFC 8452 AES-GCM-SIV example plaintext
+    static GCM_SIV_PLAINTEXT: [u8; 40] = [
+        0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08,
+        0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10,
+        0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18,
+        0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f, 0x20,
+        0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x28,
+    ];
+
+    /// RFC 8452 AES-GCM-SIV example ciphertext (without the tag)
+    static GCM_SIV_CIPHERTEXT: [u8; 40] = [
+        0xd2, 0xa7, 0xb8, 0x85, 0xc1, 0xf5, 0xc7, 0x4b,
+        0xa3, 0xdb, 0x14, 0x9c, 0x19, 0x33, 0x3a, 0x02,
+        0x5d, 0x80, 0x83, 0x44, 0x12, 0xec, 0xee, 0x9c,
+        0xd7, 0xf0, 0x1c, 0x45, 0xf8, 0x57, 0x6f, 0x25,
+        0xa0, 0x5f, 0x92, 0x37, 0x81, 0xa5, 0xf7, 0x01,
+    ];
+
+    #[test]
+    fn counter_advances_per_block() {
+        let mut iv = [0u8; BLOCK_SIZE];
+        iv[BLOCK_SIZE - 1] = 0xFF;
+
+        let mut ctr = Ctr::new(Aes128::new(&[0x42u8; 16]), &Block::from(iv));
+        ctr.apply_keystream(&mut [0u8; BLOCK_SIZE * 2 + 1]).unwrap();
+
+        let mut expected = [0u8; BLOCK_SIZE];
+        expected[BLOCK_SIZE - 2] = 1;
+        expected[BLOCK_SIZE - 1] = 2;
+        assert_eq!(ctr.state.counter.as_ref(), &expected);
+    }
+
+    #[test]
+    fn big_endian_counter_overflow() {
+        // Two blocks of keystream remain before the counter would wrap
+        let mut iv = [0xFFu8; BLOCK_SIZE];
+        iv[BLOCK_SIZE - 1] = 0xFE;
+        let iv = Block::from(iv);
+
+        let mut ctr = Ctr::new(Aes128::new(&SP800_38A_KEY), &iv);
+        let mut buffer = [0u8; BLOCK_SIZE * 3];
+        assert_eq!(ctr.apply_keystream(&mut buffer), Err(Error::CounterOverflow));
+        assert_eq!(&buffer[..], &[0u8; BLOCK_SIZE * 3][..]);
+
+        // Consuming the keystream a byte at a time fails on the third block
+        for i in 0..buffer.len() {
+            let result = ctr.apply_keystream(&mut buffer[i..i + 1]);
+
+            if i < BLOCK_SIZE * 2 {
+                assert_eq!(result, Ok(()), "byte {}", i);
+            } else {
+                assert_eq!(result, Err(Error::CounterOverflow), "byte {}", i);
+            }
+        }
+
+        // Seeking back to the start makes the keystream usable again, and
+        // seeking past its end leaves it exhausted
+        ctr.reset();
+        ctr.keystream(&mut buffer[..BLOCK_SIZE * 2]).unwrap();
+        ctr.seek(3);
+        assert_eq!(ctr.apply_keystream(&mut [0u8; 1]), Err(Error::CounterOverflow));
+        ctr.seek_to(BLOCK_SIZE as u64 * 2 - 1);
+        ctr.apply_keystream(&mut [0u8; 1]).unwrap();
+        assert_eq!(ctr.apply_keystream(&mut [0u8; 1]), Err(Error::CounterOverflow));
+        assert_eq!(ctr.apply_keystream(&mut []), Ok(()));
+    }
+
+    #[test]
+    fn zeroize_keystream_buffer() {
+        let mut ctr = Ctr::new(Aes128::new(&[0x42u8; 16]), &Block::new());
+        ctr.apply_keystream(&mut [0u8; 5]).unwrap();
+        ctr.zeroize();
+
+        assert_eq!(ctr.state.buffer.as_ref(), &[0u8; BLOCK_SIZE]);
+        assert_eq!(ctr.state.buffer_pos, BLOCK_SIZE);
+    }
+
+    #[test]
+    fn zeroize_on_drop() {
+        let mut ctr = mem::ManuallyDrop::new(Ctr::new(
+            Aes128::new(&SP800_38A_KEY),
+            &SP800_38A_COUNTER,
+        ));
+        ctr.apply_keystream(&mut [0u8; 5]).unwrap();
+
+        unsafe {
+            ptr::drop_in_place(&mut *ctr);
+
+            assert_eq!(ctr.state.iv.as_ref(), &[0u8; BLOCK_SIZE]);
+            assert_eq!(ctr.state.counter.as_ref(), &[0u8; BLOCK_SIZE]);
+            assert_eq!(ctr.state.buffer.as_ref(), &[0u8; BLOCK_SIZE]);
+        }
+    }
 
     #[test]
-    fn counter_increment() {
-        let mut block = Block::new();
-        increment_ctr(&mut block);
-        assert_eq!(block.as_ref(), *b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\x01");
+    fn transform_aligned_matches_unaligned() {
+        let mut aligned_storage = Block8::new();
+        let mut unaligned_storage = [0u8; BLOCK8_SIZE + 1];
+
+        let aligned: &mut [u8; BLOCK8_SIZE] = aligned_storage.as_mut();
+        let unaligned = &mut unaligned_storage[1..];
+
+        for (i, (a, u)) in aligned.iter_mut().zip(unaligned.iter_mut()).enumerate() {
+            *a = i as u8;
+            *u = i as u8;
+        }
+
+        let mut ctr = Ctr::new(Aes128::new(&[0x42u8; 16]), &Block::new());
+
+        // Start with a partial block so the aligned path picks up mid-stream
+        ctr.apply_keystream(&mut aligned[..3]).unwrap();
+        ctr.apply_keystream(&mut aligned[3..]).unwrap();
+        ctr.reset();
+
+        ctr.apply_keystream(&mut unaligned[..3]).unwrap();
+        ctr.apply_keystream(&mut unaligned[3..]).unwrap();
+
+        assert_eq!(&aligned[..], &unaligned[..]);
+    }
+
+    #[test]
+    fn sp800_38a_example() {
+        let mut ctr = Ctr::new(Aes128::new(&SP800_38A_KEY), &SP800_38A_COUNTER);
+        let examples = SP800_38A_PLAINTEXT.iter().zip(SP800_38A_CIPHERTEXT.iter());
+
+        for (plaintext, ciphertext) in examples {
+            let mut block = plaintext.clone();
+            ctr.apply_keystream(block.as_mut()).unwrap();
+            assert_eq!(block.as_ref(), ciphertext.as_ref());
+        }
+    }
+
+    #[test]
+    fn round_trip_unaligned_lengths() {
+        let mut plaintext = [0u8; BLOCK_SIZE * 4];
+        for (i, byte) in plaintext.iter_mut().enumerate() {
+            *byte = (i as u8).wrapping_mul(0x1d);
+        }
+
+        for len in 0..plaintext.len() {
+            let mut buffer = [0u8; BLOCK_SIZE * 4];
+            buffer[..len].copy_from_slice(&plaintext[..len]);
+
+            let mut ctr = Ctr::new(Aes128::new(&SP800_38A_KEY), &SP800_38A_COUNTER);
+            ctr.apply_keystream(&mut buffer[..len]).unwrap();
+
+            if len > 0 {
+                assert_ne!(&buffer[..len], &plaintext[..len]);
+            }
+
+            ctr.reset();
+            ctr.apply_keystream(&mut buffer[..len]).unwrap();
+            assert_eq!(&buffer[..len], &plaintext[..len]);
+        }
+    }
+
+    #[test]
+    fn seek_to_block() {
+        let mut ctr = Ctr::new(Aes128::new(&SP800_38A_KEY), &SP800_38A_COUNTER);
+
+        // Seek past the counter's low byte wrapping from 0xff to 0x00
+        for (i, (plaintext, ciphertext)) in SP800_38A_PLAINTEXT
+            .iter()
+            .zip(SP800_38A_CIPHERTEXT.iter())
+            .enumerate()
+            .rev()
+        {
+            ctr.seek(i as u64);
+
+            let mut block = plaintext.clone();
+            ctr.apply_keystream(block.as_mut()).unwrap();
+            assert_eq!(block.as_ref(), ciphertext.as_ref());
+        }
+    }
+
+    #[test]
+    fn seek_to_byte_offset() {
+        let mut reference = [0u8; BLOCK_SIZE * 4];
+        let mut ctr = Ctr::new(Aes128::new(&SP800_38A_KEY), &SP800_38A_COUNTER);
+        ctr.apply_keystream(&mut reference).unwrap();
+
+        for offset in 0..reference.len() {
+            // Process some keystream first, so the seek has to discard it
+            ctr.reset();
+            ctr.apply_keystream(&mut [0u8; 7]).unwrap();
+            ctr.seek_to(offset as u64);
+
+            let mut output = [0u8; BLOCK_SIZE * 4];
+            let len = reference.len() - offset;
+            ctr.apply_keystream(&mut output[..len]).unwrap();
+            assert_eq!(&output[..len], &reference[offset..], "offset {}", offset);
+        }
     }
 
     #[test]
-    fn counter_overflow() {
-        let mut block = Block::from([0xFFu8; BLOCK_SIZE]);
-        increment_ctr(&mut block);
-        assert_eq!(block.as_ref(), &[0u8; BLOCK_SIZE]);
+    fn seek_to_decrypts_sub_ranges() {
+        let mut plaintext = [0u8; BLOCK8_SIZE * 2 + 29];
+        for (i, byte) in plaintext.iter_mut().enumerate() {
+            *byte = (i as u8).wrapping_mul(0x65).wrapping_add(0x0b);
+        }
+
+        let mut ciphertext = plaintext;
+        let mut ctr = Ctr::new(Aes128::new(&SP800_38A_KEY), &SP800_38A_COUNTER);
+        ctr.apply_keystream(&mut ciphertext).unwrap();
+        assert_eq!(ctr.current_position(), plaintext.len() as u64);
+
+        let mut rng = XorShift64::new(0x2545_f491_4f6c_dd1d);
+        let mut next = |bound: usize| (rng.next_u64() % bound as u64) as usize;
+
+        for _ in 0..256 {
+            let start = next(plaintext.len());
+            let end = start + next(plaintext.len() - start + 1);
+
+            let mut buffer = ciphertext;
+            ctr.seek_to(start as u64);
+            assert_eq!(ctr.current_position(), start as u64);
+
+            ctr.apply_keystream(&mut buffer[start..end]).unwrap();
+            assert_eq!(ctr.current_position(), end as u64);
+            assert_eq!(&buffer[start..end], &plaintext[start..end], "{}..{}", start, end);
+        }
+
+        // Ranges which straddle block boundaries by a single byte either side
+        for boundary in (1..plaintext.len() / BLOCK_SIZE).map(|i| i * BLOCK_SIZE) {
+            let mut buffer = ciphertext;
+            ctr.seek_to(boundary as u64 - 1);
+            ctr.apply_keystream(&mut buffer[boundary - 1..boundary + 1]).unwrap();
+            assert_eq!(&buffer[boundary - 1..boundary + 1], &plaintext[boundary - 1..boundary + 1]);
+        }
+    }
+
+    #[test]
+    fn seek_updates_current_position() {
+        let mut ctr = Ctr::new(Aes128::new(&SP800_38A_KEY), &SP800_38A_COUNTER);
+        assert_eq!(ctr.current_position(), 0);
+
+        ctr.apply_keystream(&mut [0u8; 21]).unwrap();
+        assert_eq!(ctr.current_position(), 21);
+
+        ctr.seek(3);
+        assert_eq!(ctr.current_position(), 3 * BLOCK_SIZE as u64);
+
+        ctr.reset();
+        assert_eq!(ctr.current_position(), 0);
+    }
+
+    #[cfg(feature = "cipher")]
+    #[test]
+    fn stream_cipher_traits() {
+        use cipher::{NewCipher, StreamCipher, StreamCipherSeek};
+        use internals::Aes128Ctr;
+
+        let mut ctr: Aes128Ctr =
+            NewCipher::new_from_slices(&SP800_38A_KEY, SP800_38A_COUNTER.as_ref()).unwrap();
+
+        let mut buffer = [0u8; BLOCK_SIZE * 4];
+        for (chunk, plaintext) in buffer.chunks_mut(BLOCK_SIZE).zip(SP800_38A_PLAINTEXT.iter()) {
+            chunk.copy_from_slice(plaintext.as_ref());
+        }
+
+        StreamCipher::apply_keystream(&mut ctr, &mut buffer);
+        assert_eq!(ctr.current_pos::<u64>(), buffer.len() as u64);
+
+        for (chunk, ciphertext) in buffer.chunks(BLOCK_SIZE).zip(SP800_38A_CIPHERTEXT.iter()) {
+            assert_eq!(chunk, ciphertext.as_ref());
+        }
+
+        // Seek into the middle of the second block and decrypt from there
+        StreamCipherSeek::seek(&mut ctr, 21u32);
+        assert_eq!(ctr.current_pos::<u32>(), 21);
+        StreamCipher::apply_keystream(&mut ctr, &mut buffer[21..]);
+        assert_eq!(&buffer[21..BLOCK_SIZE * 2], &SP800_38A_PLAINTEXT[1].as_ref()[5..]);
+
+        assert!(ctr.try_seek(u128::max_value()).is_err());
+
+        StreamCipherSeek::seek(&mut ctr, 300u64);
+        assert!(ctr.try_current_pos::<u8>().is_err());
+    }
+
+    #[test]
+    fn little_endian32_gcm_siv_example() {
+        let mut ctr = Ctr::with_counter_mode(
+            Aes128::new(&GCM_SIV_KEY),
+            &GCM_SIV_COUNTER,
+            CounterMode::LittleEndian32,
+        );
+
+        let mut buffer = GCM_SIV_PLAINTEXT;
+        ctr.apply_keystream(&mut buffer).unwrap();
+        assert_eq!(&buffer[..], &GCM_SIV_CIPHERTEXT[..]);
+
+        // Seeking back into the middle of the stream should match as well
+        ctr.seek_to(19);
+        ctr.apply_keystream(&mut buffer[19..]).unwrap();
+        assert_eq!(&buffer[19..], &GCM_SIV_PLAINTEXT[19..]);
+    }
+
+    /// RFC 8452 Appendix C.3 (counter wrap, 32-byte plaintext), whose tag
+    /// starts the 32-bit counter at `0xFFFFFFFF` so it wraps after one block
+    #[test]
+    fn little_endian32_rfc8452_counter_wrap_example() {
+        let key = [
+            0x77, 0x9b, 0x38, 0xd1, 0x5b, 0xff, 0xb6, 0x3d,
+            0x39, 0xd6, 0xe9, 0xae, 0x76, 0xa9, 0xb2, 0xf3,
+            0x75, 0xd1, 0x1b, 0x0e, 0x3a, 0x68, 0xc4, 0x22,
+            0x84, 0x5c, 0x7d, 0x46, 0x90, 0xfa, 0x59, 0x4f,
+        ];
+
+        // The tag `ffffffff000000000000000000000000` with its top bit set
+        let counter = Block::from_bytes([
+            0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80,
+        ]);
+
+        let plaintext = [
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x4d, 0xb9, 0x23, 0xdc, 0x79, 0x3e, 0xe6, 0x49,
+            0x7c, 0x76, 0xdc, 0xc0, 0x3a, 0x98, 0xe1, 0x08,
+        ];
+
+        let ciphertext = [
+            0xf3, 0xf8, 0x0f, 0x2c, 0xf0, 0xcb, 0x2d, 0xd9,
+            0xc5, 0x98, 0x4f, 0xcd, 0xa9, 0x08, 0x45, 0x6c,
+            0xc5, 0x37, 0x70, 0x3b, 0x5b, 0xa7, 0x03, 0x24,
+            0xa6, 0x79, 0x3a, 0x7b, 0xf2, 0x18, 0xd3, 0xea,
+        ];
+
+        let mut ctr = Ctr::with_counter_mode(
+            Aes256::new(&key),
+            &counter,
+            CounterMode::LittleEndian32,
+        );
+
+        let mut buffer = plaintext;
+        ctr.apply_keystream(&mut buffer).unwrap();
+        assert_eq!(buffer, ciphertext);
+
+        // Only the low 32 bits wrapped, leaving the rest of the block intact
+        let mut expected = counter.clone();
+        {
+            let bytes: &mut [u8] = expected.as_mut();
+            bytes[..4].copy_from_slice(&[1, 0, 0, 0]);
+        }
+        assert_eq!(ctr.state.counter.as_ref(), expected.as_ref());
+    }
+
+    #[test]
+    fn little_endian32_counter_wraps() {
+        let iv = Block::from_bytes([
+            0xfe, 0xff, 0xff, 0xff, 0x00, 0x11, 0x22, 0x33,
+            0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb,
+        ]);
+
+        let mut ctr = Ctr::with_counter_mode(
+            Aes128::new(&SP800_38A_KEY),
+            &iv,
+            CounterMode::LittleEndian32,
+        );
+        ctr.apply_keystream(&mut [0u8; BLOCK_SIZE * 2]).unwrap();
+
+        // Only the 32-bit counter wraps, leaving the nonce untouched
+        let expected = Block::from_bytes([
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x11, 0x22, 0x33,
+            0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb,
+        ]);
+        assert_eq!(ctr.state.counter.as_ref(), expected.as_ref());
+
+        ctr.seek(2);
+        assert_eq!(ctr.state.counter.as_ref(), expected.as_ref());
+    }
+
+    #[test]
+    fn batched_keystream_matches_single_blocks() {
+        let iv = Block::from_bytes([
+            0xff, 0xff, 0xff, 0xfb, 0xff, 0xff, 0xff, 0xff,
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfb,
+        ]);
+
+        for &mode in &[CounterMode::BigEndian, CounterMode::LittleEndian32] {
+            // One call covering several groups of eight blocks plus a tail
+            let mut batched = [0u8; BLOCK8_SIZE * 3 + 21];
+            let mut ctr = Ctr::with_counter_mode(Aes128::new(&SP800_38A_KEY), &iv, mode);
+            ctr.apply_keystream(&mut batched[1..]).unwrap();
+
+            // Calls which are always shorter than eight blocks
+            let mut single = [0u8; BLOCK8_SIZE * 3 + 21];
+            ctr.reset();
+            for chunk in single[1..].chunks_mut(BLOCK_SIZE * 2 + 1) {
+                ctr.apply_keystream(chunk).unwrap();
+            }
+
+            assert_eq!(&batched[..], &single[..], "{:?}", mode);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    proptest! {
+        /// Any length from 0 to 1024 bytes, applied in two calls so the second
+        /// can start mid-block, matches encrypting one counter block at a time
+        #[test]
+        fn batched_matches_reference(data in vec(any::<u8>(), 0..=1024), split in any::<usize>()) {
+            let cipher = Aes128::new(&SP800_38A_KEY);
+            let split = split % (data.len() + 1);
+
+            let mut expected = data.clone();
+            let mut counter = SP800_38A_COUNTER.clone();
+            for chunk in expected.chunks_mut(BLOCK_SIZE) {
+                let mut keystream = counter.clone();
+                cipher.encrypt(&mut keystream);
+                counter.increment_be();
+
+                for (b, k) in chunk.iter_mut().zip(keystream.as_ref().iter()) {
+                    *b ^= *k;
+                }
+            }
+
+            let mut buffer = data;
+            let mut ctr = Ctr::new(cipher, &SP800_38A_COUNTER);
+            ctr.apply_keystream(&mut buffer[..split]).unwrap();
+            ctr.apply_keystream(&mut buffer[split..]).unwrap();
+
+            prop_assert_eq!(buffer, expected);
+        }
+    }
+
+    #[test]
+    fn keystream_matches_apply_keystream() {
+        let mut plaintext = [0u8; BLOCK8_SIZE * 2 + 37];
+        for (i, byte) in plaintext.iter_mut().enumerate() {
+            *byte = (i as u8).wrapping_mul(0x3b);
+        }
+
+        let mut expected = plaintext;
+        let mut ctr = Ctr::new(Aes128::new(&SP800_38A_KEY), &SP800_38A_COUNTER);
+        ctr.apply_keystream(&mut expected).unwrap();
+
+        // Chunk sizes which straddle block boundaries in different places
+        for &chunk_size in &[1, 7, BLOCK_SIZE, BLOCK_SIZE + 3, BLOCK8_SIZE + 5] {
+            let mut buffer = plaintext;
+            ctr.reset();
+
+            for chunk in buffer.chunks_mut(chunk_size) {
+                // Leave garbage in the output buffer to ensure it's overwritten
+                let mut keystream = [0xa5u8; BLOCK8_SIZE + 5];
+                let keystream = &mut keystream[..chunk.len()];
+                ctr.keystream(keystream).unwrap();
+
+                for (b, k) in chunk.iter_mut().zip(keystream.iter()) {
+                    *b ^= *k;
+                }
+            }
+
+            assert_eq!(&buffer[..], &expected[..], "chunk size {}", chunk_size);
+        }
+    }
+
+    #[test]
+    fn keystream_interleaves_with_apply_keystream() {
+        let mut plaintext = [0u8; BLOCK8_SIZE * 2 + 37];
+        for (i, byte) in plaintext.iter_mut().enumerate() {
+            *byte = (i as u8).wrapping_mul(0x3b);
+        }
+
+        let mut expected = plaintext;
+        let mut ctr = Ctr::new(Aes128::new(&SP800_38A_KEY), &SP800_38A_COUNTER);
+        ctr.apply_keystream(&mut expected).unwrap();
+
+        // Alternate between the two methods, mostly ending mid-block
+        let mut buffer = plaintext;
+        ctr.reset();
+
+        for (i, chunk) in buffer.chunks_mut(BLOCK_SIZE + 5).enumerate() {
+            if i % 2 == 0 {
+                ctr.apply_keystream(chunk).unwrap();
+            } else {
+                let mut keystream = [0u8; BLOCK_SIZE + 5];
+                let keystream = &mut keystream[..chunk.len()];
+                ctr.keystream(keystream).unwrap();
+
+                for (b, k) in chunk.iter_mut().zip(keystream.iter()) {
+                    *b ^= *k;
+                }
+            }
+        }
+
+        assert_eq!(&buffer[..], &expected[..]);
+        assert_eq!(ctr.current_position(), plaintext.len() as u64);
+    }
+
+    #[test]
+    fn reset_iv_matches_new_instance() {
+        let mut ctr = Ctr::new(Aes128::new(&SP800_38A_KEY), &Block::from([0xffu8; BLOCK_SIZE]));
+
+        // Leave the keystream both mid-block and exhausted before resetting
+        assert!(ctr.apply_keystream(&mut [0u8; 5]).is_ok());
+        assert_eq!(
+            ctr.apply_keystream(&mut [0u8; BLOCK_SIZE]).err(),
+            Some(Error::CounterOverflow)
+        );
+
+        let ivs = [Block::new(), SP800_38A_COUNTER.clone(), Block::from([0x5au8; BLOCK_SIZE])];
+
+        for (i, iv) in ivs.iter().enumerate() {
+            let mut expected = [0x24u8; BLOCK_SIZE * 3 + 7];
+            Ctr::new(Aes128::new(&SP800_38A_KEY), iv)
+                .apply_keystream(&mut expected)
+                .unwrap();
+
+            let mut buffer = [0x24u8; BLOCK_SIZE * 3 + 7];
+            ctr.reset_iv(iv);
+            assert_eq!(ctr.current_position(), 0);
+            ctr.apply_keystream(&mut buffer[..i + 1]).unwrap();
+            ctr.apply_keystream(&mut buffer[i + 1..]).unwrap();
+
+            assert_eq!(&buffer[..], &expected[..], "iv #{}", i);
+        }
+    }
+
+    #[test]
+    fn apply_keystream_from_iv_matches_new_instance() {
+        let ivs = [Block::new(), SP800_38A_COUNTER.clone(), Block::from([0xffu8; BLOCK_SIZE])];
+
+        for &mode in &[CounterMode::BigEndian, CounterMode::LittleEndian32] {
+            let mut ctr = Ctr::with_counter_mode(Aes128::new(&SP800_38A_KEY), &Block::new(), mode);
+            ctr.apply_keystream(&mut [0u8; 5]).unwrap();
+
+            for (i, iv) in ivs.iter().enumerate() {
+                let len = if i == 2 && mode == CounterMode::BigEndian {
+                    // Only one block of keystream is left before the end
+                    BLOCK_SIZE
+                } else {
+                    BLOCK8_SIZE + BLOCK_SIZE * 3 + 7
+                };
+
+                let mut expected = vec![0x24u8; len];
+                Ctr::with_counter_mode(Aes128::new(&SP800_38A_KEY), iv, mode)
+                    .apply_keystream(&mut expected)
+                    .unwrap();
+
+                let mut buffer = vec![0x24u8; len];
+                ctr.apply_keystream_from_iv(iv, &mut buffer).unwrap();
+                assert_eq!(buffer, expected, "{:?} iv #{}", mode, i);
+            }
+
+            // The instance's own position is unaffected
+            assert_eq!(ctr.current_position(), 5);
+        }
+
+        let ctr = Ctr::new(Aes128::new(&SP800_38A_KEY), &Block::new());
+        assert_eq!(
+            ctr.apply_keystream_from_iv(&ivs[2], &mut [0u8; BLOCK_SIZE + 1]).err(),
+            Some(Error::CounterOverflow)
+        );
+    }
+
+    /// Reader which returns at most 5 bytes per call, starting with an
+    /// `Interrupted` error, then an optional error at the end
+    #[cfg(feature = "std")]
+    struct ShortReader<'a> {
+        data: &'a [u8],
+        interrupted: bool,
+        error: Option<io::ErrorKind>,
+    }
+
+    #[cfg(feature = "std")]
+    impl<'a> io::Read for ShortReader<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if !self.interrupted {
+                self.interrupted = true;
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "short"));
+            }
+
+            if self.data.is_empty() {
+                return match self.error.take() {
+                    Some(kind) => Err(io::Error::new(kind, "short")),
+                    None => Ok(0),
+                };
+            }
+
+            let n = *[5, buf.len(), self.data.len()].iter().min().unwrap();
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn transform_round_trips_megabytes() {
+        let plaintext: Vec<u8> = (0..3 * 1024 * 1024 + 3).map(|i| (i % 251) as u8).collect();
+
+        let mut expected = plaintext.clone();
+        let mut ctr = Ctr::new(Aes128::new(&SP800_38A_KEY), &SP800_38A_COUNTER);
+        ctr.apply_keystream(&mut expected).unwrap();
+
+        let mut ciphertext = Vec::new();
+        ctr.reset();
+        assert_eq!(
+            ctr.transform(&plaintext[..], &mut ciphertext, 7).unwrap(),
+            plaintext.len() as u64
+        );
+        assert!(ciphertext == expected);
+
+        let mut decrypted = Vec::new();
+        ctr.reset();
+        ctr.transform(&ciphertext[..], &mut decrypted, 4099).unwrap();
+        assert!(decrypted == plaintext);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn transform_handles_short_reads() {
+        let plaintext = [0x42u8; BLOCK8_SIZE * 3 + 11];
+
+        let mut expected = plaintext;
+        let mut ctr = Ctr::new(Aes128::new(&SP800_38A_KEY), &SP800_38A_COUNTER);
+        ctr.apply_keystream(&mut expected).unwrap();
+
+        let mut ciphertext = Vec::new();
+        let reader = ShortReader {
+            data: &plaintext,
+            interrupted: false,
+            error: None,
+        };
+
+        ctr.reset();
+        assert_eq!(ctr.transform(reader, &mut ciphertext, 7).unwrap(), plaintext.len() as u64);
+        assert_eq!(&ciphertext[..], &expected[..]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn transform_propagates_errors() {
+        let reader = ShortReader {
+            data: &[0u8; 20],
+            interrupted: false,
+            error: Some(io::ErrorKind::UnexpectedEof),
+        };
+
+        let mut ctr = Ctr::new(Aes128::new(&SP800_38A_KEY), &SP800_38A_COUNTER);
+        let err = ctr.transform(reader, io::sink(), 7).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        // Running out of keystream is an error rather than wrapping around
+        let mut ctr = Ctr::new(Aes128::new(&SP800_38A_KEY), &Block::from([0xffu8; BLOCK_SIZE]));
+        let err = ctr.transform(&[0u8; BLOCK_SIZE + 1][..], io::sink(), 7).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
     }
 }
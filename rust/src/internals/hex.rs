@@ -0,0 +1,115 @@
+//! `internals/hex.rs`: Constant-time hexadecimal encoding and decoding
+//!
+//! These functions avoid data-dependent branches and table lookups, so they
+//! are safe to use on key material. Only the validity of the input may be
+//! revealed, not its contents.
+
+use error::Error;
+
+/// Encode the input as lowercase hexadecimal into the output buffer
+///
+/// Panics if the output isn't exactly twice the length of the input
+pub fn encode(input: &[u8], output: &mut [u8]) {
+    assert_eq!(
+        output.len(),
+        input.len().checked_mul(2).expect("overflow"),
+        "output must be twice the length of the input"
+    );
+
+    for (byte, pair) in input.iter().zip(output.chunks_mut(2)) {
+        pair[0] = encode_nibble(byte >> 4);
+        pair[1] = encode_nibble(byte & 0xf);
+    }
+}
+
+/// Decode hexadecimal (either upper or lower case) into the output buffer
+///
+/// Returns `Error::HexLength` if the input isn't exactly twice the length of
+/// the output, or `Error::HexCharacter` if it contains any non-hex characters
+/// (in which case the contents of the output are unspecified)
+pub fn decode(input: &[u8], output: &mut [u8]) -> Result<(), Error> {
+    if input.len() != output.len().checked_mul(2).expect("overflow") {
+        return Err(Error::HexLength);
+    }
+
+    let mut valid = 0xffu8;
+
+    for (pair, byte) in input.chunks(2).zip(output.iter_mut()) {
+        let (hi, hi_valid) = decode_nibble(pair[0]);
+        let (lo, lo_valid) = decode_nibble(pair[1]);
+
+        *byte = (hi << 4) | lo;
+        valid &= hi_valid & lo_valid;
+    }
+
+    if valid != 0xff {
+        return Err(Error::HexCharacter);
+    }
+
+    Ok(())
+}
+
+/// Encode a 4-bit value as a lowercase hex character
+#[inline]
+fn encode_nibble(nibble: u8) -> u8 {
+    let n = i16::from(nibble);
+
+    // Shift from the '0'-'9' range into 'a'-'f' if the nibble is 10 or more
+    (n + 0x30 + (((9 - n) >> 8) & 0x27)) as u8
+}
+
+/// Decode a hex character, returning its value along with a mask which is
+/// `0xff` if the character was valid hex, or `0` if it wasn't
+#[inline]
+fn decode_nibble(c: u8) -> (u8, u8) {
+    let c = i16::from(c);
+    let lower = c | 0x20;
+
+    // Each mask is all ones if the character is in range, or zero if not
+    let digit = ((0x2f - c) & (c - 0x3a)) >> 8;
+    let alpha = ((0x60 - lower) & (lower - 0x67)) >> 8;
+
+    let value = (digit & (c - 0x30)) | (alpha & (lower - 0x57));
+    (value as u8, (digit | alpha) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, decode_nibble, encode};
+    use error::Error;
+
+    #[test]
+    fn nibbles() {
+        for c in 0..256u16 {
+            let c = c as u8;
+            let (value, valid) = decode_nibble(c);
+
+            match c {
+                b'0'...b'9' => assert_eq!((value, valid), (c - b'0', 0xff)),
+                b'a'...b'f' => assert_eq!((value, valid), (c - b'a' + 10, 0xff)),
+                b'A'...b'F' => assert_eq!((value, valid), (c - b'A' + 10, 0xff)),
+                _ => assert_eq!(valid, 0, "character {:#x}", c),
+            }
+        }
+    }
+
+    #[test]
+    fn encode_decode() {
+        let mut hex = [0u8; 8];
+        encode(b"\x01\x23\xab\xef", &mut hex);
+        assert_eq!(&hex, b"0123abef");
+
+        let mut bytes = [0u8; 4];
+        decode(b"0123ABef", &mut bytes).unwrap();
+        assert_eq!(&bytes, b"\x01\x23\xab\xef");
+    }
+
+    #[test]
+    fn decode_errors() {
+        let mut bytes = [0u8; 4];
+        assert_eq!(decode(b"0123abe", &mut bytes), Err(Error::HexLength));
+        assert_eq!(decode(b"0123abef0", &mut bytes), Err(Error::HexLength));
+        assert_eq!(decode(b"0123abeg", &mut bytes), Err(Error::HexCharacter));
+        assert_eq!(decode(b"0123 bef", &mut bytes), Err(Error::HexCharacter));
+    }
+}
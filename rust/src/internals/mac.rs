@@ -0,0 +1,43 @@
+//! `internals/mac.rs`: Trait for the MACs which S2V can be built on.
+
+use super::{Block, BlockCipher};
+
+/// Common interface to the message authentication codes used by S2V
+///
+/// S2V computes a MAC over each associated data item and the message, so
+/// anything derived from the cipher alone (e.g. CMAC's subkeys) lives in
+/// `Key`, which is computed once and used to create fresh MAC instances.
+pub trait Mac<C: BlockCipher>: Sized {
+    /// Precomputed key material for this MAC
+    type Key: Clone;
+
+    /// Derive the key material for this MAC from the given cipher
+    fn new_key(cipher: C) -> Self::Key;
+
+    /// Create a fresh MAC instance from precomputed key material
+    fn from_key(key: &Self::Key) -> Self;
+
+    /// Reset the MAC back to its initial state
+    fn reset(&mut self);
+
+    /// Update the MAC state with the given message
+    ///
+    /// Panics if the MAC has already been finished (it must be reset first)
+    fn update(&mut self, msg: &[u8]);
+
+    /// Finish computing the MAC, returning the computed tag
+    ///
+    /// Panics if the MAC has already been finished (it must be reset first)
+    fn finish(&mut self) -> Block;
+
+    /// Finish computing the MAC, returning the computed tag, and reset the MAC
+    /// so it's ready to process another message
+    ///
+    /// Panics if the MAC has already been finished (it must be reset first)
+    #[inline]
+    fn finish_reset(&mut self) -> Block {
+        let tag = self.finish();
+        self.reset();
+        tag
+    }
+}
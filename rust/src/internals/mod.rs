@@ -5,11 +5,24 @@ mod block;
 pub mod block_cipher;
 mod cmac;
 mod ctr;
-mod xor;
+pub mod hex;
+pub mod mac;
+mod pmac;
+mod polyval;
+mod tag;
+#[cfg(test)]
+mod test_rng;
+pub mod xor;
 
-pub use self::aes::{Aes128, Aes256};
-pub use self::block::Block;
+pub use self::aes::{Aes128, Aes192, Aes256};
+pub use self::block::{Block, Block8, BlockError, BlockMut, BlockRef};
+pub use self::block::{BLOCK8_COUNT, BLOCK8_SIZE};
+pub use self::block::HEX_SIZE as BLOCK_HEX_SIZE;
 pub use self::block::SIZE as BLOCK_SIZE;
 pub use self::block_cipher::BlockCipher;
-pub use self::cmac::Cmac;
-pub use self::ctr::Ctr;
+pub use self::cmac::{cmac, Aes128Cmac, Aes192Cmac, Aes256Cmac, Cmac, CmacKey, MIN_TAG_SIZE};
+pub use self::ctr::{Aes128Ctr, Aes256Ctr, CounterMode, Ctr};
+pub use self::mac::Mac;
+pub use self::pmac::Pmac;
+pub use self::polyval::Polyval;
+pub use self::tag::Tag;
@@ -0,0 +1,367 @@
+//! `internals/pmac.rs`: Parallel Message Authentication Code
+
+use super::{Block, Block8, BlockCipher, Tag, BLOCK8_SIZE, BLOCK_SIZE};
+use super::mac::Mac;
+use core::fmt;
+use error::Error;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// Number of L blocks to precompute (i.e. supports messages up to 2^32 blocks)
+const L_TABLE_SIZE: usize = 32;
+
+/// Parallel Message Authentication Code
+#[derive(Clone)]
+pub struct Pmac<C: BlockCipher> {
+    cipher: C,
+    l: [Block; L_TABLE_SIZE],
+    l_inv: Block,
+    digest: Block,
+    offset: Block,
+    buffer: Block,
+    buffer_pos: usize,
+    buffer8: Block8,
+    counter: usize,
+    finished: bool,
+}
+
+impl<C: BlockCipher> Pmac<C> {
+    /// Create a new PMAC instance with the given cipher
+    #[inline]
+    pub fn new(cipher: C) -> Self {
+        let mut l: [Block; L_TABLE_SIZE] = Default::default();
+        cipher.encrypt(&mut l[0]);
+
+        for i in 1..L_TABLE_SIZE {
+            let (prev, next) = l.split_at_mut(i);
+            next[0].copy_from_block(&prev[i - 1]);
+            next[0].dbl();
+        }
+
+        let mut l_inv = l[0].clone();
+        l_inv.dbl_inv();
+
+        Self {
+            cipher: cipher,
+            l: l,
+            l_inv: l_inv,
+            digest: Block::new(),
+            offset: Block::new(),
+            buffer: Block::new(),
+            buffer_pos: 0,
+            buffer8: Block8::new(),
+            counter: 0,
+            finished: false,
+        }
+    }
+
+    /// Reset a PMAC instance back to its initial state
+    #[inline]
+    pub fn reset(&mut self) {
+        self.digest.clear();
+        self.offset.clear();
+        self.buffer.clear();
+        self.buffer_pos = 0;
+        self.buffer8.clear();
+        self.counter = 0;
+        self.finished = false;
+    }
+
+    /// Update the PMAC state with the given message
+    ///
+    /// Returns `Error::AlreadyFinished` if the MAC has already been finished
+    /// (it must be reset before reusing)
+    pub fn update(&mut self, msg: &[u8]) -> Result<(), Error> {
+        if self.finished {
+            return Err(Error::AlreadyFinished);
+        }
+
+        let mut msg_pos: usize = 0;
+        let mut msg_len: usize = msg.len();
+        let remaining = BLOCK_SIZE - self.buffer_pos;
+
+        // The last block of the message is treated differently, so we only
+        // process buffered data once we know more message data follows it
+        if self.buffer_pos > 0 && msg_len > remaining {
+            {
+                let buffer: &mut [u8] = self.buffer.as_mut();
+                buffer[self.buffer_pos..].copy_from_slice(&msg[..remaining]);
+            }
+
+            msg_len = msg_len.checked_sub(remaining).expect("underflow");
+            msg_pos = msg_pos.checked_add(remaining).expect("overflow");
+
+            self.process_buffer();
+        }
+
+        // Blocks are independent until they're XORed into the digest, so
+        // encrypt eight at a time where the cipher can pipeline them
+        while msg_len > BLOCK8_SIZE {
+            self.process_blocks8(array_ref!(msg, msg_pos, BLOCK8_SIZE));
+
+            msg_len = msg_len.checked_sub(BLOCK8_SIZE).expect("underflow");
+            msg_pos = msg_pos.checked_add(BLOCK8_SIZE).expect("overflow");
+        }
+
+        while msg_len > BLOCK_SIZE {
+            self.buffer = Block::from(*array_ref!(msg, msg_pos, BLOCK_SIZE));
+
+            msg_len = msg_len.checked_sub(BLOCK_SIZE).expect("underflow");
+            msg_pos = msg_pos.checked_add(BLOCK_SIZE).expect("overflow");
+
+            self.process_buffer();
+        }
+
+        if msg_len > 0 {
+            let buffer_end = self.buffer_pos.checked_add(msg_len).expect("overflow");
+
+            let buffer: &mut [u8] = self.buffer.as_mut();
+            buffer[self.buffer_pos..buffer_end]
+                .copy_from_slice(&msg[msg_pos..msg_pos.checked_add(msg_len).expect("overflow")]);
+
+            self.buffer_pos = buffer_end;
+        }
+
+        Ok(())
+    }
+
+    /// Finish computing PMAC, returning the computed tag
+    ///
+    /// Returns `Error::AlreadyFinished` if the MAC has already been finished
+    /// (it must be reset before reusing)
+    #[inline]
+    pub fn finish(&mut self) -> Result<Tag, Error> {
+        self.finish_block().map(Tag::from)
+    }
+
+    /// Finish computing PMAC, returning the raw output block (e.g. for S2V)
+    fn finish_block(&mut self) -> Result<Block, Error> {
+        if self.finished {
+            return Err(Error::AlreadyFinished);
+        }
+
+        if self.buffer_pos == BLOCK_SIZE {
+            self.digest.xor_block(&self.buffer);
+            self.digest.xor_block(&self.l_inv);
+        } else {
+            let padded =
+                Block::from_slice_with_bit_padding(&self.buffer.as_ref()[..self.buffer_pos]).unwrap();
+            self.digest.xor_block(&padded);
+        }
+
+        self.cipher.encrypt(&mut self.digest);
+        self.finished = true;
+
+        Ok(self.digest.clone())
+    }
+
+    /// Process a full block of buffered message data
+    ///
+    /// Panics if the message is longer than 2^32 blocks
+    #[inline]
+    fn process_buffer(&mut self) {
+        self.counter = self.counter.checked_add(1).expect("overflow");
+        self.offset.xor_block(&self.l[self.counter.trailing_zeros() as usize]);
+
+        self.buffer.xor_block(&self.offset);
+        self.cipher.encrypt(&mut self.buffer);
+        self.digest.xor_block(&self.buffer);
+        self.buffer_pos = 0;
+    }
+
+    /// Process eight full blocks of message data, encrypting them together
+    ///
+    /// Panics if the message is longer than 2^32 blocks
+    #[inline]
+    fn process_blocks8(&mut self, msg: &[u8; BLOCK8_SIZE]) {
+        {
+            let bytes: &mut [u8; BLOCK8_SIZE] = self.buffer8.as_mut();
+            bytes.copy_from_slice(msg);
+        }
+
+        for block in self.buffer8.as_blocks_mut().iter_mut() {
+            self.counter = self.counter.checked_add(1).expect("overflow");
+            self.offset.xor_block(&self.l[self.counter.trailing_zeros() as usize]);
+            block.xor_block(&self.offset);
+        }
+
+        self.cipher.encrypt8(&mut self.buffer8);
+
+        for block in self.buffer8.as_blocks().iter() {
+            self.digest.xor_block(block);
+        }
+    }
+}
+
+/// PMAC's key material is the L table, so a freshly created (or reset)
+/// `Pmac` serves as its own key and new instances are clones of it
+impl<C: BlockCipher> Mac<C> for Pmac<C> {
+    type Key = Self;
+
+    #[inline]
+    fn new_key(cipher: C) -> Self {
+        Pmac::new(cipher)
+    }
+
+    #[inline]
+    fn from_key(key: &Self) -> Self {
+        key.clone()
+    }
+
+    #[inline]
+    fn reset(&mut self) {
+        Pmac::reset(self)
+    }
+
+    #[inline]
+    fn update(&mut self, msg: &[u8]) {
+        Pmac::update(self, msg).expect("already finished")
+    }
+
+    #[inline]
+    fn finish(&mut self) -> Block {
+        self.finish_block().expect("already finished")
+    }
+}
+
+impl<C: BlockCipher> Zeroize for Pmac<C> {
+    /// Wipe the cipher's key schedule, the L table, and the MAC state
+    fn zeroize(&mut self) {
+        self.cipher.zeroize();
+
+        for l in self.l.iter_mut() {
+            l.zeroize();
+        }
+
+        self.l_inv.zeroize();
+        self.digest.zeroize();
+        self.offset.zeroize();
+        self.buffer.zeroize();
+        self.buffer_pos.zeroize();
+        self.buffer8.zeroize();
+        self.counter.zeroize();
+        self.finished.zeroize();
+    }
+}
+
+impl<C: BlockCipher> fmt::Debug for Pmac<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Pmac(...redacted...)")
+    }
+}
+
+impl<C: BlockCipher> Drop for Pmac<C> {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
+impl<C: BlockCipher> ZeroizeOnDrop for Pmac<C> {}
+
+#[cfg(test)]
+mod tests {
+    use super::Pmac;
+    use core::cmp;
+    use error::Error;
+    use internals::{Aes128, BLOCK_SIZE};
+    use internals::test_rng::XorShift64;
+
+    /// Key used by the AES-128 PMAC test vectors
+    static KEY: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+        0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+    ];
+
+    /// Compute PMAC over a message in a single update
+    fn pmac(msg: &[u8]) -> [u8; BLOCK_SIZE] {
+        let mut mac = Pmac::new(Aes128::new(&KEY));
+        mac.update(msg).unwrap();
+        let tag = mac.finish().unwrap();
+        *array_ref!(tag.as_ref(), 0, BLOCK_SIZE)
+    }
+
+    #[test]
+    fn empty_message() {
+        assert_eq!(
+            pmac(&[]),
+            [
+                0x43, 0x99, 0x57, 0x2c, 0xd6, 0xea, 0x53, 0x41,
+                0xb8, 0xd3, 0x58, 0x76, 0xa7, 0x09, 0x8a, 0xf7,
+            ]
+        );
+    }
+
+    #[test]
+    fn exactly_one_block() {
+        assert_eq!(
+            pmac(&KEY),
+            [
+                0xeb, 0xbd, 0x82, 0x2f, 0xa4, 0x58, 0xda, 0xf6,
+                0xdf, 0xda, 0xd7, 0xc2, 0x7d, 0xa7, 0x63, 0x38,
+            ]
+        );
+    }
+
+    #[test]
+    fn update_chunking_matches_single_shot() {
+        let mut rng = XorShift64::new(0x3c6e_f372_fe94_f82b);
+        let mut msg = [0u8; 200];
+        rng.fill(&mut msg);
+
+        for len in 0..msg.len() + 1 {
+            let expected = pmac(&msg[..len]);
+
+            for _ in 0..4 {
+                let mut mac = Pmac::new(Aes128::new(&KEY));
+                let mut pos = 0;
+
+                while pos < len {
+                    let chunk = (rng.next_u64() % (3 * BLOCK_SIZE as u64 + 2)) as usize;
+                    let end = cmp::min(pos + chunk, len);
+
+                    mac.update(&msg[pos..end]).unwrap();
+                    pos = end;
+                }
+
+                assert_eq!(mac.finish().unwrap().as_ref(), &expected[..], "len={}", len);
+            }
+        }
+    }
+
+    #[test]
+    fn batched_matches_sequential() {
+        let mut msg = [0u8; 1024];
+        for (i, byte) in msg.iter_mut().enumerate() {
+            *byte = (i * 7) as u8;
+        }
+
+        for &len in &[127, 128, 129, 144, 255, 256, 257, 1000, 1024] {
+            // Updating one block at a time never takes the batched path
+            let mut sequential = Pmac::new(Aes128::new(&KEY));
+            for chunk in msg[..len].chunks(BLOCK_SIZE) {
+                sequential.update(chunk).unwrap();
+            }
+
+            assert_eq!(sequential.finish().unwrap().as_ref(), &pmac(&msg[..len])[..], "len={}", len);
+        }
+    }
+
+    #[test]
+    fn reset() {
+        let mut mac = Pmac::new(Aes128::new(&KEY));
+        mac.update(b"some other message").unwrap();
+        mac.finish().unwrap();
+
+        mac.reset();
+        mac.update(&KEY).unwrap();
+        assert_eq!(mac.finish().unwrap().as_ref(), &pmac(&KEY)[..]);
+    }
+
+    #[test]
+    fn use_after_finish() {
+        let mut mac = Pmac::new(Aes128::new(&KEY));
+        mac.finish().unwrap();
+
+        assert_eq!(mac.update(&KEY), Err(Error::AlreadyFinished));
+        assert_eq!(mac.finish().err(), Some(Error::AlreadyFinished));
+    }
+}
@@ -0,0 +1,384 @@
+//! `internals/polyval.rs`: POLYVAL universal hash (RFC 8452)
+//!
+//! POLYVAL is the little-endian counterpart of GHASH, used by AES-GCM-SIV.
+//! On x86/x86_64 CPUs with `pclmulqdq` the field multiplication uses the
+//! carry-less multiply instruction. Other CPUs use a portable constant-time
+//! software multiplication. Like AES, the backend is selected when the hash
+//! is keyed: at runtime with the `std` feature, or from the compile-time
+//! target features (e.g. `-C target-feature=+pclmulqdq`) otherwise.
+
+use super::{Block, BLOCK_SIZE};
+use byteorder::{ByteOrder, LittleEndian};
+use core::fmt;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+/// Implementation used to perform field multiplications
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Backend {
+    /// Portable constant-time software implementation
+    Soft,
+
+    /// Intel `pclmulqdq` carry-less multiplication
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    Clmul,
+}
+
+impl Backend {
+    /// Select the fastest backend supported by the current CPU
+    #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "std"))]
+    fn detect() -> Self {
+        if is_x86_feature_detected!("pclmulqdq") {
+            Backend::Clmul
+        } else {
+            Backend::Soft
+        }
+    }
+
+    /// Select `pclmulqdq` if the crate was compiled with it enabled
+    #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), not(feature = "std")))]
+    fn detect() -> Self {
+        if cfg!(target_feature = "pclmulqdq") {
+            Backend::Clmul
+        } else {
+            Backend::Soft
+        }
+    }
+
+    /// No hardware backends are available on this architecture
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    fn detect() -> Self {
+        Backend::Soft
+    }
+
+    /// Compute `x = x * h * x^-128`, i.e. POLYVAL's `dot` operation
+    #[inline]
+    fn mul(self, x: &mut Block, h: &Block) {
+        match self {
+            Backend::Soft => soft::mul(x, h),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Clmul => unsafe { clmul::mul(x, h) },
+        }
+    }
+}
+
+/// POLYVAL universal hash, keyed with the block `H`
+///
+/// Unlike CMAC, POLYVAL processes whole blocks and its output isn't a MAC by
+/// itself: AES-GCM-SIV encrypts it to produce the tag. `update_padded`
+/// zero-pads partial blocks as AES-GCM-SIV does for its inputs.
+#[derive(Clone)]
+pub struct Polyval {
+    h: Block,
+    state: Block,
+    backend: Backend,
+}
+
+impl Polyval {
+    /// Create a new POLYVAL instance with the given key
+    #[inline]
+    pub fn new(key: Block) -> Self {
+        Self::with_backend(key, Backend::detect())
+    }
+
+    /// Create a new POLYVAL instance which uses the given backend
+    #[inline]
+    fn with_backend(key: Block, backend: Backend) -> Self {
+        Self {
+            h: key,
+            state: Block::new(),
+            backend: backend,
+        }
+    }
+
+    /// Reset a POLYVAL instance back to its initial state
+    #[inline]
+    pub fn reset(&mut self) {
+        self.state.clear();
+    }
+
+    /// Update the POLYVAL state with the given blocks
+    #[inline]
+    pub fn update(&mut self, blocks: &[Block]) {
+        for block in blocks {
+            self.state.xor_block(block);
+            self.backend.mul(&mut self.state, &self.h);
+        }
+    }
+
+    /// Update the POLYVAL state with the given message, zero-padding the
+    /// last block if it's partial
+    pub fn update_padded(&mut self, msg: &[u8]) {
+        for chunk in msg.chunks(BLOCK_SIZE) {
+            let mut block = Block::new();
+            {
+                let bytes: &mut [u8] = block.as_mut();
+                bytes[..chunk.len()].copy_from_slice(chunk);
+            }
+
+            self.update(&[block]);
+        }
+    }
+
+    /// Finish computing POLYVAL, returning the result and resetting the
+    /// state so the instance can be reused with the same key
+    #[inline]
+    pub fn finish(&mut self) -> Block {
+        let result = self.state.clone();
+        self.reset();
+        result
+    }
+}
+
+impl Zeroize for Polyval {
+    /// Wipe the key and the hash state
+    fn zeroize(&mut self) {
+        self.h.zeroize();
+        self.state.zeroize();
+    }
+}
+
+impl fmt::Debug for Polyval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Polyval(...redacted...)")
+    }
+}
+
+impl Drop for Polyval {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
+impl ZeroizeOnDrop for Polyval {}
+
+/// Portable constant-time field multiplication, using integer multiplies
+/// with "holes" in the operands so carries can't spread between bits (as in
+/// BearSSL's `ghash_ctmul64`)
+mod soft {
+    use super::{as_u64x2, from_u64x2};
+    use internals::Block;
+
+    /// Compute `x = x * h * x^-128`
+    pub fn mul(x: &mut Block, h: &Block) {
+        let (x0, x1) = as_u64x2(x);
+        let (h0, h1) = as_u64x2(h);
+
+        let x0r = rev64(x0);
+        let x1r = rev64(x1);
+        let x2 = x0 ^ x1;
+        let x2r = x0r ^ x1r;
+
+        let h0r = rev64(h0);
+        let h1r = rev64(h1);
+        let h2 = h0 ^ h1;
+        let h2r = h0r ^ h1r;
+
+        // Karatsuba multiplication of the low and (bit-reversed) high halves
+        let z0 = bmul64(x0, h0);
+        let z1 = bmul64(x1, h1);
+        let mut z2 = bmul64(x2, h2);
+        let mut z0h = bmul64(x0r, h0r);
+        let mut z1h = bmul64(x1r, h1r);
+        let mut z2h = bmul64(x2r, h2r);
+
+        z2 ^= z0 ^ z1;
+        z2h ^= z0h ^ z1h;
+        z0h = rev64(z0h) >> 1;
+        z1h = rev64(z1h) >> 1;
+        z2h = rev64(z2h) >> 1;
+
+        let v0 = z0;
+        let mut v1 = z0h ^ z2;
+        let mut v2 = z1 ^ z2h;
+        let mut v3 = z1h;
+
+        // Montgomery reduction by x^128 + x^127 + x^126 + x^121 + 1
+        v2 ^= v0 ^ (v0 >> 1) ^ (v0 >> 2) ^ (v0 >> 7);
+        v1 ^= (v0 << 63) ^ (v0 << 62) ^ (v0 << 57);
+        v3 ^= v1 ^ (v1 >> 1) ^ (v1 >> 2) ^ (v1 >> 7);
+        v2 ^= (v1 << 63) ^ (v1 << 62) ^ (v1 << 57);
+
+        from_u64x2(x, v2, v3);
+    }
+
+    /// Carry-less multiplication of two 64-bit values, keeping the low half
+    #[inline]
+    fn bmul64(x: u64, y: u64) -> u64 {
+        let x0 = x & 0x1111_1111_1111_1111;
+        let x1 = x & 0x2222_2222_2222_2222;
+        let x2 = x & 0x4444_4444_4444_4444;
+        let x3 = x & 0x8888_8888_8888_8888;
+        let y0 = y & 0x1111_1111_1111_1111;
+        let y1 = y & 0x2222_2222_2222_2222;
+        let y2 = y & 0x4444_4444_4444_4444;
+        let y3 = y & 0x8888_8888_8888_8888;
+
+        let z0 = x0.wrapping_mul(y0) ^ x1.wrapping_mul(y3) ^ x2.wrapping_mul(y2) ^ x3.wrapping_mul(y1);
+        let z1 = x0.wrapping_mul(y1) ^ x1.wrapping_mul(y0) ^ x2.wrapping_mul(y3) ^ x3.wrapping_mul(y2);
+        let z2 = x0.wrapping_mul(y2) ^ x1.wrapping_mul(y1) ^ x2.wrapping_mul(y0) ^ x3.wrapping_mul(y3);
+        let z3 = x0.wrapping_mul(y3) ^ x1.wrapping_mul(y2) ^ x2.wrapping_mul(y1) ^ x3.wrapping_mul(y0);
+
+        (z0 & 0x1111_1111_1111_1111) | (z1 & 0x2222_2222_2222_2222) | (z2 & 0x4444_4444_4444_4444)
+            | (z3 & 0x8888_8888_8888_8888)
+    }
+
+    /// Reverse the bits of a 64-bit value
+    #[inline]
+    fn rev64(mut x: u64) -> u64 {
+        x = ((x >> 1) & 0x5555_5555_5555_5555) | ((x & 0x5555_5555_5555_5555) << 1);
+        x = ((x >> 2) & 0x3333_3333_3333_3333) | ((x & 0x3333_3333_3333_3333) << 2);
+        x = ((x >> 4) & 0x0f0f_0f0f_0f0f_0f0f) | ((x & 0x0f0f_0f0f_0f0f_0f0f) << 4);
+        x.swap_bytes()
+    }
+}
+
+/// Field multiplication using the `pclmulqdq` instruction
+///
+/// All functions here are `unsafe` as they must only be called on CPUs which
+/// support `pclmulqdq`. `Backend::detect()` is responsible for checking that.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod clmul {
+    use super::*;
+
+    /// Compute `x = x * h * x^-128`
+    #[target_feature(enable = "pclmulqdq", enable = "sse2")]
+    pub unsafe fn mul(x: &mut Block, h: &Block) {
+        let bytes: &mut [u8] = x.as_mut();
+        let y = _mm_load_si128(bytes.as_ptr() as *const __m128i);
+        let h = _mm_load_si128(h.as_ref().as_ptr() as *const __m128i);
+
+        // Karatsuba multiplication of the 64-bit halves
+        let y2 = _mm_xor_si128(y, _mm_shuffle_epi32(y, 0x0e));
+        let h2 = _mm_xor_si128(h, _mm_shuffle_epi32(h, 0x0e));
+
+        let t0 = _mm_clmulepi64_si128(y, h, 0x00);
+        let t1 = _mm_clmulepi64_si128(y, h, 0x11);
+        let t2 = _mm_clmulepi64_si128(y2, h2, 0x00);
+        let t2 = _mm_xor_si128(t2, _mm_xor_si128(t0, t1));
+
+        // Only the low 64 bits of each of these are used
+        let v0 = t0;
+        let v1 = _mm_xor_si128(_mm_shuffle_epi32(t0, 0x0e), t2);
+        let v2 = _mm_xor_si128(t1, _mm_shuffle_epi32(t2, 0x0e));
+        let v3 = _mm_shuffle_epi32(t1, 0x0e);
+
+        // Montgomery reduction by x^128 + x^127 + x^126 + x^121 + 1
+        let v2 = xor4(v2, v0, _mm_srli_epi64(v0, 1), _mm_xor_si128(_mm_srli_epi64(v0, 2), _mm_srli_epi64(v0, 7)));
+        let v1 = xor4(v1, _mm_slli_epi64(v0, 63), _mm_slli_epi64(v0, 62), _mm_slli_epi64(v0, 57));
+        let v3 = xor4(v3, v1, _mm_srli_epi64(v1, 1), _mm_xor_si128(_mm_srli_epi64(v1, 2), _mm_srli_epi64(v1, 7)));
+        let v2 = xor4(v2, _mm_slli_epi64(v1, 63), _mm_slli_epi64(v1, 62), _mm_slli_epi64(v1, 57));
+
+        _mm_store_si128(bytes.as_mut_ptr() as *mut __m128i, _mm_unpacklo_epi64(v2, v3));
+    }
+
+    /// XOR four values together
+    #[inline(always)]
+    unsafe fn xor4(a: __m128i, b: __m128i, c: __m128i, d: __m128i) -> __m128i {
+        _mm_xor_si128(_mm_xor_si128(a, b), _mm_xor_si128(c, d))
+    }
+}
+
+/// Load a block as little endian 64-bit halves (low, high)
+#[inline]
+fn as_u64x2(block: &Block) -> (u64, u64) {
+    let bytes = block.as_ref();
+    (LittleEndian::read_u64(&bytes[..8]), LittleEndian::read_u64(&bytes[8..]))
+}
+
+/// Store little endian 64-bit halves (low, high) into a block
+#[inline]
+fn from_u64x2(block: &mut Block, lo: u64, hi: u64) {
+    let bytes: &mut [u8] = block.as_mut();
+    LittleEndian::write_u64(&mut bytes[..8], lo);
+    LittleEndian::write_u64(&mut bytes[8..], hi);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Backend, Polyval};
+    use internals::Block;
+    use internals::test_rng::XorShift64;
+
+    /// RFC 8452 Appendix A
+    #[test]
+    fn rfc8452_appendix_a() {
+        let h = Block::from([
+            0x25, 0x62, 0x93, 0x47, 0x58, 0x92, 0x42, 0x76,
+            0x1d, 0x31, 0xf8, 0x26, 0xba, 0x4b, 0x75, 0x7b,
+        ]);
+
+        let blocks = [
+            Block::from([
+                0x4f, 0x4f, 0x95, 0x66, 0x8c, 0x83, 0xdf, 0xb6,
+                0x40, 0x17, 0x62, 0xbb, 0x2d, 0x01, 0xa2, 0x62,
+            ]),
+            Block::from([
+                0xd1, 0xa2, 0x4d, 0xdd, 0x27, 0x21, 0xd0, 0x06,
+                0xbb, 0xe4, 0x5f, 0x20, 0xd3, 0xc9, 0xf3, 0x62,
+            ]),
+        ];
+
+        let expected = [
+            0xf7, 0xa3, 0xb4, 0x7b, 0x84, 0x61, 0x19, 0xfa,
+            0xe5, 0xb7, 0x86, 0x6c, 0xf5, 0xe5, 0xb7, 0x7e,
+        ];
+
+        let mut polyval = Polyval::with_backend(h.clone(), Backend::Soft);
+        polyval.update(&blocks);
+        assert_eq!(polyval.finish().as_ref(), &expected);
+
+        let mut polyval = Polyval::new(h);
+        polyval.update(&blocks);
+        assert_eq!(polyval.finish().as_ref(), &expected);
+
+        // Finishing resets the state
+        polyval.update(&blocks);
+        assert_eq!(polyval.finish().as_ref(), &expected);
+    }
+
+    /// RFC 8452 Appendix C.1: POLYVAL over the 8-byte plaintext `01` followed
+    /// by the length block
+    #[test]
+    fn rfc8452_update_padded() {
+        let h = Block::from([
+            0xd9, 0xb3, 0x60, 0x27, 0x96, 0x94, 0x94, 0x1a,
+            0xc5, 0xdb, 0xc6, 0x98, 0x7a, 0xda, 0x73, 0x77,
+        ]);
+
+        let mut polyval = Polyval::new(h);
+        polyval.update_padded(&[1, 0, 0, 0, 0, 0, 0, 0]);
+        polyval.update_padded(&[0, 0, 0, 0, 0, 0, 0, 0, 0x40, 0, 0, 0, 0, 0, 0, 0]);
+
+        assert_eq!(
+            polyval.finish().as_ref(),
+            &[
+                0xeb, 0x93, 0xb7, 0x74, 0x09, 0x62, 0xc5, 0xe4,
+                0x9d, 0x2a, 0x90, 0xa7, 0xdc, 0x5c, 0xec, 0x74,
+            ]
+        );
+    }
+
+    #[test]
+    fn backends_agree() {
+        let backend = Backend::detect();
+
+        let mut rng = XorShift64::new(0x6a09_e667_f3bc_c908);
+
+        for _ in 0..64 {
+            let h = rng.block();
+            let blocks = [rng.block(), rng.block(), rng.block()];
+
+            let mut soft = Polyval::with_backend(h.clone(), Backend::Soft);
+            let mut detected = Polyval::with_backend(h, backend);
+            soft.update(&blocks);
+            detected.update(&blocks);
+
+            assert_eq!(soft.finish().as_ref(), detected.finish().as_ref());
+        }
+    }
+}
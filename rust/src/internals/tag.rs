@@ -0,0 +1,281 @@
+//! `internals/tag.rs`: Message authentication tags
+
+use super::{Block, BLOCK_SIZE};
+#[cfg(feature = "serde")]
+use super::hex;
+use core::convert::TryFrom;
+use core::fmt;
+#[cfg(feature = "serde")]
+use core::str;
+use error::Error;
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::Zeroize;
+
+/// A message authentication tag, either computed by a MAC (e.g.
+/// `Cmac::finish`) or parsed from a received message
+///
+/// Received tags may be truncated to anywhere from 1 to `BLOCK_SIZE` bytes.
+/// Tags compare in constant time (tags of different lengths never match),
+/// and are wiped when dropped.
+///
+/// With the `serde` feature, tags serialize as lowercase hex for
+/// human-readable formats and as raw bytes otherwise. SIV tags, which are
+/// returned as arrays, can be serialized by converting them with `Tag::from`.
+#[derive(Clone)]
+pub struct Tag {
+    bytes: [u8; BLOCK_SIZE],
+    len: usize,
+}
+
+impl Tag {
+    /// Parse a tag from a slice of 1 to `BLOCK_SIZE` bytes
+    ///
+    /// Returns `Error::BlockSize` if the slice is empty or too long
+    #[inline]
+    pub fn from_slice(slice: &[u8]) -> Result<Tag, Error> {
+        if slice.is_empty() || slice.len() > BLOCK_SIZE {
+            return Err(Error::BlockSize { len: slice.len() });
+        }
+
+        let mut bytes = [0u8; BLOCK_SIZE];
+        bytes[..slice.len()].copy_from_slice(slice);
+
+        Ok(Tag {
+            bytes: bytes,
+            len: slice.len(),
+        })
+    }
+}
+
+impl From<Block> for Tag {
+    /// Use a full-length computed MAC block as a tag
+    #[inline]
+    fn from(block: Block) -> Tag {
+        Tag {
+            bytes: *array_ref!(block.as_ref(), 0, BLOCK_SIZE),
+            len: BLOCK_SIZE,
+        }
+    }
+}
+
+impl From<[u8; BLOCK_SIZE]> for Tag {
+    /// Use a full-length tag returned as an array (e.g. by
+    /// `Siv::seal_in_place_detached`) as a `Tag`
+    #[inline]
+    fn from(bytes: [u8; BLOCK_SIZE]) -> Tag {
+        Tag {
+            bytes: bytes,
+            len: BLOCK_SIZE,
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for Tag {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(slice: &[u8]) -> Result<Tag, Error> {
+        Tag::from_slice(slice)
+    }
+}
+
+impl AsRef<[u8]> for Tag {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+impl ConstantTimeEq for Tag {
+    /// Compare tags in constant time. Lengths aren't secret, so tags of
+    /// different lengths are rejected up front.
+    #[inline]
+    fn ct_eq(&self, other: &Tag) -> Choice {
+        if self.len != other.len {
+            return Choice::from(0);
+        }
+
+        self.as_ref().ct_eq(other.as_ref())
+    }
+}
+
+impl PartialEq for Tag {
+    /// Compare tags in constant time
+    #[inline]
+    fn eq(&self, other: &Tag) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for Tag {}
+
+impl fmt::Debug for Tag {
+    #[cfg(not(feature = "danger-debug"))]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Tag(...redacted...)")
+    }
+
+    #[cfg(feature = "danger-debug")]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Tag(")?;
+
+        for byte in self.as_ref().iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+
+        write!(f, ")")
+    }
+}
+
+impl Zeroize for Tag {
+    #[inline]
+    fn zeroize(&mut self) {
+        self.bytes.as_mut().zeroize();
+    }
+}
+
+impl Drop for Tag {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Tag {
+    /// Serialize as a lowercase hex string for human-readable formats (e.g.
+    /// JSON), or as raw bytes otherwise (e.g. bincode)
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            let mut hex_bytes = [0u8; BLOCK_SIZE * 2];
+            let hex_bytes = &mut hex_bytes[..self.len * 2];
+            hex::encode(self.as_ref(), hex_bytes);
+            serializer.serialize_str(str::from_utf8(hex_bytes).expect("hex is valid UTF-8"))
+        } else {
+            serializer.serialize_bytes(self.as_ref())
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Tag {
+    /// Deserialize from the encoding produced by `serialize`, accepting
+    /// tags of 1 to `BLOCK_SIZE` bytes
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Tag, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(TagVisitor)
+        } else {
+            deserializer.deserialize_bytes(TagVisitor)
+        }
+    }
+}
+
+/// Visitor for parsing tags from hex strings or raw bytes
+#[cfg(feature = "serde")]
+struct TagVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> de::Visitor<'de> for TagVisitor {
+    type Value = Tag;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a tag of 1 to {} bytes", BLOCK_SIZE)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Tag, E> {
+        if v.is_empty() || v.len() > BLOCK_SIZE * 2 || v.len() % 2 != 0 {
+            return Err(E::invalid_length(v.len() / 2, &self));
+        }
+
+        let mut bytes = [0u8; BLOCK_SIZE];
+        let len = v.len() / 2;
+        let result = match hex::decode(v.as_bytes(), &mut bytes[..len]) {
+            Ok(()) => Ok(Tag::from_slice(&bytes[..len]).expect("length checked above")),
+            Err(_) => Err(E::invalid_value(de::Unexpected::Str(v), &self)),
+        };
+
+        bytes.zeroize();
+        result
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Tag, E> {
+        Tag::from_slice(v).map_err(|_| E::invalid_length(v.len(), &self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Tag;
+    use core::{mem, ptr};
+    use core::convert::TryFrom;
+    use error::Error;
+    use internals::{Block, BLOCK_SIZE};
+
+    #[test]
+    fn equality() {
+        let bytes = [0x42u8; BLOCK_SIZE];
+        let tag = Tag::from(Block::from(bytes));
+
+        assert_eq!(tag, Tag::from_slice(&bytes).unwrap());
+        assert_eq!(tag.as_ref(), &bytes[..]);
+
+        for i in 0..BLOCK_SIZE {
+            let mut other = bytes;
+            other[i] ^= 1;
+            assert_ne!(tag, Tag::from_slice(&other).unwrap());
+        }
+    }
+
+    #[test]
+    fn mismatched_lengths_never_match() {
+        let bytes = [0x42u8; BLOCK_SIZE];
+        let tag = Tag::from_slice(&bytes).unwrap();
+
+        for len in 1..BLOCK_SIZE {
+            let truncated = Tag::from_slice(&bytes[..len]).unwrap();
+            assert_eq!(truncated.as_ref(), &bytes[..len]);
+            assert_ne!(tag, truncated);
+            assert_ne!(truncated, tag);
+        }
+    }
+
+    #[test]
+    fn try_from_rejects_invalid_lengths() {
+        let bytes = [0u8; BLOCK_SIZE + 1];
+
+        assert_eq!(Tag::try_from(&bytes[..0]).err(), Some(Error::BlockSize { len: 0 }));
+        assert_eq!(
+            Tag::try_from(&bytes[..]).err(),
+            Some(Error::BlockSize { len: BLOCK_SIZE + 1 })
+        );
+        assert!(Tag::try_from(&bytes[..1]).is_ok());
+        assert!(Tag::try_from(&bytes[..BLOCK_SIZE]).is_ok());
+    }
+
+    #[test]
+    fn zeroize_on_drop() {
+        let mut tag = mem::ManuallyDrop::new(Tag::from_slice(&[0x42u8; BLOCK_SIZE]).unwrap());
+
+        unsafe {
+            ptr::drop_in_place(&mut *tag);
+            assert_eq!(tag.bytes, [0u8; BLOCK_SIZE]);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn zeroize_on_drop_after_deserializing() {
+        use serde::Deserialize;
+        use serde::de::value::{Error as ValueError, StrDeserializer};
+
+        let hex = StrDeserializer::<ValueError>::new("000102030405060708090a0b0c0d0e0f");
+        let mut tag = mem::ManuallyDrop::new(Tag::deserialize(hex).unwrap());
+        assert_eq!(tag.len, BLOCK_SIZE);
+
+        unsafe {
+            ptr::drop_in_place(&mut *tag);
+            assert_eq!(tag.bytes, [0u8; BLOCK_SIZE]);
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! `internals/test_rng.rs`: Deterministic pseudorandom inputs for tests
+
+use internals::{Block, BLOCK_SIZE};
+
+/// xorshift64 PRNG, so randomized tests see the same inputs on every run.
+/// Only for choosing test inputs: it's in no way cryptographically secure!
+pub struct XorShift64(u64);
+
+impl XorShift64 {
+    /// Create a new generator from the given (nonzero) seed
+    pub fn new(seed: u64) -> Self {
+        debug_assert!(seed != 0, "xorshift64 seed must be nonzero");
+        XorShift64(seed)
+    }
+
+    /// Step the generator, returning its new state
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Fill the given buffer, stepping the generator once per byte
+    pub fn fill(&mut self, buffer: &mut [u8]) {
+        for byte in buffer.iter_mut() {
+            *byte = self.next_u64() as u8;
+        }
+    }
+
+    /// Generate a pseudorandom block
+    pub fn block(&mut self) -> Block {
+        let mut bytes = [0u8; BLOCK_SIZE];
+        self.fill(&mut bytes);
+        Block::from(bytes)
+    }
+}
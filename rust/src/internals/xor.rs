@@ -1,13 +1,76 @@
 //! `internals/xor.rs`: XOR as an in-place bytestring operation
 
+use core::{mem, ptr};
+
+/// Number of bytes XORed at a time by `in_place`
+const WIDTH: usize = mem::size_of::<u128>();
+
 /// XOR the second argument into the first in-place. Slices do not have to be
 /// aligned in memory.
 ///
+/// Works 16 bytes at a time using unaligned `u128` reads and writes (which
+/// compile to SSE2/NEON loads and stores), with a byte-at-a-time tail.
+///
 /// Panics if the two slices aren't the same length
 pub fn in_place(a: &mut [u8], b: &[u8]) {
     assert_eq!(a.len(), b.len(), "slices are not the same length!");
 
-    for (b1, b2) in a.iter_mut().zip(b.iter()) {
+    let wide_len = a.len() - a.len() % WIDTH;
+    let (a_wide, a_tail) = a.split_at_mut(wide_len);
+    let (b_wide, b_tail) = b.split_at(wide_len);
+
+    for (x, y) in a_wide.chunks_mut(WIDTH).zip(b_wide.chunks(WIDTH)) {
+        let x = x.as_mut_ptr() as *mut u128;
+        let y = y.as_ptr() as *const u128;
+
+        unsafe {
+            ptr::write_unaligned(x, ptr::read_unaligned(x) ^ ptr::read_unaligned(y));
+        }
+    }
+
+    for (b1, b2) in a_tail.iter_mut().zip(b_tail.iter()) {
         *b1 ^= *b2;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{in_place, WIDTH};
+
+    #[test]
+    fn matches_bytewise_xor_at_every_offset() {
+        let mut a_buf = [0u8; 64 + WIDTH];
+        let mut b_buf = [0u8; 64 + WIDTH];
+
+        for (i, (x, y)) in a_buf.iter_mut().zip(b_buf.iter_mut()).enumerate() {
+            *x = (i * 7 + 3) as u8;
+            *y = (i * 13 + 5) as u8;
+        }
+
+        for len in 0..64 {
+            for a_offset in 0..WIDTH {
+                for b_offset in 0..WIDTH {
+                    let mut a = a_buf;
+                    let b = &b_buf[b_offset..b_offset + len];
+                    in_place(&mut a[a_offset..a_offset + len], b);
+
+                    for i in 0..a.len() {
+                        let expected = if i >= a_offset && i < a_offset + len {
+                            a_buf[i] ^ b[i - a_offset]
+                        } else {
+                            a_buf[i]
+                        };
+
+                        assert_eq!(a[i], expected, "len={} offsets={},{}", len, a_offset, b_offset);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn mismatched_lengths() {
+        in_place(&mut [0u8; 16], &[0u8; 15]);
+    }
+}
@@ -0,0 +1,138 @@
+//! `kdf.rs`: NIST SP 800-108 key derivation in counter mode with AES-CMAC
+//!
+//! Derives keys (e.g. the 32 or 64-byte keys used by SIV) from a master
+//! secret. Each 16-byte block of output is the CMAC of:
+//!
+//! ```text
+//! counter || label || 0x00 || context || length
+//! ```
+//!
+//! where `counter` is the 32-bit big endian block number starting from 1, and
+//! `length` is the total output length in bits as a 32-bit big endian
+//! integer. Everything after the counter is SP 800-108's fixed input data,
+//! which `derive_key_from_fixed_input` takes as-is: that's the CAVP KBKDF
+//! configuration with the counter before the fixed input data and an `r` of
+//! 32 bits, whose published vectors it's tested against.
+
+use byteorder::{BigEndian, ByteOrder};
+use error::Error;
+use internals::{Aes128, Aes192, Aes256, BLOCK_SIZE, BlockCipher, CmacKey};
+
+/// Fill `out` with key material derived from `prf_key` (an AES-128, AES-192,
+/// or AES-256 key), the given label, and the given context
+///
+/// Returns `Error::KeySize` if `prf_key` isn't 16, 24, or 32 bytes.
+///
+/// # Panics
+///
+/// Panics if `out` is 2^29 bytes or longer, as its length in bits must fit
+/// in 32 bits.
+pub fn derive_key(
+    prf_key: &[u8],
+    label: &[u8],
+    context: &[u8],
+    out: &mut [u8],
+) -> Result<(), Error> {
+    let mut length = [0u8; 4];
+    BigEndian::write_u32(&mut length, output_bits(out));
+
+    derive_with_key(prf_key, &[label, &[0], context, &length[..]], out)
+}
+
+/// Fill `out` with key material derived from `prf_key` (an AES-128, AES-192,
+/// or AES-256 key) and the given fixed input data, which follows the counter
+/// in every block's input. Unlike `derive_key`, nothing is added to it, so
+/// the caller is responsible for encoding the label, context, and length.
+///
+/// Returns `Error::KeySize` if `prf_key` isn't 16, 24, or 32 bytes.
+///
+/// # Panics
+///
+/// Panics if `out` is 2^29 bytes or longer, as its length in bits must fit
+/// in 32 bits.
+pub fn derive_key_from_fixed_input(
+    prf_key: &[u8],
+    fixed_input: &[u8],
+    out: &mut [u8],
+) -> Result<(), Error> {
+    derive_with_key(prf_key, &[fixed_input], out)
+}
+
+/// Derive key material with an AES cipher of `prf_key`'s size, as described
+/// in `derive_key_from_fixed_input`
+fn derive_with_key(prf_key: &[u8], fixed_input: &[&[u8]], out: &mut [u8]) -> Result<(), Error> {
+    match prf_key.len() {
+        16 => derive(Aes128::new(array_ref!(prf_key, 0, 16)), fixed_input, out),
+        24 => derive(Aes192::new(array_ref!(prf_key, 0, 24)), fixed_input, out),
+        32 => derive(Aes256::new(array_ref!(prf_key, 0, 32)), fixed_input, out),
+        _ => return Err(Error::KeySize),
+    }
+
+    Ok(())
+}
+
+/// Derive key material with the given block cipher from the concatenation of
+/// the `fixed_input` slices
+fn derive<C: BlockCipher>(cipher: C, fixed_input: &[&[u8]], out: &mut [u8]) {
+    // Bounding the length in bits also keeps the block counter from wrapping
+    output_bits(out);
+
+    // The subkeys are computed once and reused for every block
+    let mut mac = CmacKey::new(cipher).mac();
+    let mut counter = [0u8; 4];
+
+    for (i, chunk) in out.chunks_mut(BLOCK_SIZE).enumerate() {
+        BigEndian::write_u32(&mut counter, i as u32 + 1);
+        mac.update(&counter).unwrap();
+
+        for input in fixed_input {
+            mac.update(input).unwrap();
+        }
+
+        let block = mac.finish_reset();
+        chunk.copy_from_slice(&block.as_ref()[..chunk.len()]);
+    }
+}
+
+/// Length of `out` in bits, panicking if it doesn't fit in 32 bits
+fn output_bits(out: &[u8]) -> u32 {
+    let bits = (out.len() as u64)
+        .checked_mul(8)
+        .expect("overflow");
+
+    if bits > u64::from(u32::max_value()) {
+        panic!("KDF output too long");
+    }
+
+    bits as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::derive_key;
+    use error::Error;
+
+    #[test]
+    fn invalid_key_size() {
+        let mut out = [0u8; 32];
+
+        for &len in &[0, 15, 17, 64] {
+            let key = [0u8; 64];
+            assert_eq!(derive_key(&key[..len], b"", b"", &mut out).err(), Some(Error::KeySize));
+        }
+    }
+
+    #[test]
+    fn output_length_is_bound_into_every_block() {
+        let key = [0x42u8; 16];
+        let mut short = [0u8; 20];
+        let mut long = [0u8; 32];
+
+        derive_key(&key, b"label", b"context", &mut short).unwrap();
+        derive_key(&key, b"label", b"context", &mut long).unwrap();
+
+        // The length is part of every block's input, so a shorter output
+        // isn't a prefix of a longer one
+        assert_ne!(&short[..16], &long[..16]);
+    }
+}
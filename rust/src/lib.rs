@@ -12,18 +12,69 @@
 // Experimental features
 // TODO: make crate work on stable
 #![feature(i128_type)]
-#![feature(asm)]
 #![feature(attr_literals)]
-#![feature(core_intrinsics)]
+#![feature(const_fn)]
 #![feature(repr_align)]
+#![feature(try_from)]
 
+#[cfg(feature = "aead")]
+extern crate aead;
 #[macro_use]
 extern crate arrayref;
 extern crate byteorder;
+#[cfg(feature = "base64")]
+extern crate base64;
+#[cfg(feature = "cipher")]
+extern crate cipher;
+#[cfg(feature = "crypto-mac")]
+extern crate crypto_mac;
+#[cfg(feature = "std")]
+extern crate getrandom;
+#[cfg(all(test, feature = "std"))]
+#[macro_use]
+extern crate proptest;
+#[cfg(feature = "hkdf")]
+extern crate hkdf;
+#[cfg(feature = "rand_core")]
+extern crate rand_core;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "hkdf")]
+extern crate sha2;
+#[cfg(any(feature = "std", test))]
+#[macro_use]
+extern crate std;
 extern crate subtle;
+#[cfg(feature = "wasm-bindgen")]
+extern crate wasm_bindgen;
+#[cfg(all(test, feature = "wasm-bindgen", target_arch = "wasm32"))]
+extern crate wasm_bindgen_test;
+extern crate zeroize;
 
+pub mod drbg;
+mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod file;
 // TODO: reduce visibility by gating it on e.g. #[cfg(debug_assertions)]
 pub mod internals;
+pub mod gcm_siv;
+pub mod kdf;
 pub mod siv;
+#[cfg(feature = "std")]
+pub mod stream;
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
 
-pub use siv::{Aes128Siv, Aes256Siv};
+pub use drbg::CtrDrbg;
+pub use error::Error;
+#[cfg(feature = "std")]
+pub use file::{decrypt_file, encrypt_file, FileError};
+pub use gcm_siv::{Aes128GcmSiv, Aes256GcmSiv};
+pub use internals::{Aes128Ctr, Aes256Ctr, CounterMode, Ctr};
+pub use siv::{Aes128PmacSiv, Aes128Siv, Aes192Siv, Aes256PmacSiv, Aes256Siv};
+#[cfg(feature = "std")]
+pub use siv::{Aes128KeyWrap, Aes256KeyWrap, KeyWrap, NonceTracker};
+#[cfg(feature = "wasm-bindgen")]
+pub use wasm::WasmAesSiv;
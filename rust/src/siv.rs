@@ -1,10 +1,39 @@
 //! `siv.rs`: The SIV misuse resistant block cipher mode of operation
 
+#[cfg(feature = "std")]
+use core::iter;
+use core::marker::PhantomData;
 use core::ptr;
 
-use internals::{Aes128, Aes256};
-use internals::{BLOCK_SIZE, Block, BlockCipher, Cmac, Ctr};
-use subtle::CTEq;
+#[cfg(feature = "aead")]
+use aead::{self, AeadCore, AeadInPlace, Key, NewAead, Nonce};
+#[cfg(feature = "aead")]
+use aead::generic_array::GenericArray;
+#[cfg(feature = "aead")]
+use aead::generic_array::typenum::{U0, U16, U32, U48, U64};
+use internals::{Aes128, Aes192, Aes256};
+#[cfg(feature = "encoding")]
+use base64;
+use error::Error;
+#[cfg(feature = "hkdf")]
+use hkdf::Hkdf;
+use internals::{BLOCK_SIZE, Block, BlockCipher, Cmac, Ctr, Mac, Pmac};
+#[cfg(feature = "encoding")]
+use internals::hex;
+#[cfg(feature = "rand_core")]
+use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "hkdf")]
+use sha2::Sha256;
+#[cfg(feature = "std")]
+use std::collections::{HashSet, VecDeque};
+#[cfg(feature = "encoding")]
+use std::string::String;
+#[cfg(feature = "std")]
+use std::vec::Vec;
+use subtle::ConstantTimeEq;
+#[cfg(feature = "std")]
+use zeroize::Zeroize;
+use zeroize::ZeroizeOnDrop;
 
 /// Maximum number of associated data items
 pub const MAX_ASSOCIATED_DATA: usize = 126;
@@ -15,42 +44,162 @@ const ZERO_BLOCK: &[u8; BLOCK_SIZE] = &[0u8; BLOCK_SIZE];
 /// A SIV tag
 type Tag = Block;
 
-/// The SIV misuse resistant block cipher mode of operation
-pub struct Siv<C: BlockCipher> {
-    mac: Cmac<C>,
+/// The SIV misuse resistant block cipher mode of operation, generic over the
+/// MAC used by S2V (CMAC for standard SIV, or PMAC for PMAC-SIV)
+///
+/// Cloning copies the already expanded keys, so it's cheaper than calling
+/// `new` again with the same key material. Every clone holds its own copy of
+/// the key schedules until it's dropped.
+///
+/// # Keys
+///
+/// SIV uses two independent AES keys, one for S2V and one for CTR mode, so
+/// its key is twice the size of the underlying AES key: 32 bytes for
+/// `Aes128Siv` and `Aes128PmacSiv`, 48 bytes for `Aes192Siv`, and 64 bytes
+/// for `Aes256Siv` and `Aes256PmacSiv`. Never reuse a single AES key for both halves;
+/// `generate_key` produces a key of the right size, and `derive_key` expands
+/// one from a master secret.
+///
+/// Both key schedules (and the MAC's subkeys) are zeroized when a `Siv` is
+/// dropped.
+///
+/// The CTR mode context is kept for the lifetime of the instance and
+/// borrowed to generate each message's keystream from its synthetic IV, so no
+/// sealing or opening path (including `&self` ones) copies the key schedule.
+#[derive(Clone)]
+pub struct Siv<C: BlockCipher, M: Mac<C>> {
+    mac_key: M::Key,
     ctr: Ctr<C>,
 }
 
+/// Each field wipes itself when dropped, so a `Siv` needs no `Drop` of its own
+impl<C, M> ZeroizeOnDrop for Siv<C, M>
+where
+    C: BlockCipher + ZeroizeOnDrop,
+    M: Mac<C>,
+    M::Key: ZeroizeOnDrop,
+{
+}
+
 /// AES-SIV with a 128-bit key
-pub type Aes128Siv = Siv<Aes128>;
+pub type Aes128Siv = Siv<Aes128, Cmac<Aes128>>;
+
+/// AES-SIV with a 192-bit key
+pub type Aes192Siv = Siv<Aes192, Cmac<Aes192>>;
+
+/// AES-SIV with a 256-bit key
+pub type Aes256Siv = Siv<Aes256, Cmac<Aes256>>;
+
+/// AES-PMAC-SIV with a 128-bit key
+pub type Aes128PmacSiv = Siv<Aes128, Pmac<Aes128>>;
+
+/// AES-PMAC-SIV with a 256-bit key
+pub type Aes256PmacSiv = Siv<Aes256, Pmac<Aes256>>;
 
-impl Aes128Siv {
-    /// Create a new AES-SIV instance with a 32-byte key
+impl<M: Mac<Aes128>> Siv<Aes128, M> {
+    /// Create a new AES-SIV or AES-PMAC-SIV instance with a 32-byte key
     pub fn new(key: &[u8; 32]) -> Self {
         Self {
-            mac: Cmac::new(Aes128::new(array_ref!(key, 0, 16))),
-            ctr: Ctr::new(Aes128::new(array_ref!(key, 16, 16))),
+            mac_key: M::new_key(Aes128::new(array_ref!(key, 0, 16))),
+            ctr: Ctr::new(Aes128::new(array_ref!(key, 16, 16)), &Block::new()),
         }
     }
+
+    /// Generate a random 32-byte key (two 128-bit AES keys) with the given
+    /// cryptographically secure RNG
+    #[cfg(feature = "rand_core")]
+    pub fn generate_key<R: RngCore + CryptoRng>(rng: &mut R) -> [u8; 32] {
+        let mut key = [0u8; 32];
+        rng.fill_bytes(&mut key);
+        key
+    }
+
+    /// Derive a 32-byte key from a master secret with HKDF-SHA256 (RFC 5869,
+    /// without a salt). Use a distinct `info` for each purpose the master
+    /// secret is used for, so the derived keys are independent.
+    #[cfg(feature = "hkdf")]
+    pub fn derive_key(master_secret: &[u8], info: &[u8]) -> [u8; 32] {
+        let mut key = [0u8; 32];
+        hkdf_sha256(master_secret, info, &mut key);
+        key
+    }
 }
 
-/// AES-SIV with a 256-bit key
-pub type Aes256Siv = Siv<Aes256>;
+impl<M: Mac<Aes192>> Siv<Aes192, M> {
+    /// Create a new AES-SIV instance with a 48-byte key
+    pub fn new(key: &[u8; 48]) -> Self {
+        Self {
+            mac_key: M::new_key(Aes192::new(array_ref!(key, 0, 24))),
+            ctr: Ctr::new(Aes192::new(array_ref!(key, 24, 24)), &Block::new()),
+        }
+    }
+
+    /// Generate a random 48-byte key (two 192-bit AES keys) with the given
+    /// cryptographically secure RNG
+    #[cfg(feature = "rand_core")]
+    pub fn generate_key<R: RngCore + CryptoRng>(rng: &mut R) -> [u8; 48] {
+        let mut key = [0u8; 48];
+        rng.fill_bytes(&mut key);
+        key
+    }
 
-impl Aes256Siv {
-    /// Create a new AES-SIV instance with a 32-byte key
+    /// Derive a 48-byte key from a master secret with HKDF-SHA256 (RFC 5869,
+    /// without a salt). Use a distinct `info` for each purpose the master
+    /// secret is used for, so the derived keys are independent.
+    #[cfg(feature = "hkdf")]
+    pub fn derive_key(master_secret: &[u8], info: &[u8]) -> [u8; 48] {
+        let mut key = [0u8; 48];
+        hkdf_sha256(master_secret, info, &mut key);
+        key
+    }
+}
+
+impl<M: Mac<Aes256>> Siv<Aes256, M> {
+    /// Create a new AES-SIV or AES-PMAC-SIV instance with a 64-byte key
     pub fn new(key: &[u8; 64]) -> Self {
         Self {
-            mac: Cmac::new(Aes256::new(array_ref!(key, 0, 32))),
-            ctr: Ctr::new(Aes256::new(array_ref!(key, 32, 32))),
+            mac_key: M::new_key(Aes256::new(array_ref!(key, 0, 32))),
+            ctr: Ctr::new(Aes256::new(array_ref!(key, 32, 32)), &Block::new()),
         }
     }
+
+    /// Generate a random 64-byte key (two 256-bit AES keys) with the given
+    /// cryptographically secure RNG
+    #[cfg(feature = "rand_core")]
+    pub fn generate_key<R: RngCore + CryptoRng>(rng: &mut R) -> [u8; 64] {
+        let mut key = [0u8; 64];
+        rng.fill_bytes(&mut key);
+        key
+    }
+
+    /// Derive a 64-byte key from a master secret with HKDF-SHA256 (RFC 5869,
+    /// without a salt). Use a distinct `info` for each purpose the master
+    /// secret is used for, so the derived keys are independent.
+    #[cfg(feature = "hkdf")]
+    pub fn derive_key(master_secret: &[u8], info: &[u8]) -> [u8; 64] {
+        let mut key = [0u8; 64];
+        hkdf_sha256(master_secret, info, &mut key);
+        key
+    }
 }
 
-impl<C: BlockCipher> Siv<C> {
+/// Fill `out` with HKDF-SHA256 output keying material
+#[cfg(feature = "hkdf")]
+fn hkdf_sha256(master_secret: &[u8], info: &[u8], out: &mut [u8]) {
+    Hkdf::<Sha256>::new(None, master_secret)
+        .expand(info, out)
+        .expect("SIV keys are shorter than HKDF's maximum output");
+}
+
+impl<C: BlockCipher, M: Mac<C>> Siv<C, M> {
     /// Encrypt the given plaintext in-place, replacing it with the SIV tag and
     /// ciphertext. Requires a buffer with 16-bytes additional space.
     ///
+    /// Each associated data item (e.g. a header, with the nonce last) is a
+    /// separate input to S2V, as in RFC 5297: `&[header, nonce]` is not the
+    /// same as their concatenation. See `seal_in_place_single_ad` for the
+    /// common case of a single item.
+    ///
     /// # Usage
     ///
     /// It's important to note that only the beginning of the buffer will be
@@ -78,6 +227,37 @@ impl<C: BlockCipher> Siv<C> {
         I: IntoIterator<Item = T>,
         T: AsRef<[u8]>,
     {
+        let mut s2v = S2v::from_key(&self.mac_key);
+
+        for ad in associated_data {
+            s2v.add_vector(ad.as_ref());
+        }
+
+        Self::seal_with_s2v(&self.ctr, s2v, plaintext);
+    }
+
+    /// Encrypt the given plaintext in-place with a single associated data item
+    /// (e.g. just a nonce), exactly like `seal_in_place(&[ad], plaintext)`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `plaintext.len()` is less than `BLOCK_SIZE`.
+    pub fn seal_in_place_single_ad(&mut self, ad: &[u8], plaintext: &mut [u8]) {
+        self.seal_in_place(&[ad], plaintext)
+    }
+
+    /// Begin sealing a message whose associated data items will be supplied
+    /// one at a time (e.g. as headers arrive), instead of all at once
+    pub fn sealer<'a>(&'a mut self) -> SivSealer<'a, C, M> {
+        SivSealer {
+            s2v: S2v::from_key(&self.mac_key),
+            siv: self,
+        }
+    }
+
+    /// Finish computing S2V over the plaintext, which has already absorbed
+    /// the associated data, then encrypt as described in `seal_in_place`
+    fn seal_with_s2v(ctr: &Ctr<C>, s2v: S2v<C, M>, plaintext: &mut [u8]) {
         if plaintext.len() < BLOCK_SIZE {
             panic!("plaintext buffer too small to hold SIV tag!");
         }
@@ -92,48 +272,155 @@ impl<C: BlockCipher> Siv<C> {
             );
         }
 
-        // Compute the synthetic IV for this plaintext
-        let mut iv = self.s2v(associated_data, &plaintext[BLOCK_SIZE..]);
-        plaintext[..BLOCK_SIZE].copy_from_slice(iv.as_ref());
+        let tag = Self::seal_detached_with_s2v(ctr, s2v, &mut plaintext[BLOCK_SIZE..]);
+        plaintext[..BLOCK_SIZE].copy_from_slice(tag.as_ref());
+    }
 
-        zero_iv_bits(&mut iv);
-        self.ctr.transform(&mut iv, &mut plaintext[BLOCK_SIZE..]);
-        self.ctr.reset();
+    /// Encrypt the given plaintext in-place, returning the SIV tag separately
+    /// instead of prepending it to the ciphertext
+    ///
+    /// The combined form produced by `seal_in_place` is exactly this tag
+    /// followed by this ciphertext.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `associated_data.len()` is greater than `MAX_ASSOCIATED_DATA`.
+    #[cfg(feature = "std")]
+    pub fn seal_detached<I, T>(
+        &mut self,
+        associated_data: I,
+        plaintext: &[u8],
+    ) -> ([u8; BLOCK_SIZE], Vec<u8>)
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let mut ciphertext = plaintext.to_vec();
+        let tag = self.seal_in_place_detached(associated_data, &mut ciphertext);
+
+        (tag, ciphertext)
     }
 
-    /// Decrypt the given ciphertext in-place, authenticating it against the
-    /// synthetic IV included in the message.
-    pub fn open_in_place<'a, I, T>(
+    /// Encrypt the given plaintext in-place without any additional space,
+    /// returning the SIV tag separately
+    ///
+    /// # Panics
+    ///
+    /// Panics if `associated_data.len()` is greater than `MAX_ASSOCIATED_DATA`.
+    pub fn seal_in_place_detached<I, T>(
         &mut self,
         associated_data: I,
-        ciphertext: &'a mut [u8],
-    ) -> Result<&'a [u8], ()>
+        plaintext: &mut [u8],
+    ) -> [u8; BLOCK_SIZE]
     where
         I: IntoIterator<Item = T>,
         T: AsRef<[u8]>,
     {
-        if ciphertext.len() < BLOCK_SIZE {
-            return Err(());
+        let mut s2v = S2v::from_key(&self.mac_key);
+
+        for ad in associated_data {
+            s2v.add_vector(ad.as_ref());
         }
 
-        let mut iv = Block::from(&ciphertext[..BLOCK_SIZE]);
-        zero_iv_bits(&mut iv);
+        let tag = Self::seal_detached_with_s2v(&self.ctr, s2v, plaintext);
+        *array_ref!(tag.as_ref(), 0, BLOCK_SIZE)
+    }
 
-        self.ctr.transform(&mut iv, &mut ciphertext[BLOCK_SIZE..]);
-        self.ctr.reset();
+    /// Encrypt the given plaintext into `out` as the SIV tag followed by the
+    /// ciphertext (the same format as `seal_in_place`), leaving the plaintext
+    /// untouched and without allocating
+    ///
+    /// Returns the number of bytes written (`plaintext.len() + BLOCK_SIZE`),
+    /// or `Error::BufferSize` if `out` is too small to hold them. Any bytes
+    /// in `out` past that length are left as they were.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `associated_data.len()` is greater than `MAX_ASSOCIATED_DATA`.
+    pub fn seal_to_slice<I, T>(
+        &mut self,
+        associated_data: I,
+        plaintext: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize, Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let len = plaintext.len().checked_add(BLOCK_SIZE).expect("overflow");
 
-        let actual_tag = self.s2v(associated_data, &ciphertext[BLOCK_SIZE..]);
+        if out.len() < len {
+            return Err(Error::BufferSize);
+        }
 
-        if actual_tag.ct_eq(&Block::from(&ciphertext[..BLOCK_SIZE])) != 1 {
-            let mut iv = Block::from(&ciphertext[..BLOCK_SIZE]);
+        let (tag, ciphertext) = out[..len].split_at_mut(BLOCK_SIZE);
+        ciphertext.copy_from_slice(plaintext);
+        tag.copy_from_slice(&self.seal_in_place_detached(associated_data, ciphertext));
 
-            // Re-encrypt the decrypted plaintext to avoid revealing it
-            self.ctr.transform(&mut iv, &mut ciphertext[BLOCK_SIZE..]);
-            self.ctr.reset();
+        Ok(len)
+    }
 
-            return Err(());
+    /// Compute the synthetic IV for the given plaintext, then encrypt it
+    /// in-place, returning the IV as the SIV tag
+    fn seal_detached_with_s2v(ctr: &Ctr<C>, s2v: S2v<C, M>, plaintext: &mut [u8]) -> Tag {
+        let tag = s2v.finish(plaintext);
+
+        let mut iv = tag.clone();
+        zero_iv_bits(&mut iv);
+        Self::transform(ctr, &iv, plaintext);
+
+        tag
+    }
+
+    /// Decrypt the given ciphertext in-place, authenticating it against the
+    /// synthetic IV included in the message.
+    ///
+    /// The associated data items must be the same ones, in the same order, as
+    /// were passed to `seal_in_place`.
+    ///
+    /// Returns `Error::Authentication` if the message is too short to contain a
+    /// tag or fails authentication.
+    pub fn open_in_place<'a, I, T>(
+        &mut self,
+        associated_data: I,
+        ciphertext: &'a mut [u8],
+    ) -> Result<&'a [u8], Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        Self::open(&self.mac_key, &self.ctr, associated_data, ciphertext)
+    }
+
+    /// Decrypt the given ciphertext in-place with a single associated data
+    /// item, exactly like `open_in_place(&[ad], ciphertext)`
+    pub fn open_in_place_single_ad<'a>(
+        &mut self,
+        ad: &[u8],
+        ciphertext: &'a mut [u8],
+    ) -> Result<&'a [u8], Error> {
+        self.open_in_place(&[ad], ciphertext)
+    }
+
+    /// Decrypt and authenticate as described in `open_in_place`
+    fn open<'a, I, T>(
+        mac_key: &M::Key,
+        ctr: &Ctr<C>,
+        associated_data: I,
+        ciphertext: &'a mut [u8],
+    ) -> Result<&'a [u8], Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        if ciphertext.len() < BLOCK_SIZE {
+            return Err(Error::Authentication);
         }
 
+        let tag = *array_ref!(ciphertext, 0, BLOCK_SIZE);
+        let message = &mut ciphertext[BLOCK_SIZE..];
+        Self::open_detached_in_place(mac_key, ctr, &tag, associated_data, message)?;
+
         let len = ciphertext.len().checked_sub(BLOCK_SIZE).unwrap();
 
         unsafe {
@@ -147,50 +434,783 @@ impl<C: BlockCipher> Siv<C> {
         Ok(&ciphertext[..len])
     }
 
-    /// The S2V operation consists of the doubling and XORing of the outputs
-    /// of the pseudo-random function CMAC.
+    /// Decrypt a ciphertext whose SIV tag is stored separately (e.g. produced
+    /// by `seal_detached`), authenticating it against the tag
     ///
-    /// See Section 2.4 of RFC 5297 for more information
-    fn s2v<I, T>(&mut self, associated_data: I, plaintext: &[u8]) -> Tag
+    /// Returns `Error::Authentication` if the ciphertext fails authentication.
+    #[cfg(feature = "std")]
+    pub fn open_detached<I, T>(
+        &mut self,
+        tag: &[u8; BLOCK_SIZE],
+        associated_data: I,
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, Error>
     where
         I: IntoIterator<Item = T>,
         T: AsRef<[u8]>,
     {
-        self.mac.reset();
-        self.mac.update(ZERO_BLOCK);
-        let mut state = self.mac.finish();
+        let mut plaintext = ciphertext.to_vec();
+        Self::open_detached_in_place(
+            &self.mac_key,
+            &self.ctr,
+            tag,
+            associated_data,
+            &mut plaintext,
+        )?;
+        Ok(plaintext)
+    }
 
-        for (i, ad) in associated_data.into_iter().enumerate() {
-            if i >= MAX_ASSOCIATED_DATA {
-                panic!("too many associated data items!");
+    /// Decrypt the given ciphertext in-place and authenticate it against the
+    /// given SIV tag. On failure the ciphertext is re-encrypted, so no
+    /// unauthenticated plaintext is left behind.
+    fn open_detached_in_place<I, T>(
+        mac_key: &M::Key,
+        ctr: &Ctr<C>,
+        tag: &[u8; BLOCK_SIZE],
+        associated_data: I,
+        ciphertext: &mut [u8],
+    ) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let expected_tag = Block::from(*tag);
+
+        let mut iv = expected_tag.clone();
+        zero_iv_bits(&mut iv);
+
+        Self::transform(ctr, &iv, ciphertext);
+
+        let actual_tag = Self::s2v(mac_key, associated_data, ciphertext);
+
+        if !bool::from(actual_tag.ct_eq(&expected_tag)) {
+            // Re-encrypt the decrypted plaintext to avoid revealing it
+            Self::transform(ctr, &iv, ciphertext);
+
+            return Err(Error::Authentication);
+        }
+
+        Ok(())
+    }
+
+    /// Decrypt a message (SIV tag followed by ciphertext) in-place, leaving
+    /// just the authenticated plaintext in the buffer
+    ///
+    /// Returns `Error::Authentication` if the message is too short to contain a
+    /// tag or fails authentication, in which case the buffer is zeroed so no
+    /// unauthenticated plaintext is left behind.
+    #[cfg(feature = "std")]
+    pub fn open_in_place_vec<I, T>(
+        &mut self,
+        associated_data: I,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        Self::open_vec(&self.mac_key, &self.ctr, associated_data, buffer)
+    }
+
+    /// Decrypt a message in a `Vec` as described in `open_in_place_vec`
+    #[cfg(feature = "std")]
+    fn open_vec<I, T>(
+        mac_key: &M::Key,
+        ctr: &Ctr<C>,
+        associated_data: I,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let len = match Self::open(mac_key, ctr, associated_data, buffer) {
+            Ok(plaintext) => plaintext.len(),
+            Err(e) => {
+                buffer.as_mut_slice().zeroize();
+                return Err(e);
             }
+        };
+
+        buffer.truncate(len);
+        Ok(())
+    }
+
+    /// Encrypt the given plaintext with a nonce, returning the SIV tag
+    /// followed by the ciphertext
+    ///
+    /// The associated data can be any sequence of items (an array, a `Vec`,
+    /// or a lazily generated iterator), each a separate input to S2V in
+    /// order, with the nonce last: `seal_with(nonce, &[header], plaintext)`
+    /// is the same as `seal_to_slice(&[header, nonce], ...)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than `MAX_ASSOCIATED_DATA - 1` associated
+    /// data items.
+    #[cfg(feature = "std")]
+    pub fn seal_with<I, T>(
+        &mut self,
+        nonce: &[u8],
+        associated_data: I,
+        plaintext: &[u8],
+    ) -> Vec<u8>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let associated_data = associated_data
+            .into_iter()
+            .map(NonceOrAd::Ad)
+            .chain(iter::once(NonceOrAd::Nonce(nonce)));
+
+        self.seal_vec(associated_data, plaintext)
+    }
+
+    /// Decrypt a message produced by `seal_with` with the same nonce and
+    /// associated data items
+    ///
+    /// Returns `Error::Authentication` if the message is too short to contain a
+    /// tag or fails authentication.
+    #[cfg(feature = "std")]
+    pub fn open_with<I, T>(
+        &mut self,
+        nonce: &[u8],
+        associated_data: I,
+        message: &[u8],
+    ) -> Result<Vec<u8>, Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let associated_data = associated_data
+            .into_iter()
+            .map(NonceOrAd::Ad)
+            .chain(iter::once(NonceOrAd::Nonce(nonce)));
 
-            self.mac.reset();
-            self.mac.update(ad.as_ref());
+        let mut buffer = message.to_vec();
+        self.open_in_place_vec(associated_data, &mut buffer)?;
+        Ok(buffer)
+    }
 
-            state.dbl();
-            state.xor_in_place(&self.mac.finish());
+    /// Encrypt the given plaintext, returning the SIV tag followed by the
+    /// ciphertext (as produced by `seal_to_slice`) encoded as standard base64
+    ///
+    /// # Panics
+    ///
+    /// Panics if `associated_data.len()` is greater than `MAX_ASSOCIATED_DATA`.
+    #[cfg(feature = "encoding")]
+    pub fn seal_to_base64<I, T>(&mut self, associated_data: I, plaintext: &[u8]) -> String
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        base64::encode(&self.seal_vec(associated_data, plaintext))
+    }
+
+    /// Decrypt a message produced by `seal_to_base64`
+    ///
+    /// Returns `Error::Base64` if the message isn't valid base64, or
+    /// `Error::Authentication` if it fails authentication.
+    #[cfg(feature = "encoding")]
+    pub fn open_from_base64<I, T>(
+        &mut self,
+        associated_data: I,
+        message: &str,
+    ) -> Result<Vec<u8>, Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let mut buffer = base64::decode(message).map_err(|_| Error::Base64)?;
+        self.open_in_place_vec(associated_data, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Encrypt the given plaintext, returning the SIV tag followed by the
+    /// ciphertext (as produced by `seal_to_slice`) as lowercase hex
+    ///
+    /// # Panics
+    ///
+    /// Panics if `associated_data.len()` is greater than `MAX_ASSOCIATED_DATA`.
+    #[cfg(feature = "encoding")]
+    pub fn seal_to_hex<I, T>(&mut self, associated_data: I, plaintext: &[u8]) -> String
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let message = self.seal_vec(associated_data, plaintext);
+        let mut encoded = vec![0u8; message.len().checked_mul(2).expect("overflow")];
+        hex::encode(&message, &mut encoded);
+
+        String::from_utf8(encoded).expect("hex is ASCII")
+    }
+
+    /// Decrypt a message produced by `seal_to_hex` (in either case)
+    ///
+    /// Returns `Error::HexLength` or `Error::HexCharacter` if the message
+    /// isn't valid hex, or `Error::Authentication` if it fails authentication.
+    #[cfg(feature = "encoding")]
+    pub fn open_from_hex<I, T>(
+        &mut self,
+        associated_data: I,
+        message: &str,
+    ) -> Result<Vec<u8>, Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        if message.len() % 2 != 0 {
+            return Err(Error::HexLength);
         }
 
+        let mut buffer = vec![0u8; message.len() / 2];
+        hex::decode(message.as_bytes(), &mut buffer)?;
+        self.open_in_place_vec(associated_data, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Encrypt into a new `Vec` as described in `seal_to_slice`
+    #[cfg(feature = "std")]
+    fn seal_vec<I, T>(&mut self, associated_data: I, plaintext: &[u8]) -> Vec<u8>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let mut message = vec![0u8; plaintext.len().checked_add(BLOCK_SIZE).expect("overflow")];
+        self.seal_to_slice(associated_data, plaintext, &mut message)
+            .expect("buffer sized for tag");
+        message
+    }
+
+    /// Authenticate the given associated data items without any message,
+    /// returning just the 16-byte tag
+    ///
+    /// This is the SIV tag `seal_in_place` produces for an empty plaintext,
+    /// which makes it a deterministic MAC over the items.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `associated_data.len()` is greater than `MAX_ASSOCIATED_DATA`.
+    pub fn authenticate<I, T>(&self, associated_data: I) -> [u8; BLOCK_SIZE]
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let tag = Self::s2v(&self.mac_key, associated_data, &[]);
+        *array_ref!(tag.as_ref(), 0, BLOCK_SIZE)
+    }
+
+    /// Check a tag produced by `authenticate` in constant time
+    ///
+    /// Returns `Error::Authentication` if it doesn't match the associated data
+    pub fn verify<I, T>(&self, tag: &[u8; BLOCK_SIZE], associated_data: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let expected = Self::s2v(&self.mac_key, associated_data, &[]);
+
+        if bool::from(expected.ct_eq(&Block::from(*tag))) {
+            Ok(())
+        } else {
+            Err(Error::Authentication)
+        }
+    }
+
+    /// Encrypt/decrypt the given data in-place using CTR mode (with a 128-bit
+    /// big endian counter), starting from the given (already bit-zeroed)
+    /// synthetic IV, borrowing the given CTR context (and its key schedule)
+    fn transform(ctr: &Ctr<C>, iv: &Block, data: &mut [u8]) {
+        // Clearing bit 63 of the IV leaves room for 2^63 blocks before the
+        // counter could wrap, which is more than any slice can hold
+        ctr.apply_keystream_from_iv(iv, data).expect("SIV counter overflowed");
+    }
+
+    /// Compute S2V over the given associated data and plaintext
+    fn s2v<I, T>(mac_key: &M::Key, associated_data: I, plaintext: &[u8]) -> Tag
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let mut s2v: S2v<C, M> = S2v::from_key(mac_key);
+
+        for ad in associated_data {
+            s2v.add_vector(ad.as_ref());
+        }
+
+        s2v.finish(plaintext)
+    }
+}
+
+/// An associated data item from the caller, or the nonce appended after them
+#[cfg(feature = "std")]
+enum NonceOrAd<'a, T> {
+    Ad(T),
+    Nonce(&'a [u8]),
+}
+
+#[cfg(feature = "std")]
+impl<'a, T: AsRef<[u8]>> AsRef<[u8]> for NonceOrAd<'a, T> {
+    fn as_ref(&self) -> &[u8] {
+        match *self {
+            NonceOrAd::Ad(ref ad) => ad.as_ref(),
+            NonceOrAd::Nonce(nonce) => nonce,
+        }
+    }
+}
+
+/// Seals a message whose associated data is supplied incrementally, one item
+/// at a time. Created by `Siv::sealer`.
+///
+/// Produces exactly the same output as `Siv::seal_in_place` given the same
+/// associated data items, including the final one.
+pub struct SivSealer<'a, C: BlockCipher + 'a, M: Mac<C> + 'a> {
+    siv: &'a mut Siv<C, M>,
+    s2v: S2v<C, M>,
+}
+
+impl<'a, C: BlockCipher, M: Mac<C>> SivSealer<'a, C, M> {
+    /// Add the next associated data item
+    ///
+    /// Panics if more than `MAX_ASSOCIATED_DATA` items are added in total
+    /// (including the final one passed to `seal_in_place`)
+    pub fn add_associated_data(&mut self, ad: &[u8]) {
+        self.s2v.add_vector(ad);
+    }
+
+    /// Add the final associated data item (e.g. the nonce), then encrypt the
+    /// given plaintext in-place exactly like `Siv::seal_in_place`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `plaintext.len()` is less than `BLOCK_SIZE`, or if this
+    /// makes more than `MAX_ASSOCIATED_DATA` associated data items.
+    pub fn seal_in_place(mut self, final_ad: &[u8], plaintext: &mut [u8]) {
+        self.s2v.add_vector(final_ad);
+        Siv::<C, M>::seal_with_s2v(&self.siv.ctr, self.s2v, plaintext);
+    }
+}
+
+/// The nonce is 16 bytes, and is the last S2V input after the associated data,
+/// so `encrypt(nonce, Payload { msg, aad })` is `seal_in_place(&[aad, nonce],
+/// ...)`. Tags are 16 bytes.
+#[cfg(feature = "aead")]
+impl<C: BlockCipher, M: Mac<C>> AeadCore for Siv<C, M> {
+    type NonceSize = U16;
+    type TagSize = U16;
+    type CiphertextOverhead = U0;
+}
+
+#[cfg(feature = "aead")]
+impl<M: Mac<Aes128>> NewAead for Siv<Aes128, M> {
+    type KeySize = U32;
+
+    #[inline]
+    fn new(key: &Key<Self>) -> Self {
+        Self::new(array_ref!(key, 0, 32))
+    }
+}
+
+#[cfg(feature = "aead")]
+impl<M: Mac<Aes192>> NewAead for Siv<Aes192, M> {
+    type KeySize = U48;
+
+    #[inline]
+    fn new(key: &Key<Self>) -> Self {
+        Self::new(array_ref!(key, 0, 48))
+    }
+}
+
+#[cfg(feature = "aead")]
+impl<M: Mac<Aes256>> NewAead for Siv<Aes256, M> {
+    type KeySize = U64;
+
+    #[inline]
+    fn new(key: &Key<Self>) -> Self {
+        Self::new(array_ref!(key, 0, 64))
+    }
+}
+
+/// Unlike e.g. AES-GCM, whose tag follows the ciphertext, SIV *prepends* its
+/// tag: the combined methods (and so `Aead::encrypt`/`Aead::decrypt`) use
+/// the same tag-then-ciphertext layout as `seal_in_place`. Code which splits
+/// off the last 16 bytes as the tag must use the detached methods instead.
+#[cfg(feature = "aead")]
+impl<C: BlockCipher, M: Mac<C>> AeadInPlace for Siv<C, M> {
+    /// Encrypt the buffer in-place, prepending the SIV tag
+    fn encrypt_in_place(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut dyn aead::Buffer,
+    ) -> Result<(), aead::Error> {
+        buffer.extend_from_slice(ZERO_BLOCK)?;
+
+        let s2v = self.aead_s2v(nonce, associated_data);
+        Self::seal_with_s2v(&self.ctr, s2v, buffer.as_mut());
+
+        Ok(())
+    }
+
+    fn encrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<aead::Tag<Self>, aead::Error> {
+        let s2v = self.aead_s2v(nonce, associated_data);
+        let tag = Self::seal_detached_with_s2v(&self.ctr, s2v, buffer);
+
+        Ok(GenericArray::clone_from_slice(tag.as_ref()))
+    }
+
+    /// Decrypt a buffer containing the SIV tag followed by the ciphertext
+    /// in-place, leaving just the plaintext
+    fn decrypt_in_place(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut dyn aead::Buffer,
+    ) -> Result<(), aead::Error> {
+        let ad = [associated_data, nonce.as_slice()];
+        let len = Self::open(&self.mac_key, &self.ctr, &ad, buffer.as_mut())
+            .map_err(|_| aead::Error)?
+            .len();
+
+        buffer.truncate(len);
+        Ok(())
+    }
+
+    fn decrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+        tag: &aead::Tag<Self>,
+    ) -> Result<(), aead::Error> {
+        let ad = [associated_data, nonce.as_slice()];
+
+        let tag = array_ref!(tag, 0, BLOCK_SIZE);
+
+        Self::open_detached_in_place(&self.mac_key, &self.ctr, tag, &ad, buffer)
+            .map_err(|_| aead::Error)
+    }
+}
+
+#[cfg(feature = "aead")]
+impl<C: BlockCipher, M: Mac<C>> Siv<C, M> {
+    /// Begin S2V with the `AeadInPlace` associated data followed by the nonce
+    fn aead_s2v(&self, nonce: &[u8], associated_data: &[u8]) -> S2v<C, M> {
+        let mut s2v = S2v::from_key(&self.mac_key);
+        s2v.add_vector(associated_data);
+        s2v.add_vector(nonce);
+        s2v
+    }
+}
+
+/// Wraps a SIV instance, refusing to seal with a nonce it has recently used
+///
+/// SIV stays secure if a nonce repeats, but sealing the same message under
+/// the same nonce and associated data produces the same ciphertext, revealing
+/// that the plaintexts are equal. This remembers the last `capacity` nonces
+/// used to seal and returns `Error::NonceReuse` instead of reusing one.
+///
+/// This is best-effort: older nonces are forgotten, and nothing is shared
+/// between instances or persisted, so it's no substitute for generating
+/// unique nonces. Deterministic use without a nonce should use `Siv` directly.
+#[cfg(feature = "std")]
+pub struct NonceTracker<C: BlockCipher, M: Mac<C>> {
+    siv: Siv<C, M>,
+    capacity: usize,
+    recent: VecDeque<Vec<u8>>,
+    seen: HashSet<Vec<u8>>,
+}
+
+#[cfg(feature = "std")]
+impl<C: BlockCipher, M: Mac<C>> NonceTracker<C, M> {
+    /// Track up to `capacity` of the most recently used nonces
+    ///
+    /// Panics if `capacity` is zero
+    pub fn new(siv: Siv<C, M>, capacity: usize) -> Self {
+        if capacity == 0 {
+            panic!("nonce tracker capacity must be nonzero");
+        }
+
+        Self {
+            siv: siv,
+            capacity: capacity,
+            recent: VecDeque::with_capacity(capacity),
+            seen: HashSet::with_capacity(capacity),
+        }
+    }
+
+    /// Encrypt the given plaintext in-place like `Siv::seal_in_place`, with
+    /// the nonce as the last associated data item
+    ///
+    /// Returns `Error::NonceReuse` (leaving the plaintext untouched) if the
+    /// nonce is one of the recently used ones.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `plaintext.len()` is less than `BLOCK_SIZE`.
+    /// Panics if there are more than `MAX_ASSOCIATED_DATA` associated data
+    /// items (including the nonce).
+    pub fn seal_in_place(
+        &mut self,
+        nonce: &[u8],
+        associated_data: &[&[u8]],
+        plaintext: &mut [u8],
+    ) -> Result<(), Error> {
+        if self.seen.contains(nonce) {
+            return Err(Error::NonceReuse);
+        }
+
+        let mut s2v = S2v::from_key(&self.siv.mac_key);
+
+        for ad in associated_data {
+            s2v.add_vector(ad);
+        }
+
+        s2v.add_vector(nonce);
+        Siv::<C, M>::seal_with_s2v(&self.siv.ctr, s2v, plaintext);
+
+        self.remember(nonce);
+        Ok(())
+    }
+
+    /// Decrypt the given ciphertext in-place like `Siv::open_in_place`, with
+    /// the nonce as the last associated data item. Opening doesn't record
+    /// the nonce.
+    pub fn open_in_place<'a>(
+        &mut self,
+        nonce: &[u8],
+        associated_data: &[&[u8]],
+        ciphertext: &'a mut [u8],
+    ) -> Result<&'a [u8], Error> {
+        let associated_data = associated_data.iter().chain(Some(&nonce));
+        Siv::<C, M>::open(&self.siv.mac_key, &self.siv.ctr, associated_data, ciphertext)
+    }
+
+    /// Record a nonce, forgetting the oldest one if we're at capacity
+    fn remember(&mut self, nonce: &[u8]) {
+        if self.recent.len() == self.capacity {
+            if let Some(oldest) = self.recent.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+
+        self.recent.push_back(nonce.to_vec());
+        self.seen.insert(nonce.to_vec());
+    }
+}
+
+/// Deterministic key wrapping with SIV, as described in RFC 5297 Section 1.3.2
+///
+/// Unlike `Siv::seal_in_place` used with a nonce, no nonce is involved here:
+/// S2V is computed over the associated data followed by the key being
+/// wrapped, so wrapping the same key with the same associated data always
+/// produces the same output. This reveals nothing but equality, which is
+/// harmless for the unique, random keys this is intended for. Use the nonce
+/// based mode to encrypt anything else.
+///
+/// The wrapped key is the 16-byte synthetic IV followed by the encrypted key.
+#[cfg(feature = "std")]
+#[derive(Clone)]
+pub struct KeyWrap<C: BlockCipher, M: Mac<C>> {
+    siv: Siv<C, M>,
+}
+
+/// Deterministic key wrapping with AES-SIV and a 256-bit key
+#[cfg(feature = "std")]
+pub type Aes128KeyWrap = KeyWrap<Aes128, Cmac<Aes128>>;
+
+/// Deterministic key wrapping with AES-SIV and a 512-bit key
+#[cfg(feature = "std")]
+pub type Aes256KeyWrap = KeyWrap<Aes256, Cmac<Aes256>>;
+
+#[cfg(feature = "std")]
+impl<M: Mac<Aes128>> KeyWrap<Aes128, M> {
+    /// Create a new key wrapping instance with a 32-byte key-encryption key
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self { siv: Siv::<Aes128, M>::new(key) }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<M: Mac<Aes256>> KeyWrap<Aes256, M> {
+    /// Create a new key wrapping instance with a 64-byte key-encryption key
+    pub fn new(key: &[u8; 64]) -> Self {
+        Self { siv: Siv::<Aes256, M>::new(key) }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<C: BlockCipher, M: Mac<C>> KeyWrap<C, M> {
+    /// Wrap the given key, authenticating (but not encrypting) the given
+    /// associated data items along with it
+    ///
+    /// # Panics
+    ///
+    /// Panics if `associated_data.len()` is greater than `MAX_ASSOCIATED_DATA`.
+    pub fn wrap(&self, key_to_wrap: &[u8], associated_data: &[&[u8]]) -> Vec<u8> {
+        let mut s2v: S2v<C, M> = S2v::from_key(&self.siv.mac_key);
+
+        for ad in associated_data {
+            s2v.add_vector(ad);
+        }
+
+        let mut buffer = Vec::with_capacity(key_to_wrap.len() + BLOCK_SIZE);
+        buffer.extend_from_slice(key_to_wrap);
+        buffer.extend_from_slice(ZERO_BLOCK);
+
+        Siv::<C, M>::seal_with_s2v(&self.siv.ctr, s2v, &mut buffer);
+        buffer
+    }
+
+    /// Unwrap a key wrapped by `wrap` with the same associated data items
+    ///
+    /// Returns `Error::Authentication` if the wrapped key is too short or fails
+    /// authentication. The returned key isn't wiped when dropped, so callers
+    /// should zeroize it once they're done with it.
+    pub fn unwrap(&self, wrapped_key: &[u8], associated_data: &[&[u8]]) -> Result<Vec<u8>, Error> {
+        let mut buffer = wrapped_key.to_vec();
+        Siv::<C, M>::open_vec(&self.siv.mac_key, &self.siv.ctr, associated_data, &mut buffer)?;
+        Ok(buffer)
+    }
+}
+
+/// The S2V operation consists of the doubling and XORing of the outputs
+/// of a pseudo-random function: CMAC, or PMAC for PMAC-SIV. Any number of
+/// vectors (e.g. headers) are absorbed one at a time, followed by the message.
+///
+/// Besides deriving SIV's synthetic IV, this is useful on its own as a
+/// deterministic authenticator over multiple strings.
+///
+/// See Section 2.4 of RFC 5297 for more information
+pub struct S2v<C: BlockCipher, M: Mac<C>> {
+    mac: M,
+    state: Block,
+    ad_count: usize,
+    cipher: PhantomData<C>,
+}
+
+impl<C: BlockCipher, M: Mac<C>> S2v<C, M> {
+    /// Create a new S2V instance keyed with the given cipher
+    pub fn new(cipher: C) -> Self {
+        Self::from_key(&M::new_key(cipher))
+    }
+
+    /// Begin S2V with the MAC of the zero block
+    fn from_key(mac_key: &M::Key) -> Self {
+        let mut mac = M::from_key(mac_key);
+        mac.update(ZERO_BLOCK);
+        let state = mac.finish_reset();
+
+        Self {
+            mac: mac,
+            state: state,
+            ad_count: 0,
+            cipher: PhantomData,
+        }
+    }
+
+    /// Double the state and XOR in the MAC of the next vector
+    ///
+    /// Panics if more than `MAX_ASSOCIATED_DATA` vectors are added
+    pub fn add_vector(&mut self, vector: &[u8]) {
+        if self.ad_count >= MAX_ASSOCIATED_DATA {
+            panic!("too many associated data items!");
+        }
+
+        self.ad_count = self.ad_count.checked_add(1).expect("overflow");
+
+        self.mac.update(vector);
+
+        self.state.dbl();
+        self.state.xor_block(&self.mac.finish_reset());
+    }
+
+    /// Absorb the final message, returning the S2V output, then reset so the
+    /// instance can be reused
+    pub fn finish_with_message(&mut self, message: &[u8]) -> Block {
+        let output = self.absorb_message(message);
+        self.reset();
+        output
+    }
+
+    /// Finish S2V over no input strings at all, which RFC 5297 defines as the
+    /// MAC of the block `0^127 || 1`
+    ///
+    /// Panics if any vectors have been added
+    pub fn finish_empty(&mut self) -> Block {
+        if self.ad_count != 0 {
+            panic!("vectors have already been added");
+        }
+
+        let mut one = [0u8; BLOCK_SIZE];
+        one[BLOCK_SIZE - 1] = 1;
+
+        self.mac.update(&one);
+        self.mac.finish_reset()
+    }
+
+    /// Discard any vectors added so far, returning to the initial state
+    pub fn reset(&mut self) {
         self.mac.reset();
+        self.mac.update(ZERO_BLOCK);
+        self.state = self.mac.finish_reset();
+        self.ad_count = 0;
+    }
+
+    /// Absorb the plaintext, returning the synthetic IV
+    fn finish(mut self, plaintext: &[u8]) -> Tag {
+        self.absorb_message(plaintext)
+    }
 
-        if plaintext.len() >= BLOCK_SIZE {
-            let n = plaintext.len().checked_sub(BLOCK_SIZE).unwrap();
-            self.mac.update(&plaintext[..n]);
-            state.xor_in_place(array_ref!(plaintext, n, BLOCK_SIZE));
+    /// XOR the message into the state (or double and XOR in the padded
+    /// message if it's shorter than a block), then MAC the result
+    fn absorb_message(&mut self, message: &[u8]) -> Block {
+        if message.len() >= BLOCK_SIZE {
+            let n = message.len().checked_sub(BLOCK_SIZE).unwrap();
+            self.mac.update(&message[..n]);
+            self.state.xor_array(array_ref!(message, n, BLOCK_SIZE));
         } else {
-            let mut tmp = Block::from(plaintext);
-            tmp.as_mut()[plaintext.len()] = 0x80;
+            let tmp = Block::from_slice_with_bit_padding(message).unwrap();
 
-            state.dbl();
-            state.xor_in_place(&tmp);
+            self.state.dbl();
+            self.state.xor_block(&tmp);
         };
 
-        self.mac.update(state.as_ref());
-        let result = self.mac.finish();
-        self.mac.reset();
+        self.mac.update(self.state.as_ref());
+        self.mac.finish_reset()
+    }
+}
+
+/// Compute S2V with CMAC over the given strings, returning a 128-bit value
+///
+/// S2V is a deterministic PRF over a vector of strings, and the MAC at the
+/// core of SIV: the synthetic IV is S2V over the associated data items
+/// followed by the plaintext. The last string is absorbed as the final
+/// message, and S2V over no strings at all is the CMAC of `0^127 || 1`
+/// (RFC 5297 Section 2.4). Use `S2v` directly to absorb strings one at a
+/// time, or with PMAC.
+///
+/// # Panics
+///
+/// Panics if there are more than `MAX_ASSOCIATED_DATA + 1` strings.
+pub fn s2v<C: BlockCipher>(cipher: C, inputs: &[&[u8]]) -> Block {
+    let mut s2v: S2v<C, Cmac<C>> = S2v::new(cipher);
 
-        result
+    match inputs.split_last() {
+        Some((message, vectors)) => {
+            for vector in vectors {
+                s2v.add_vector(vector);
+            }
+
+            s2v.finish_with_message(message)
+        }
+        None => s2v.finish_empty(),
     }
 }
 
@@ -199,6 +1219,125 @@ fn zero_iv_bits(block: &mut Block) {
     // "We zero-out the top bit in each of the last two 32-bit words
     // of the IV before assigning it to Ctr"
     //  — http://web.cs.ucdavis.edu/~rogaway/papers/siv.pdf
-    block.as_mut()[8] &= 0x7f;
-    block.as_mut()[12] &= 0x7f;
+    let bytes: &mut [u8] = block.as_mut();
+    bytes[8] &= 0x7f;
+    bytes[12] &= 0x7f;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{s2v, Aes128PmacSiv, Aes128Siv, Aes192Siv, Aes256PmacSiv, Aes256Siv, S2v};
+    use core::{mem, ptr};
+    use gcm_siv::{Aes128GcmSiv, Aes256GcmSiv};
+    use internals::{cmac, Aes128, Block, Cmac, BLOCK_SIZE};
+    use zeroize::ZeroizeOnDrop;
+
+    /// RFC 5297 Appendix A.1 S2V key (the first half of the SIV key)
+    const RFC5297_S2V_KEY: [u8; 16] = [
+        0xff, 0xfe, 0xfd, 0xfc, 0xfb, 0xfa, 0xf9, 0xf8,
+        0xf7, 0xf6, 0xf5, 0xf4, 0xf3, 0xf2, 0xf1, 0xf0,
+    ];
+
+    /// RFC 5297 Appendix A.1 associated data
+    const RFC5297_AD: [u8; 24] = [
+        0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17,
+        0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f,
+        0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27,
+    ];
+
+    /// RFC 5297 Appendix A.1 plaintext
+    const RFC5297_PLAINTEXT: [u8; 14] = [
+        0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88,
+        0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee,
+    ];
+
+    fn is_zero(blocks: &[Block]) -> bool {
+        blocks.iter().all(|block| block.as_ref() == &[0u8; BLOCK_SIZE])
+    }
+
+    fn assert_zeroize_on_drop<T: ZeroizeOnDrop>() {}
+
+    #[test]
+    fn public_ciphers_are_zeroize_on_drop() {
+        assert_zeroize_on_drop::<Aes128Siv>();
+        assert_zeroize_on_drop::<Aes192Siv>();
+        assert_zeroize_on_drop::<Aes256Siv>();
+        assert_zeroize_on_drop::<Aes128PmacSiv>();
+        assert_zeroize_on_drop::<Aes256PmacSiv>();
+        assert_zeroize_on_drop::<Aes128GcmSiv>();
+        assert_zeroize_on_drop::<Aes256GcmSiv>();
+    }
+
+    #[test]
+    fn aes128_siv_zeroize_on_drop() {
+        let mut siv = mem::ManuallyDrop::new(Aes128Siv::new(&[0x42u8; 32]));
+
+        unsafe {
+            ptr::drop_in_place(&mut *siv);
+
+            let (mac_cipher, subkey1, subkey2) = siv.mac_key.key_material();
+            assert!(is_zero(mac_cipher.round_keys()));
+            assert_eq!(subkey1.as_ref(), &[0u8; BLOCK_SIZE]);
+            assert_eq!(subkey2.as_ref(), &[0u8; BLOCK_SIZE]);
+            assert!(is_zero(siv.ctr.cipher().round_keys()));
+        }
+    }
+
+    #[test]
+    fn aes256_siv_zeroize_on_drop() {
+        let mut siv = mem::ManuallyDrop::new(Aes256Siv::new(&[0x42u8; 64]));
+
+        unsafe {
+            ptr::drop_in_place(&mut *siv);
+
+            let (mac_cipher, subkey1, subkey2) = siv.mac_key.key_material();
+            assert!(is_zero(mac_cipher.round_keys()));
+            assert_eq!(subkey1.as_ref(), &[0u8; BLOCK_SIZE]);
+            assert_eq!(subkey2.as_ref(), &[0u8; BLOCK_SIZE]);
+            assert!(is_zero(siv.ctr.cipher().round_keys()));
+        }
+    }
+
+    #[test]
+    fn rfc5297_s2v_intermediate_values() {
+        let cipher = Aes128::new(&RFC5297_S2V_KEY);
+        let mut incremental: S2v<Aes128, Cmac<Aes128>> = S2v::new(cipher.clone());
+
+        // CMAC(zero)
+        assert_eq!(incremental.state.as_ref(), &[
+            0x0e, 0x04, 0xdf, 0xaf, 0xc1, 0xef, 0xbf, 0x04,
+            0x01, 0x40, 0x58, 0x28, 0x59, 0xbf, 0x07, 0x3a,
+        ]);
+
+        // CMAC(ad)
+        assert_eq!(cmac(cipher.clone(), &RFC5297_AD).as_ref(), &[
+            0xf1, 0xf9, 0x22, 0xb7, 0xf5, 0x19, 0x3c, 0xe6,
+            0x4f, 0xf8, 0x0c, 0xb4, 0x7d, 0x93, 0xf2, 0x3b,
+        ]);
+
+        // double(CMAC(zero)) xor CMAC(ad)
+        incremental.add_vector(&RFC5297_AD);
+        assert_eq!(incremental.state.as_ref(), &[
+            0xed, 0xf0, 0x9d, 0xe8, 0x76, 0xc6, 0x42, 0xee,
+            0x4d, 0x78, 0xbc, 0xe4, 0xce, 0xed, 0xfc, 0x4f,
+        ]);
+
+        // CMAC(double(state) xor pad(plaintext))
+        let expected_v = [
+            0x85, 0x63, 0x2d, 0x07, 0xc6, 0xe8, 0xf3, 0x7f,
+            0x95, 0x0a, 0xcd, 0x32, 0x0a, 0x2e, 0xcc, 0x93,
+        ];
+
+        assert_eq!(incremental.finish_with_message(&RFC5297_PLAINTEXT).as_ref(), &expected_v);
+        assert_eq!(s2v(cipher, &[&RFC5297_AD[..], &RFC5297_PLAINTEXT[..]]).as_ref(), &expected_v);
+    }
+
+    #[test]
+    fn s2v_of_no_strings() {
+        let cipher = Aes128::new(&RFC5297_S2V_KEY);
+        let mut one = [0u8; BLOCK_SIZE];
+        one[BLOCK_SIZE - 1] = 1;
+
+        assert_eq!(s2v(cipher.clone(), &[]).as_ref(), cmac(cipher, &one).as_ref());
+    }
 }
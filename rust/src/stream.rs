@@ -0,0 +1,361 @@
+//! `stream.rs`: The STREAM online authenticated encryption construction.
+//!
+//! STREAM (from Hoang, Reyhanitabar, Rogaway, and Vizár's "Online
+//! Authenticated-Encryption and its Nonce-Reuse Misuse-Resistance") splits a
+//! message into a sequence of chunks, sealing each one with SIV under a nonce
+//! derived from a per-stream prefix, a chunk counter, and a flag marking the
+//! last chunk. Chunks can't be reordered, dropped, or truncated from the end
+//! of the stream without decryption failing.
+//!
+//! The nonce for each chunk is the 8-byte prefix, followed by the 32-bit
+//! big endian counter, followed by a byte which is 1 for the last chunk and
+//! 0 otherwise. It's passed to SIV as the last associated data item, after
+//! the chunk's own associated data.
+
+use byteorder::{BigEndian, ByteOrder};
+use error::Error;
+use internals::{Aes128, Aes256, BLOCK_SIZE, BlockCipher, Cmac, Mac, Pmac};
+use siv::Siv;
+use std::vec::Vec;
+
+/// Size of the nonce prefix supplied when creating a stream
+pub const NONCE_PREFIX_SIZE: usize = 8;
+
+/// Size of the full per-chunk nonce: prefix, 32-bit counter, last chunk flag
+const NONCE_SIZE: usize = NONCE_PREFIX_SIZE + 4 + 1;
+
+/// STREAM encryptor with AES-CMAC-SIV and a 256-bit key
+pub type Aes128SivEncryptor = StreamEncryptor<Aes128, Cmac<Aes128>>;
+
+/// STREAM decryptor with AES-CMAC-SIV and a 256-bit key
+pub type Aes128SivDecryptor = StreamDecryptor<Aes128, Cmac<Aes128>>;
+
+/// STREAM encryptor with AES-CMAC-SIV and a 512-bit key
+pub type Aes256SivEncryptor = StreamEncryptor<Aes256, Cmac<Aes256>>;
+
+/// STREAM decryptor with AES-CMAC-SIV and a 512-bit key
+pub type Aes256SivDecryptor = StreamDecryptor<Aes256, Cmac<Aes256>>;
+
+/// STREAM encryptor with AES-PMAC-SIV and a 256-bit key
+pub type Aes128PmacSivEncryptor = StreamEncryptor<Aes128, Pmac<Aes128>>;
+
+/// STREAM decryptor with AES-PMAC-SIV and a 256-bit key
+pub type Aes128PmacSivDecryptor = StreamDecryptor<Aes128, Pmac<Aes128>>;
+
+/// STREAM encryptor with AES-PMAC-SIV and a 512-bit key
+pub type Aes256PmacSivEncryptor = StreamEncryptor<Aes256, Pmac<Aes256>>;
+
+/// STREAM decryptor with AES-PMAC-SIV and a 512-bit key
+pub type Aes256PmacSivDecryptor = StreamDecryptor<Aes256, Pmac<Aes256>>;
+
+/// Encrypts a message as a sequence of chunks
+///
+/// Call `encrypt_next` for every chunk but the last, then `encrypt_last`.
+/// Each nonce prefix must only ever be used for one stream under a given key.
+pub struct StreamEncryptor<C: BlockCipher, M: Mac<C>> {
+    siv: Siv<C, M>,
+    nonce: NonceEncoder,
+}
+
+impl<M: Mac<Aes128>> StreamEncryptor<Aes128, M> {
+    /// Create a new STREAM encryptor with a 256-bit key
+    pub fn new(key: &[u8; 32], nonce_prefix: &[u8; NONCE_PREFIX_SIZE]) -> Self {
+        Self::from_siv(Siv::<Aes128, M>::new(key), nonce_prefix)
+    }
+}
+
+impl<M: Mac<Aes256>> StreamEncryptor<Aes256, M> {
+    /// Create a new STREAM encryptor with a 512-bit key
+    pub fn new(key: &[u8; 64], nonce_prefix: &[u8; NONCE_PREFIX_SIZE]) -> Self {
+        Self::from_siv(Siv::<Aes256, M>::new(key), nonce_prefix)
+    }
+}
+
+impl<C: BlockCipher, M: Mac<C>> StreamEncryptor<C, M> {
+    /// Create a new STREAM encryptor from an existing SIV instance
+    fn from_siv(siv: Siv<C, M>, nonce_prefix: &[u8; NONCE_PREFIX_SIZE]) -> Self {
+        Self {
+            siv: siv,
+            nonce: NonceEncoder::new(nonce_prefix),
+        }
+    }
+
+    /// Encrypt the next chunk of the stream, returning the SIV tag followed
+    /// by the ciphertext
+    ///
+    /// Returns `Error::AlreadyFinished` if `encrypt_last` has been called.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stream has more than 2^32 chunks.
+    pub fn encrypt_next(&mut self, ad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        self.encrypt(ad, plaintext, false)
+    }
+
+    /// Encrypt the last chunk of the stream, after which no more chunks can
+    /// be encrypted
+    ///
+    /// Returns `Error::AlreadyFinished` if `encrypt_last` has already been
+    /// called.
+    pub fn encrypt_last(&mut self, ad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        self.encrypt(ad, plaintext, true)
+    }
+
+    /// Encrypt a chunk with the current nonce, then advance it
+    fn encrypt(&mut self, ad: &[u8], plaintext: &[u8], last: bool) -> Result<Vec<u8>, Error> {
+        let mut buffer = plaintext.to_vec();
+        buffer.extend_from_slice(&[0; BLOCK_SIZE]);
+
+        self.siv.seal_in_place(&[ad, self.nonce.current(last)?], &mut buffer);
+        self.nonce.advance(last);
+
+        Ok(buffer)
+    }
+}
+
+/// Decrypts a message encrypted with `StreamEncryptor`, one chunk at a time
+///
+/// Call `decrypt_next` for every chunk but the last, then `decrypt_last`.
+/// A stream is only complete once `decrypt_last` has succeeded: if it never
+/// does, the stream was truncated.
+pub struct StreamDecryptor<C: BlockCipher, M: Mac<C>> {
+    siv: Siv<C, M>,
+    nonce: NonceEncoder,
+}
+
+impl<M: Mac<Aes128>> StreamDecryptor<Aes128, M> {
+    /// Create a new STREAM decryptor with a 256-bit key
+    pub fn new(key: &[u8; 32], nonce_prefix: &[u8; NONCE_PREFIX_SIZE]) -> Self {
+        Self::from_siv(Siv::<Aes128, M>::new(key), nonce_prefix)
+    }
+}
+
+impl<M: Mac<Aes256>> StreamDecryptor<Aes256, M> {
+    /// Create a new STREAM decryptor with a 512-bit key
+    pub fn new(key: &[u8; 64], nonce_prefix: &[u8; NONCE_PREFIX_SIZE]) -> Self {
+        Self::from_siv(Siv::<Aes256, M>::new(key), nonce_prefix)
+    }
+}
+
+impl<C: BlockCipher, M: Mac<C>> StreamDecryptor<C, M> {
+    /// Create a new STREAM decryptor from an existing SIV instance
+    fn from_siv(siv: Siv<C, M>, nonce_prefix: &[u8; NONCE_PREFIX_SIZE]) -> Self {
+        Self {
+            siv: siv,
+            nonce: NonceEncoder::new(nonce_prefix),
+        }
+    }
+
+    /// Decrypt the next chunk of the stream
+    ///
+    /// Returns `Error::Authentication` if the chunk fails to authenticate, e.g.
+    /// because chunks were reordered or this is actually the last chunk. The
+    /// stream position doesn't advance on failure.
+    ///
+    /// Returns `Error::AlreadyFinished` if `decrypt_last` has succeeded.
+    pub fn decrypt_next(&mut self, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        self.decrypt(ad, ciphertext, false)
+    }
+
+    /// Decrypt the last chunk of the stream
+    ///
+    /// Returns `Error::Authentication` if the chunk fails to authenticate, e.g.
+    /// because it isn't the last chunk. Returns `Error::AlreadyFinished` if
+    /// `decrypt_last` has already succeeded.
+    pub fn decrypt_last(&mut self, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        self.decrypt(ad, ciphertext, true)
+    }
+
+    /// Decrypt the chunk at the given index, e.g. to seek into the middle of
+    /// a stream, without decrypting the chunks before it
+    ///
+    /// `is_last` must be set if and only if this is the stream's last chunk,
+    /// otherwise it fails to authenticate with `Error::Authentication`, as does
+    /// a chunk from any other index. This is independent of sequential
+    /// decryption with `decrypt_next`/`decrypt_last`, whose position is left
+    /// unchanged.
+    pub fn decrypt_chunk(
+        &mut self,
+        index: u32,
+        is_last: bool,
+        ad: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        let mut buffer = ciphertext.to_vec();
+        self.siv.open_in_place_vec(&[ad, self.nonce.at(index, is_last)], &mut buffer)?;
+
+        Ok(buffer)
+    }
+
+    /// Decrypt a chunk with the current nonce, advancing it on success
+    fn decrypt(&mut self, ad: &[u8], ciphertext: &[u8], last: bool) -> Result<Vec<u8>, Error> {
+        let mut buffer = ciphertext.to_vec();
+
+        self.siv.open_in_place_vec(&[ad, self.nonce.current(last)?], &mut buffer)?;
+        self.nonce.advance(last);
+
+        Ok(buffer)
+    }
+}
+
+/// Computes the per-chunk STREAM nonces
+struct NonceEncoder {
+    value: [u8; NONCE_SIZE],
+    counter: u32,
+    finished: bool,
+}
+
+impl NonceEncoder {
+    /// Start a stream with the given nonce prefix
+    fn new(prefix: &[u8; NONCE_PREFIX_SIZE]) -> Self {
+        let mut value = [0u8; NONCE_SIZE];
+        value[..NONCE_PREFIX_SIZE].copy_from_slice(prefix);
+
+        Self {
+            value: value,
+            counter: 0,
+            finished: false,
+        }
+    }
+
+    /// Nonce for the current chunk, which is the last chunk if `last` is set
+    fn current(&mut self, last: bool) -> Result<&[u8], Error> {
+        if self.finished {
+            return Err(Error::AlreadyFinished);
+        }
+
+        let counter = self.counter;
+        Ok(self.at(counter, last))
+    }
+
+    /// Nonce for the chunk at the given index, regardless of the current
+    /// position in the stream
+    fn at(&mut self, index: u32, last: bool) -> &[u8] {
+        BigEndian::write_u32(&mut self.value[NONCE_PREFIX_SIZE..NONCE_SIZE - 1], index);
+        self.value[NONCE_SIZE - 1] = last as u8;
+
+        &self.value
+    }
+
+    /// Move on to the next chunk once the current one has been processed
+    fn advance(&mut self, last: bool) {
+        if last {
+            self.finished = true;
+        } else {
+            self.counter = self.counter.checked_add(1).expect(
+                "STREAM nonce counter overflowed",
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Aes128SivDecryptor, Aes128SivEncryptor};
+    use error::Error;
+
+    const KEY: [u8; 32] = [0x42; 32];
+    const NONCE_PREFIX: [u8; 8] = [0x24; 8];
+
+    #[test]
+    fn encrypt_after_last() {
+        let mut encryptor = Aes128SivEncryptor::new(&KEY, &NONCE_PREFIX);
+        encryptor.encrypt_next(b"", b"first").unwrap();
+        encryptor.encrypt_last(b"", b"last").unwrap();
+
+        assert_eq!(
+            encryptor.encrypt_next(b"", b"more").err(),
+            Some(Error::AlreadyFinished)
+        );
+        assert_eq!(
+            encryptor.encrypt_last(b"", b"more").err(),
+            Some(Error::AlreadyFinished)
+        );
+    }
+
+    #[test]
+    fn reordered_and_truncated_chunks() {
+        let mut encryptor = Aes128SivEncryptor::new(&KEY, &NONCE_PREFIX);
+        let first = encryptor.encrypt_next(b"", b"first").unwrap();
+        let second = encryptor.encrypt_next(b"", b"second").unwrap();
+        let last = encryptor.encrypt_last(b"", b"last").unwrap();
+
+        let mut decryptor = Aes128SivDecryptor::new(&KEY, &NONCE_PREFIX);
+        assert_eq!(decryptor.decrypt_next(b"", &second).err(), Some(Error::Authentication));
+        assert_eq!(decryptor.decrypt_next(b"", &first).unwrap(), b"first");
+
+        // A non-final chunk can't be passed off as the last one
+        assert_eq!(decryptor.decrypt_last(b"", &second).err(), Some(Error::Authentication));
+        assert_eq!(decryptor.decrypt_next(b"", &second).unwrap(), b"second");
+        assert_eq!(decryptor.decrypt_next(b"", &last).err(), Some(Error::Authentication));
+        assert_eq!(decryptor.decrypt_last(b"", &last).unwrap(), b"last");
+
+        assert_eq!(
+            decryptor.decrypt_last(b"", &last).err(),
+            Some(Error::AlreadyFinished)
+        );
+    }
+
+    #[test]
+    fn decrypt_chunk_matches_sequential() {
+        let chunks: [&[u8]; 4] = [b"zero", b"one", b"two", b"three"];
+
+        let mut encryptor = Aes128SivEncryptor::new(&KEY, &NONCE_PREFIX);
+        let mut ciphertexts = vec![];
+
+        for chunk in &chunks[..3] {
+            ciphertexts.push(encryptor.encrypt_next(b"ad", chunk).unwrap());
+        }
+
+        ciphertexts.push(encryptor.encrypt_last(b"ad", chunks[3]).unwrap());
+
+        let mut sequential = Aes128SivDecryptor::new(&KEY, &NONCE_PREFIX);
+        let mut seeking = Aes128SivDecryptor::new(&KEY, &NONCE_PREFIX);
+
+        for (i, ciphertext) in ciphertexts.iter().enumerate() {
+            let is_last = i == ciphertexts.len() - 1;
+
+            let expected = if is_last {
+                sequential.decrypt_last(b"ad", ciphertext).unwrap()
+            } else {
+                sequential.decrypt_next(b"ad", ciphertext).unwrap()
+            };
+
+            assert_eq!(expected, chunks[i]);
+
+            // Seek backwards from each chunk to every chunk up to it
+            for j in (0..i + 1).rev() {
+                let plaintext = seeking
+                    .decrypt_chunk(j as u32, j == ciphertexts.len() - 1, b"ad", &ciphertexts[j])
+                    .unwrap();
+                assert_eq!(plaintext, chunks[j]);
+            }
+        }
+
+        // Seeking doesn't disturb sequential decryption
+        assert_eq!(seeking.decrypt_next(b"ad", &ciphertexts[0]).unwrap(), chunks[0]);
+    }
+
+    #[test]
+    fn decrypt_chunk_wrong_position() {
+        let mut encryptor = Aes128SivEncryptor::new(&KEY, &NONCE_PREFIX);
+        let first = encryptor.encrypt_next(b"", b"first").unwrap();
+        let last = encryptor.encrypt_last(b"", b"last").unwrap();
+
+        let mut decryptor = Aes128SivDecryptor::new(&KEY, &NONCE_PREFIX);
+        assert_eq!(
+            decryptor.decrypt_chunk(0, true, b"", &first).err(),
+            Some(Error::Authentication)
+        );
+        assert_eq!(
+            decryptor.decrypt_chunk(1, false, b"", &last).err(),
+            Some(Error::Authentication)
+        );
+        assert_eq!(
+            decryptor.decrypt_chunk(1, false, b"", &first).err(),
+            Some(Error::Authentication)
+        );
+        assert_eq!(decryptor.decrypt_chunk(0, true, b"", &last).err(), Some(Error::Authentication));
+        assert_eq!(decryptor.decrypt_chunk(1, true, b"", &last).unwrap(), b"last");
+    }
+}
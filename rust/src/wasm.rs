@@ -0,0 +1,139 @@
+//! `wasm.rs`: WebAssembly bindings for AES-SIV via `wasm-bindgen`
+//!
+//! Messages are the SIV tag followed by the ciphertext, with the associated
+//! data and nonce as separate S2V inputs (nonce last), the same as the
+//! `aead` trait impls. This interoperates with miscreant.js:
+//!
+//! ```text
+//! WasmAesSiv.seal(nonce, ad, plaintext) == AesSiv.seal(plaintext, [ad, nonce])
+//! ```
+
+use error::Error;
+use internals::BLOCK_SIZE;
+use siv::{Aes128Siv, Aes256Siv};
+use std::vec::Vec;
+use wasm_bindgen::prelude::*;
+
+/// AES-SIV with a 256-bit (AES-128) or 512-bit (AES-256) key, for JavaScript
+#[wasm_bindgen]
+pub struct WasmAesSiv {
+    siv: Siv,
+}
+
+/// The AES variant selected by the key length
+enum Siv {
+    Aes128(Aes128Siv),
+    Aes256(Aes256Siv),
+}
+
+#[wasm_bindgen]
+impl WasmAesSiv {
+    /// Create a new AES-SIV instance from a 32-byte or 64-byte key, throwing
+    /// an `Error` for any other length
+    #[wasm_bindgen(constructor)]
+    pub fn new(key: &[u8]) -> Result<WasmAesSiv, JsError> {
+        let siv = match key.len() {
+            32 => Siv::Aes128(Aes128Siv::new(array_ref!(key, 0, 32))),
+            64 => Siv::Aes256(Aes256Siv::new(array_ref!(key, 0, 64))),
+            _ => return Err(js_error(Error::KeySize)),
+        };
+
+        Ok(Self { siv: siv })
+    }
+
+    /// Encrypt and authenticate `plaintext`, returning a `Uint8Array` of the
+    /// SIV tag followed by the ciphertext
+    pub fn seal(&mut self, nonce: &[u8], ad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        let mut out = vec![0u8; plaintext.len().checked_add(BLOCK_SIZE).expect("overflow")];
+        let ad = [ad, nonce];
+
+        let result = match self.siv {
+            Siv::Aes128(ref mut siv) => siv.seal_to_slice(&ad, plaintext, &mut out),
+            Siv::Aes256(ref mut siv) => siv.seal_to_slice(&ad, plaintext, &mut out),
+        };
+
+        result.expect("output sized for the tag");
+        out
+    }
+
+    /// Decrypt and authenticate a message produced by `seal`, throwing an
+    /// `Error` if it's truncated or fails authentication
+    pub fn open(
+        &mut self,
+        nonce: &[u8],
+        ad: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, JsError> {
+        let mut buffer = ciphertext.to_vec();
+        let ad = [ad, nonce];
+
+        let len = match self.siv {
+            Siv::Aes128(ref mut siv) => siv.open_in_place(&ad, &mut buffer).map(|p| p.len()),
+            Siv::Aes256(ref mut siv) => siv.open_in_place(&ad, &mut buffer).map(|p| p.len()),
+        }.map_err(js_error)?;
+
+        buffer.truncate(len);
+        Ok(buffer)
+    }
+}
+
+/// Convert an `Error` into a JavaScript `Error` with the same message
+fn js_error(err: Error) -> JsError {
+    JsError::new(&format!("AES-SIV: {}", err))
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use super::WasmAesSiv;
+    use wasm_bindgen_test::*;
+
+    const KEY: [u8; 32] = [0x42; 32];
+    const NONCE: &[u8] = b"unique nonce";
+    const AD: &[u8] = b"associated data";
+
+    #[wasm_bindgen_test]
+    fn seal_and_open() {
+        for key in &[&KEY[..], &[0x42u8; 64][..]] {
+            let mut siv = WasmAesSiv::new(key).unwrap();
+
+            for len in &[0, 1, 16, 100] {
+                let plaintext = vec![0x24u8; *len];
+                let sealed = siv.seal(NONCE, AD, &plaintext);
+                assert_eq!(sealed.len(), len + 16);
+                assert_eq!(siv.open(NONCE, AD, &sealed).unwrap(), plaintext);
+            }
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn matches_seal_to_slice() {
+        let mut siv = WasmAesSiv::new(&KEY).unwrap();
+        let mut expected = ::siv::Aes128Siv::new(&KEY);
+
+        let mut buffer = [0u8; 25];
+        let len = expected.seal_to_slice(&[AD, NONCE], b"plaintext", &mut buffer).unwrap();
+
+        assert_eq!(&siv.seal(NONCE, AD, b"plaintext")[..], &buffer[..len]);
+    }
+
+    #[wasm_bindgen_test]
+    fn tampered_messages_are_rejected() {
+        let mut siv = WasmAesSiv::new(&KEY).unwrap();
+        let sealed = siv.seal(NONCE, AD, b"attack at dawn");
+
+        for i in 0..sealed.len() {
+            let mut tampered = sealed.clone();
+            tampered[i] ^= 1;
+            assert!(siv.open(NONCE, AD, &tampered).is_err());
+        }
+
+        assert!(siv.open(b"other nonce", AD, &sealed).is_err());
+        assert!(siv.open(NONCE, b"other ad", &sealed).is_err());
+        assert!(siv.open(NONCE, AD, &sealed[..15]).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn invalid_key_size() {
+        assert!(WasmAesSiv::new(&[0u8; 16]).is_err());
+    }
+}
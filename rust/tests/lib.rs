@@ -1,27 +1,64 @@
+#[cfg(feature = "aead")]
+extern crate aead;
+#[cfg(feature = "cipher")]
+extern crate aes;
 #[macro_use]
 extern crate arrayref;
+#[cfg(feature = "serde")]
+extern crate bincode;
+#[cfg(feature = "cipher")]
+extern crate cipher;
+#[cfg(feature = "crypto-mac")]
+extern crate crypto_mac;
+#[cfg(feature = "cipher")]
+extern crate ctr;
+#[cfg(feature = "rand_core")]
+extern crate rand_core;
 extern crate miscreant;
+#[macro_use]
+extern crate proptest;
+#[cfg(feature = "serde")]
+extern crate serde_json;
 
-use miscreant::{Aes128Siv, Aes256Siv};
-use miscreant::internals::{Aes128, Aes256, Block, BlockCipher, Cmac, Ctr};
+use miscreant::{Aes128GcmSiv, Aes128PmacSiv, Aes128Siv, Aes192Siv, Aes256GcmSiv, Aes256PmacSiv,
+                Aes256Siv, Ctr};
+use miscreant::{CtrDrbg, Error};
+use miscreant::kdf;
+use miscreant::siv::{s2v, S2v, Siv};
+#[cfg(feature = "std")]
+use miscreant::{decrypt_file, encrypt_file, Aes128KeyWrap, Aes256KeyWrap, FileError, NonceTracker};
+#[cfg(feature = "std")]
+use miscreant::stream::{Aes128PmacSivDecryptor, Aes128PmacSivEncryptor, Aes128SivDecryptor,
+                        Aes128SivEncryptor, Aes256PmacSivDecryptor, Aes256PmacSivEncryptor,
+                        Aes256SivDecryptor, Aes256SivEncryptor, StreamDecryptor, StreamEncryptor};
+use miscreant::internals::{Aes128, Aes192, Aes256, Block, BlockCipher, Cmac, Mac, Pmac, Tag};
+use miscreant::internals::cmac;
 use miscreant::internals::BLOCK_SIZE;
 
+mod properties;
 mod test_vectors;
-use test_vectors::{AesExample, AesCmacExample, AesCtrExample, AesSivExample, DblExample};
+use test_vectors::{AesExample, AesCmacExample, AesCmacKdfCavpExample, AesCmacKdfExample,
+                   AesCtrDrbgExample, AesCtrExample, AesGcmSivExample, AesPmacExample,
+                   AesPmacSivExample, AesSivExample, DblExample};
+#[cfg(feature = "std")]
+use test_vectors::AesSivStreamExample;
 
 #[test]
 fn aes_examples() {
     let examples = AesExample::load_all();
 
     for example in examples {
-        let mut block = Block::new();
-        block.as_mut().copy_from_slice(&example.src);
+        let mut block = Block::try_from_slice(&example.src).unwrap();
 
         match example.key.len() {
             16 => {
                 let aes = Aes128::new(array_ref!(example.key, 0, 16));
                 aes.encrypt(&mut block);
             }
+            24 => {
+                let aes = Aes192::new(array_ref!(example.key, 0, 24));
+                aes.encrypt(&mut block);
+            }
             32 => {
                 let aes = Aes256::new(array_ref!(example.key, 0, 32));
                 aes.encrypt(&mut block);
@@ -43,15 +80,142 @@ fn aes_cmac_examples() {
                 let aes = Aes128::new(array_ref!(example.key, 0, 16));
                 let mut aes_cmac = Cmac::new(aes);
 
-                aes_cmac.update(&example.message);
-                aes_cmac.finish()
+                aes_cmac.update(&example.message).unwrap();
+                aes_cmac.finish().unwrap()
             }
             32 => {
                 let aes = Aes256::new(array_ref!(example.key, 0, 32));
                 let mut aes_cmac = Cmac::new(aes);
 
-                aes_cmac.update(&example.message);
-                aes_cmac.finish()
+                aes_cmac.update(&example.message).unwrap();
+                aes_cmac.finish().unwrap()
+            }
+            _ => panic!("unexpected key size: {}", example.key.len()),
+        };
+
+        assert_eq!(result.as_ref(), array_ref!(example.tag, 0, 16));
+    }
+}
+
+#[test]
+fn aes_cmac_oneshot_examples() {
+    for example in AesCmacExample::load_all() {
+        let tag = match example.key.len() {
+            16 => cmac(Aes128::new(array_ref!(example.key, 0, 16)), &example.message),
+            32 => cmac(Aes256::new(array_ref!(example.key, 0, 32)), &example.message),
+            _ => panic!("unexpected key size: {}", example.key.len()),
+        };
+
+        assert_eq!(tag.as_ref(), array_ref!(example.tag, 0, 16));
+    }
+}
+
+#[cfg(feature = "crypto-mac")]
+#[test]
+fn aes_cmac_mac_trait() {
+    use crypto_mac::Mac;
+
+    for example in AesCmacExample::load_all()
+        .iter()
+        .filter(|ex| ex.key.len() == 16)
+    {
+        let mut mac = Cmac::new(Aes128::new(array_ref!(example.key, 0, 16)));
+        Mac::update(&mut mac, &example.message);
+        assert!(mac.clone().verify(&example.tag).is_ok());
+        assert_eq!(&mac.finalize().into_bytes()[..], &example.tag[..]);
+
+        let mut forged = example.tag.clone();
+        forged[0] ^= 1;
+
+        let mut mac = Cmac::new(Aes128::new(array_ref!(example.key, 0, 16)));
+        Mac::update(&mut mac, &example.message);
+        assert!(mac.verify(&forged).is_err());
+    }
+}
+
+#[cfg(feature = "crypto-mac")]
+#[test]
+fn aes_cmac_new_mac_trait() {
+    for example in AesCmacExample::load_all() {
+        match example.key.len() {
+            16 => check_new_mac_trait::<Cmac<Aes128>>(&example),
+            32 => check_new_mac_trait::<Cmac<Aes256>>(&example),
+            _ => panic!("unexpected key size: {}", example.key.len()),
+        }
+    }
+}
+
+/// Exercise a MAC purely through the `NewMac` and `Mac` traits
+#[cfg(feature = "crypto-mac")]
+fn check_new_mac_trait<M: crypto_mac::Mac + crypto_mac::NewMac>(example: &AesCmacExample) {
+    assert!(M::new_from_slice(&example.key[1..]).is_err());
+
+    let mut mac = M::new_from_slice(&example.key).unwrap();
+    mac.update(&example.message);
+    assert_eq!(&mac.finalize_reset().into_bytes()[..], &example.tag[..]);
+
+    // The MAC must be reusable after `finalize_reset`
+    mac.update(&example.message);
+    assert!(mac.clone().verify(&example.tag).is_ok());
+    assert!(mac.clone().verify(&example.tag[..8]).is_err());
+    assert_eq!(&mac.finalize().into_bytes()[..], &example.tag[..]);
+}
+
+#[test]
+fn aes_cmac_kdf_examples() {
+    for example in AesCmacKdfExample::load_all() {
+        let mut output = vec![0u8; example.output.len()];
+        kdf::derive_key(&example.key, &example.label, &example.context, &mut output).unwrap();
+        assert_eq!(output, example.output);
+    }
+}
+
+#[test]
+fn aes_cmac_kdf_cavp_examples() {
+    for example in AesCmacKdfCavpExample::load_all() {
+        let mut output = vec![0u8; example.output.len()];
+        kdf::derive_key_from_fixed_input(&example.key, &example.fixed_input, &mut output).unwrap();
+        assert_eq!(output, example.output);
+    }
+}
+
+#[test]
+fn aes_ctr_drbg_examples() {
+    for example in AesCtrDrbgExample::load_all() {
+        let mut drbg = CtrDrbg::new(&example.entropy_input, &example.personalization_string)
+            .unwrap();
+
+        if !example.entropy_input_reseed.is_empty() {
+            drbg.reseed(&example.entropy_input_reseed, &example.additional_input_reseed)
+                .unwrap();
+        }
+
+        let mut output = vec![0u8; example.returned_bytes.len()];
+        drbg.generate(&mut output, &example.additional_input_1).unwrap();
+        drbg.generate(&mut output, &example.additional_input_2).unwrap();
+        assert_eq!(output, example.returned_bytes);
+    }
+}
+
+#[test]
+fn aes_pmac_examples() {
+    let examples = AesPmacExample::load_all();
+
+    for example in examples {
+        let result = match example.key.len() {
+            16 => {
+                let aes = Aes128::new(array_ref!(example.key, 0, 16));
+                let mut aes_pmac = Pmac::new(aes);
+
+                aes_pmac.update(&example.message).unwrap();
+                aes_pmac.finish().unwrap()
+            }
+            32 => {
+                let aes = Aes256::new(array_ref!(example.key, 0, 32));
+                let mut aes_pmac = Pmac::new(aes);
+
+                aes_pmac.update(&example.message).unwrap();
+                aes_pmac.finish().unwrap()
             }
             _ => panic!("unexpected key size: {}", example.key.len()),
         };
@@ -60,6 +224,22 @@ fn aes_cmac_examples() {
     }
 }
 
+#[test]
+fn aes_pmac_incremental_update() {
+    let examples = AesPmacExample::load_all();
+
+    for example in examples.iter().filter(|ex| ex.key.len() == 16) {
+        let aes = Aes128::new(array_ref!(example.key, 0, 16));
+        let mut aes_pmac = Pmac::new(aes);
+
+        for chunk in example.message.chunks(7) {
+            aes_pmac.update(chunk).unwrap();
+        }
+
+        assert_eq!(aes_pmac.finish().unwrap().as_ref(), array_ref!(example.tag, 0, 16));
+    }
+}
+
 #[test]
 fn aes_ctr_examples() {
     let examples = AesCtrExample::load_all();
@@ -70,15 +250,13 @@ fn aes_ctr_examples() {
         match example.key.len() {
             16 => {
                 let aes = Aes128::new(array_ref!(example.key, 0, 16));
-                let mut aes_ctr = Ctr::new(aes);
-                let mut iv = Block::from(&example.iv[..]);
-                aes_ctr.transform(&mut iv, &mut buffer);
+                let iv = Block::try_from_slice(&example.iv).unwrap();
+                Ctr::new(aes, &iv).apply_keystream(&mut buffer).unwrap();
             }
             32 => {
                 let aes = Aes256::new(array_ref!(example.key, 0, 32));
-                let mut aes_ctr = Ctr::new(aes);
-                let mut iv = Block::from(&example.iv[..]);
-                aes_ctr.transform(&mut iv, &mut buffer);
+                let iv = Block::try_from_slice(&example.iv).unwrap();
+                Ctr::new(aes, &iv).apply_keystream(&mut buffer).unwrap();
             }
             _ => panic!("unexpected key size: {}", example.key.len()),
         };
@@ -112,6 +290,107 @@ fn aes_siv_examples_seal() {
     }
 }
 
+#[test]
+fn aes_siv_clone_examples() {
+    let examples = AesSivExample::load_all();
+
+    for example in examples {
+        let len = example.plaintext.len();
+        let mut buffer = vec![0; len + BLOCK_SIZE];
+        buffer[..len].copy_from_slice(&example.plaintext);
+
+        match example.key.len() {
+            32 => {
+                let siv = Aes128Siv::new(array_ref!(example.key, 0, 32));
+                siv.clone().seal_in_place(&example.ad, &mut buffer);
+            }
+            64 => {
+                let siv = Aes256Siv::new(array_ref!(example.key, 0, 64));
+                siv.clone().seal_in_place(&example.ad, &mut buffer);
+            }
+            _ => panic!("unexpected key size: {}", example.key.len()),
+        };
+
+        assert_eq!(buffer, example.ciphertext);
+    }
+}
+
+#[test]
+fn aes_siv_reused_instance_matches_fresh_instances() {
+    check_reused_instance(&mut Aes128Siv::new(&[0x42; 32]), || Aes128Siv::new(&[0x42; 32]));
+    check_reused_instance(&mut Aes256Siv::new(&[0x42; 64]), || Aes256Siv::new(&[0x42; 64]));
+    check_reused_instance(&mut Aes128PmacSiv::new(&[0x42; 32]), || {
+        Aes128PmacSiv::new(&[0x42; 32])
+    });
+}
+
+/// Seal and open many messages with one instance, including failed opens
+/// (which re-encrypt), checking each result against a newly created instance
+fn check_reused_instance<C, M, F>(siv: &mut Siv<C, M>, fresh: F)
+where
+    C: BlockCipher,
+    M: Mac<C>,
+    F: Fn() -> Siv<C, M>,
+{
+    for len in 0..80 {
+        let ad: &[&[u8]] = &[b"header", &[len as u8]];
+        let plaintext: Vec<u8> = (0..len).map(|i| (i * 7) as u8).collect();
+
+        let mut sealed = plaintext.clone();
+        sealed.extend_from_slice(&[0u8; BLOCK_SIZE]);
+        let mut expected = sealed.clone();
+
+        siv.seal_in_place(ad, &mut sealed);
+        fresh().seal_in_place(ad, &mut expected);
+        assert_eq!(sealed, expected, "len {}", len);
+
+        let mut forged = sealed.clone();
+        *forged.last_mut().unwrap() ^= 1;
+        assert_eq!(siv.open_in_place(ad, &mut forged).err(), Some(Error::Authentication));
+
+        assert_eq!(siv.open_in_place(ad, &mut sealed).unwrap(), &plaintext[..]);
+    }
+}
+
+#[test]
+fn aes_siv_sealer_examples() {
+    let examples = AesSivExample::load_all();
+
+    for example in examples {
+        // The sealer always takes a final associated data item
+        let (final_ad, ad) = match example.ad.split_last() {
+            Some(split) => split,
+            None => continue,
+        };
+
+        let len = example.plaintext.len();
+        let mut buffer = vec![0; len + BLOCK_SIZE];
+        buffer[..len].copy_from_slice(&example.plaintext);
+
+        match example.key.len() {
+            32 => {
+                let mut siv = Aes128Siv::new(array_ref!(example.key, 0, 32));
+                let mut sealer = siv.sealer();
+                for item in ad {
+                    sealer.add_associated_data(item);
+                }
+                sealer.seal_in_place(final_ad, &mut buffer);
+            }
+            64 => {
+                let mut siv = Aes256Siv::new(array_ref!(example.key, 0, 64));
+                let mut sealer = siv.sealer();
+                for item in ad {
+                    sealer.add_associated_data(item);
+                }
+                sealer.seal_in_place(final_ad, &mut buffer);
+            }
+            _ => panic!("unexpected key size: {}", example.key.len()),
+        };
+
+        assert_eq!(buffer, example.ciphertext);
+    }
+}
+
 #[test]
 fn aes_siv_examples_open() {
     let examples = AesSivExample::load_all();
@@ -136,11 +415,1337 @@ fn aes_siv_examples_open() {
 }
 
 #[test]
-fn dbl_examples() {
-    let examples = DblExample::load_all();
+fn aes_siv_examples_open_forged_tag() {
+    let examples = AesSivExample::load_all();
+
     for example in examples {
-        let mut block = Block::from(&example.input[..]);
-        block.dbl();
-        assert_eq!(block.as_ref(), &example.output[..]);
+        let mut buffer = example.ciphertext.clone();
+        buffer[0] ^= 1;
+
+        let result = match example.key.len() {
+            32 => {
+                let mut siv = Aes128Siv::new(array_ref!(example.key, 0, 32));
+                siv.open_in_place(&example.ad, &mut buffer).map(|_| ())
+            }
+            64 => {
+                let mut siv = Aes256Siv::new(array_ref!(example.key, 0, 64));
+                siv.open_in_place(&example.ad, &mut buffer).map(|_| ())
+            }
+            _ => panic!("unexpected key size: {}", example.key.len()),
+        };
+
+        assert_eq!(result, Err(Error::Authentication));
+    }
+}
+
+#[test]
+fn aes192_siv_examples_seal() {
+    for example in AesSivExample::load_aes192() {
+        let len = example.plaintext.len();
+        let mut buffer = vec![0; len + BLOCK_SIZE];
+        buffer[..len].copy_from_slice(&example.plaintext);
+
+        let mut siv = Aes192Siv::new(array_ref!(example.key, 0, 48));
+        siv.seal_in_place(&example.ad, &mut buffer);
+        assert_eq!(buffer, example.ciphertext);
+    }
+}
+
+#[test]
+fn aes192_siv_examples_open() {
+    for example in AesSivExample::load_aes192() {
+        let mut siv = Aes192Siv::new(array_ref!(example.key, 0, 48));
+
+        let mut buffer = example.ciphertext.clone();
+        let plaintext = siv.open_in_place(&example.ad, &mut buffer).expect("successful decrypt");
+        assert_eq!(plaintext, &example.plaintext[..]);
+
+        let mut buffer = example.ciphertext.clone();
+        buffer[0] ^= 1;
+        assert_eq!(siv.open_in_place(&example.ad, &mut buffer).err(), Some(Error::Authentication));
+    }
+}
+
+#[test]
+fn open_truncated_messages() {
+    let mut siv = Aes128Siv::new(&[0u8; 32]);
+    let mut pmac_siv = Aes128PmacSiv::new(&[0u8; 32]);
+    let mut gcm_siv = Aes128GcmSiv::new(&[0u8; 16]);
+    let nonce = [0u8; 12];
+
+    // Too short to hold a tag, including the empty message
+    for len in 0..BLOCK_SIZE {
+        let mut buffer = vec![0u8; len];
+
+        assert_eq!(siv.open_in_place(&[&nonce], &mut buffer).err(), Some(Error::Authentication));
+        assert_eq!(
+            pmac_siv.open_in_place(&[&nonce], &mut buffer).err(),
+            Some(Error::Authentication)
+        );
+        assert_eq!(
+            gcm_siv.open_in_place(&[&nonce], &mut buffer).err(),
+            Some(Error::Authentication)
+        );
+    }
+}
+
+#[test]
+fn aes_pmac_siv_examples_seal() {
+    let examples = AesPmacSivExample::load_all();
+
+    for example in examples {
+        let len = example.plaintext.len();
+        let mut buffer = vec![0; len + BLOCK_SIZE];
+        buffer[..len].copy_from_slice(&example.plaintext);
+
+        match example.key.len() {
+            32 => {
+                let mut siv = Aes128PmacSiv::new(array_ref!(example.key, 0, 32));
+                siv.seal_in_place(&example.ad, &mut buffer);
+            }
+            64 => {
+                let mut siv = Aes256PmacSiv::new(array_ref!(example.key, 0, 64));
+                siv.seal_in_place(&example.ad, &mut buffer);
+            }
+            _ => panic!("unexpected key size: {}", example.key.len()),
+        };
+
+        assert_eq!(buffer, example.ciphertext);
+    }
+}
+
+#[test]
+fn aes_pmac_siv_examples_open() {
+    let examples = AesPmacSivExample::load_all();
+
+    for example in examples {
+        let mut buffer = example.ciphertext.clone();
+
+        let plaintext = match example.key.len() {
+            32 => {
+                let mut siv = Aes128PmacSiv::new(array_ref!(example.key, 0, 32));
+                siv.open_in_place(&example.ad, &mut buffer)
+            }
+            64 => {
+                let mut siv = Aes256PmacSiv::new(array_ref!(example.key, 0, 64));
+                siv.open_in_place(&example.ad, &mut buffer)
+            }
+            _ => panic!("unexpected key size: {}", example.key.len()),
+        }.expect("successful decrypt");
+
+        assert_eq!(plaintext, &example.plaintext[..]);
+    }
+}
+
+#[test]
+fn aes_pmac_siv_examples_open_forged_tag() {
+    let examples = AesPmacSivExample::load_all();
+
+    for example in examples {
+        let mut buffer = example.ciphertext.clone();
+        buffer[0] ^= 1;
+
+        let result = match example.key.len() {
+            32 => {
+                let mut siv = Aes128PmacSiv::new(array_ref!(example.key, 0, 32));
+                siv.open_in_place(&example.ad, &mut buffer).map(|_| ())
+            }
+            64 => {
+                let mut siv = Aes256PmacSiv::new(array_ref!(example.key, 0, 64));
+                siv.open_in_place(&example.ad, &mut buffer).map(|_| ())
+            }
+            _ => panic!("unexpected key size: {}", example.key.len()),
+        };
+
+        assert_eq!(result, Err(Error::Authentication));
+    }
+}
+
+#[test]
+fn aes_pmac_siv_sealer_examples() {
+    let examples = AesPmacSivExample::load_all();
+
+    for example in examples {
+        // The sealer always takes a final associated data item
+        let (final_ad, ad) = match example.ad.split_last() {
+            Some(split) => split,
+            None => continue,
+        };
+
+        let len = example.plaintext.len();
+        let mut buffer = vec![0; len + BLOCK_SIZE];
+        buffer[..len].copy_from_slice(&example.plaintext);
+
+        match example.key.len() {
+            32 => {
+                let mut siv = Aes128PmacSiv::new(array_ref!(example.key, 0, 32));
+                let mut sealer = siv.sealer();
+                for item in ad {
+                    sealer.add_associated_data(item);
+                }
+                sealer.seal_in_place(final_ad, &mut buffer);
+            }
+            64 => {
+                let mut siv = Aes256PmacSiv::new(array_ref!(example.key, 0, 64));
+                let mut sealer = siv.sealer();
+                for item in ad {
+                    sealer.add_associated_data(item);
+                }
+                sealer.seal_in_place(final_ad, &mut buffer);
+            }
+            _ => panic!("unexpected key size: {}", example.key.len()),
+        };
+
+        assert_eq!(buffer, example.ciphertext);
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn aes_pmac_siv_examples_detached() {
+    for example in AesPmacSivExample::load_all() {
+        let tag = array_ref!(example.ciphertext, 0, BLOCK_SIZE);
+        let ciphertext = &example.ciphertext[BLOCK_SIZE..];
+
+        let (sealed_tag, sealed_ciphertext, opened) = match example.key.len() {
+            32 => {
+                let mut siv = Aes128PmacSiv::new(array_ref!(example.key, 0, 32));
+                let (sealed_tag, sealed_ciphertext) = siv.seal_detached(&example.ad, &example.plaintext);
+                (sealed_tag, sealed_ciphertext, siv.open_detached(tag, &example.ad, ciphertext))
+            }
+            64 => {
+                let mut siv = Aes256PmacSiv::new(array_ref!(example.key, 0, 64));
+                let (sealed_tag, sealed_ciphertext) = siv.seal_detached(&example.ad, &example.plaintext);
+                (sealed_tag, sealed_ciphertext, siv.open_detached(tag, &example.ad, ciphertext))
+            }
+            _ => panic!("unexpected key size: {}", example.key.len()),
+        };
+
+        assert_eq!(&sealed_tag, tag);
+        assert_eq!(sealed_ciphertext, ciphertext);
+        assert_eq!(opened.unwrap(), example.plaintext);
     }
 }
+
+#[cfg(feature = "std")]
+#[test]
+fn aes_siv_examples_open_vec() {
+    let examples = AesSivExample::load_all();
+
+    for example in examples {
+        let mut buffer = example.ciphertext.clone();
+        open_vec(&example.key, &example.ad, &mut buffer).expect("successful decrypt");
+        assert_eq!(buffer, example.plaintext);
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn aes_siv_examples_open_vec_tampered() {
+    let examples = AesSivExample::load_all();
+
+    for example in examples {
+        // Tampered ciphertext (or tag, if there's no ciphertext)
+        let mut buffer = example.ciphertext.clone();
+        let last = buffer.len() - 1;
+        buffer[last] ^= 1;
+
+        let result = open_vec(&example.key, &example.ad, &mut buffer);
+        assert_eq!(result, Err(Error::Authentication));
+        assert_eq!(buffer, vec![0u8; example.ciphertext.len()]);
+
+        // Tampered associated data
+        let mut ad = example.ad.clone();
+        ad.push(b"unexpected".to_vec());
+
+        let mut buffer = example.ciphertext.clone();
+        let result = open_vec(&example.key, &ad, &mut buffer);
+        assert_eq!(result, Err(Error::Authentication));
+        assert_eq!(buffer, vec![0u8; example.ciphertext.len()]);
+    }
+
+    let mut buffer = vec![0xffu8; BLOCK_SIZE - 1];
+    let result = open_vec(&[0u8; 32], &[], &mut buffer);
+    assert_eq!(result, Err(Error::Authentication));
+    assert_eq!(buffer, vec![0u8; BLOCK_SIZE - 1]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn aes_siv_examples_detached() {
+    for example in AesSivExample::load_all() {
+        let tag = array_ref!(example.ciphertext, 0, BLOCK_SIZE);
+        let ciphertext = &example.ciphertext[BLOCK_SIZE..];
+
+        // The combined form is the detached tag followed by the ciphertext
+        let (sealed_tag, sealed_ciphertext, opened) = match example.key.len() {
+            32 => {
+                let mut siv = Aes128Siv::new(array_ref!(example.key, 0, 32));
+                let (sealed_tag, sealed_ciphertext) = siv.seal_detached(&example.ad, &example.plaintext);
+                (sealed_tag, sealed_ciphertext, siv.open_detached(tag, &example.ad, ciphertext))
+            }
+            64 => {
+                let mut siv = Aes256Siv::new(array_ref!(example.key, 0, 64));
+                let (sealed_tag, sealed_ciphertext) = siv.seal_detached(&example.ad, &example.plaintext);
+                (sealed_tag, sealed_ciphertext, siv.open_detached(tag, &example.ad, ciphertext))
+            }
+            _ => panic!("unexpected key size: {}", example.key.len()),
+        };
+
+        assert_eq!(&sealed_tag, tag);
+        assert_eq!(sealed_ciphertext, ciphertext);
+        assert_eq!(opened.unwrap(), example.plaintext);
+    }
+}
+
+#[test]
+fn aes_siv_examples_seal_to_slice() {
+    for example in AesSivExample::load_all() {
+        let len = example.ciphertext.len();
+
+        // Trailing space in the output is left untouched
+        let mut out = vec![0xffu8; len + 3];
+
+        let written = match example.key.len() {
+            32 => Aes128Siv::new(array_ref!(example.key, 0, 32))
+                .seal_to_slice(&example.ad, &example.plaintext, &mut out),
+            64 => Aes256Siv::new(array_ref!(example.key, 0, 64))
+                .seal_to_slice(&example.ad, &example.plaintext, &mut out),
+            _ => panic!("unexpected key size: {}", example.key.len()),
+        };
+
+        assert_eq!(written, Ok(len));
+        assert_eq!(&out[..len], &example.ciphertext[..]);
+        assert_eq!(&out[len..], &[0xffu8; 3]);
+    }
+
+    let mut siv = Aes128Siv::new(&[0x42u8; 32]);
+    let mut out = [0u8; BLOCK_SIZE + 4];
+    assert_eq!(
+        siv.seal_to_slice(&[b"nonce"], b"hello", &mut out),
+        Err(Error::BufferSize)
+    );
+    assert_eq!(out, [0u8; BLOCK_SIZE + 4]);
+}
+
+/// The slice API writes exactly the detached tag followed by the ciphertext
+#[cfg(feature = "std")]
+#[test]
+fn aes_siv_seal_to_slice_matches_vec() {
+    let mut siv = Aes128Siv::new(&[0x42u8; 32]);
+    let ad: &[&[u8]] = &[b"header", b"nonce"];
+
+    for len in 0..40 {
+        let plaintext = vec![len as u8; len];
+        let (tag, ciphertext) = siv.seal_detached(ad, &plaintext);
+
+        let mut out = vec![0u8; len + BLOCK_SIZE];
+        assert_eq!(siv.seal_to_slice(ad, &plaintext, &mut out), Ok(len + BLOCK_SIZE));
+        assert_eq!(&out[..BLOCK_SIZE], &tag[..]);
+        assert_eq!(&out[BLOCK_SIZE..], &ciphertext[..]);
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn aes_siv_detached_tampered() {
+    let mut siv = Aes128Siv::new(&[0x42u8; 32]);
+    let ad: &[&[u8]] = &[b"header", b"nonce"];
+    let (tag, ciphertext) = siv.seal_detached(ad, b"plaintext");
+
+    let mut bad_tag = tag;
+    bad_tag[0] ^= 1;
+    assert_eq!(
+        siv.open_detached(&bad_tag, ad, &ciphertext),
+        Err(Error::Authentication)
+    );
+
+    let mut bad_ciphertext = ciphertext.clone();
+    bad_ciphertext[0] ^= 1;
+    assert_eq!(
+        siv.open_detached(&tag, ad, &bad_ciphertext),
+        Err(Error::Authentication)
+    );
+
+    assert_eq!(
+        siv.open_detached(&tag, &ad[1..], &ciphertext),
+        Err(Error::Authentication)
+    );
+
+    assert_eq!(
+        siv.open_detached(&tag, ad, &ciphertext).unwrap(),
+        b"plaintext"
+    );
+}
+
+#[cfg(feature = "aead")]
+#[test]
+fn aes_siv_aead_trait() {
+    use aead::{Aead, AeadInPlace, NewAead, Payload};
+    use aead::generic_array::GenericArray;
+
+    let key = [0x42u8; 32];
+    let nonce = GenericArray::clone_from_slice(&[0x24u8; 16]);
+    let aad = b"associated data";
+    let msg = b"this is some plaintext to encrypt using SIV-AES";
+
+    let siv = <Aes128Siv as NewAead>::new(&GenericArray::clone_from_slice(&key));
+    assert!(<Aes128Siv as NewAead>::new_from_slice(&key[1..]).is_err());
+
+    // The nonce follows the associated data, and the tag is prepended
+    let ciphertext = siv.encrypt(&nonce, Payload { msg: msg, aad: aad }).unwrap();
+
+    let mut expected = vec![0u8; msg.len() + BLOCK_SIZE];
+    expected[..msg.len()].copy_from_slice(msg);
+    Aes128Siv::new(&key).seal_in_place(&[&aad[..], &nonce[..]], &mut expected);
+    assert_eq!(ciphertext, expected);
+
+    let plaintext = siv.decrypt(&nonce, Payload { msg: &ciphertext, aad: aad }).unwrap();
+    assert_eq!(&plaintext[..], &msg[..]);
+
+    // Detached tags are the leading bytes of the combined form
+    let mut buffer = msg.to_vec();
+    let tag = siv.encrypt_in_place_detached(&nonce, aad, &mut buffer).unwrap();
+    assert_eq!(&tag[..], &ciphertext[..BLOCK_SIZE]);
+    assert_eq!(&buffer[..], &ciphertext[BLOCK_SIZE..]);
+
+    siv.decrypt_in_place_detached(&nonce, aad, &mut buffer, &tag).unwrap();
+    assert_eq!(&buffer[..], &msg[..]);
+
+    let mut forged = ciphertext.clone();
+    forged[0] ^= 1;
+    assert!(siv.decrypt(&nonce, Payload { msg: &forged, aad: aad }).is_err());
+    assert!(siv.decrypt(&nonce, Payload { msg: &ciphertext, aad: b"" }).is_err());
+    assert!(siv.decrypt(&nonce, &ciphertext[..BLOCK_SIZE - 1]).is_err());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn nonce_tracker_rejects_reuse() {
+    let key = [0x42u8; 32];
+    let mut tracker = NonceTracker::new(Aes128Siv::new(&key), 2);
+    let ad: &[&[u8]] = &[b"header"];
+
+    let mut buffer = [0u8; 21];
+    buffer[..5].copy_from_slice(b"hello");
+    tracker.seal_in_place(b"nonce 1", ad, &mut buffer).unwrap();
+
+    // The nonce is the last associated data item
+    let mut expected = [0u8; 21];
+    expected[..5].copy_from_slice(b"hello");
+    Aes128Siv::new(&key).seal_in_place(&[&b"header"[..], b"nonce 1"], &mut expected);
+    assert_eq!(buffer, expected);
+
+    assert_eq!(tracker.open_in_place(b"nonce 1", ad, &mut buffer).unwrap(), b"hello");
+
+    let mut buffer = [0u8; 21];
+    assert_eq!(
+        tracker.seal_in_place(b"nonce 1", ad, &mut buffer),
+        Err(Error::NonceReuse)
+    );
+    assert_eq!(buffer, [0u8; 21]);
+
+    // Only the most recent nonces are remembered
+    tracker.seal_in_place(b"nonce 2", ad, &mut buffer).unwrap();
+    tracker.seal_in_place(b"nonce 3", ad, &mut buffer).unwrap();
+    assert_eq!(
+        tracker.seal_in_place(b"nonce 2", ad, &mut buffer),
+        Err(Error::NonceReuse)
+    );
+    assert!(tracker.seal_in_place(b"nonce 1", ad, &mut buffer).is_ok());
+}
+
+#[cfg(feature = "std")]
+fn open_vec(key: &[u8], ad: &[Vec<u8>], buffer: &mut Vec<u8>) -> Result<(), Error> {
+    match key.len() {
+        32 => Aes128Siv::new(array_ref!(key, 0, 32)).open_in_place_vec(ad, buffer),
+        64 => Aes256Siv::new(array_ref!(key, 0, 64)).open_in_place_vec(ad, buffer),
+        _ => panic!("unexpected key size: {}", key.len()),
+    }
+}
+
+/// RFC 5297 Appendix A.2: Nonce-Based Authenticated Encryption Example,
+/// which has two associated data items followed by a nonce
+#[test]
+fn aes_siv_rfc5297_multiple_ad_example() {
+    let mut siv = Aes128Siv::new(&[
+        0x7f, 0x7e, 0x7d, 0x7c, 0x7b, 0x7a, 0x79, 0x78, 0x77, 0x76, 0x75, 0x74, 0x73, 0x72, 0x71, 0x70,
+        0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4a, 0x4b, 0x4c, 0x4d, 0x4e, 0x4f,
+    ]);
+
+    let ad1: &[u8] = &[
+        0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff,
+        0xde, 0xad, 0xda, 0xda, 0xde, 0xad, 0xda, 0xda, 0xff, 0xee, 0xdd, 0xcc, 0xbb, 0xaa, 0x99, 0x88,
+        0x77, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11, 0x00,
+    ];
+    let ad2: &[u8] = &[0x10, 0x20, 0x30, 0x40, 0x50, 0x60, 0x70, 0x80, 0x90, 0xa0];
+    let nonce: &[u8] = &[
+        0x09, 0xf9, 0x11, 0x02, 0x9d, 0x74, 0xe3, 0x5b, 0xd8, 0x41, 0x56, 0xc5, 0x63, 0x56, 0x88, 0xc0,
+    ];
+
+    let plaintext = b"this is some plaintext to encrypt using SIV-AES";
+    let ciphertext = [
+        0x7b, 0xdb, 0x6e, 0x3b, 0x43, 0x26, 0x67, 0xeb, 0x06, 0xf4, 0xd1, 0x4b, 0xff, 0x2f, 0xbd, 0x0f,
+        0xcb, 0x90, 0x0f, 0x2f, 0xdd, 0xbe, 0x40, 0x43, 0x26, 0x60, 0x19, 0x65, 0xc8, 0x89, 0xbf, 0x17,
+        0xdb, 0xa7, 0x7c, 0xeb, 0x09, 0x4f, 0xa6, 0x63, 0xb7, 0xa3, 0xf7, 0x48, 0xba, 0x8a, 0xf8, 0x29,
+        0xea, 0x64, 0xad, 0x54, 0x4a, 0x27, 0x2e, 0x9c, 0x48, 0x5b, 0x62, 0xa3, 0xfd, 0x5c, 0x0d,
+    ];
+
+    let associated_data: &[&[u8]] = &[ad1, ad2, nonce];
+
+    let mut buffer = [0u8; 63];
+    buffer[..plaintext.len()].copy_from_slice(plaintext);
+    siv.seal_in_place(associated_data, &mut buffer);
+    assert_eq!(&buffer[..], &ciphertext[..]);
+
+    assert_eq!(siv.open_in_place(associated_data, &mut buffer).unwrap(), &plaintext[..]);
+
+    // Each item is a separate S2V input, so neither dropping one nor joining
+    // them together authenticates
+    let joined: Vec<u8> = [ad1, ad2].concat();
+    let mut buffer = ciphertext;
+    assert!(siv.open_in_place(&[&joined[..], nonce], &mut buffer).is_err());
+
+    let mut buffer = ciphertext;
+    assert!(siv.open_in_place(&[ad1, nonce], &mut buffer).is_err());
+}
+
+/// S2V on its own: the SIV tag of each example is the S2V output over its
+/// associated data followed by the plaintext
+#[test]
+fn s2v_examples() {
+    for example in AesSivExample::load_all() {
+        let tag = match example.key.len() {
+            32 => {
+                let mut s2v: S2v<Aes128, Cmac<Aes128>> = S2v::new(Aes128::new(array_ref!(example.key, 0, 16)));
+                for ad in &example.ad {
+                    s2v.add_vector(ad);
+                }
+                s2v.finish_with_message(&example.plaintext)
+            }
+            64 => {
+                let mut s2v: S2v<Aes256, Cmac<Aes256>> = S2v::new(Aes256::new(array_ref!(example.key, 0, 32)));
+                for ad in &example.ad {
+                    s2v.add_vector(ad);
+                }
+                s2v.finish_with_message(&example.plaintext)
+            }
+            _ => panic!("unexpected key size: {}", example.key.len()),
+        };
+
+        assert_eq!(tag.as_ref(), &example.ciphertext[..BLOCK_SIZE]);
+    }
+
+    for example in AesPmacSivExample::load_all().iter().filter(|ex| ex.key.len() == 32) {
+        let mut s2v: S2v<Aes128, Pmac<Aes128>> = S2v::new(Aes128::new(array_ref!(example.key, 0, 16)));
+        for ad in &example.ad {
+            s2v.add_vector(ad);
+        }
+
+        let tag = s2v.finish_with_message(&example.plaintext);
+        assert_eq!(tag.as_ref(), &example.ciphertext[..BLOCK_SIZE]);
+    }
+}
+
+#[test]
+fn s2v_function_examples() {
+    for example in AesSivExample::load_all() {
+        let mut inputs: Vec<&[u8]> = example.ad.iter().map(|ad| &ad[..]).collect();
+        inputs.push(&example.plaintext);
+
+        let tag = match example.key.len() {
+            32 => s2v(Aes128::new(array_ref!(example.key, 0, 16)), &inputs),
+            64 => s2v(Aes256::new(array_ref!(example.key, 0, 32)), &inputs),
+            _ => panic!("unexpected key size: {}", example.key.len()),
+        };
+
+        assert_eq!(tag.as_ref(), &example.ciphertext[..BLOCK_SIZE]);
+    }
+}
+
+#[test]
+fn s2v_reuse_and_reset() {
+    let key = [0x42u8; 16];
+    let mut s2v: S2v<Aes128, Cmac<Aes128>> = S2v::new(Aes128::new(&key));
+
+    let vectors: [&[u8]; 4] = [b"one", b"two", b"three", b"four"];
+    for vector in &vectors {
+        s2v.add_vector(vector);
+    }
+    let expected = s2v.finish_with_message(b"message");
+
+    // Finishing resets the state, so the same inputs give the same output
+    for vector in &vectors {
+        s2v.add_vector(vector);
+    }
+    assert_eq!(s2v.finish_with_message(b"message").as_ref(), expected.as_ref());
+
+    // Explicitly resetting discards vectors added so far
+    s2v.add_vector(b"discarded");
+    s2v.reset();
+    for vector in &vectors {
+        s2v.add_vector(vector);
+    }
+    assert_eq!(s2v.finish_with_message(b"message").as_ref(), expected.as_ref());
+}
+
+/// RFC 5297 Section 2.4: S2V over no inputs is the CMAC of `0^127 || 1`
+#[test]
+fn s2v_empty_input() {
+    let key = [0x42u8; 16];
+    let mut s2v: S2v<Aes128, Cmac<Aes128>> = S2v::new(Aes128::new(&key));
+
+    let mut one = [0u8; BLOCK_SIZE];
+    one[BLOCK_SIZE - 1] = 1;
+
+    let expected = cmac(Aes128::new(&key), &one);
+    assert_eq!(s2v.finish_empty().as_ref(), expected.as_ref());
+
+    // The instance is still usable afterwards
+    let mut fresh: S2v<Aes128, Cmac<Aes128>> = S2v::new(Aes128::new(&key));
+    assert_eq!(
+        s2v.finish_with_message(b"message").as_ref(),
+        fresh.finish_with_message(b"message").as_ref()
+    );
+}
+
+/// Authenticating associated data alone gives the tag of an empty plaintext
+#[test]
+fn aes_siv_authenticate_examples() {
+    for example in AesSivExample::load_all().iter().filter(|ex| ex.key.len() == 32) {
+        let mut siv = Aes128Siv::new(array_ref!(example.key, 0, 32));
+
+        let mut sealed = [0u8; BLOCK_SIZE];
+        siv.seal_in_place(&example.ad, &mut sealed);
+
+        let tag = siv.authenticate(&example.ad);
+        assert_eq!(tag, sealed);
+        assert!(siv.verify(&tag, &example.ad).is_ok());
+
+        if example.plaintext.is_empty() {
+            assert_eq!(&tag[..], &example.ciphertext[..]);
+        }
+
+        let mut forged = tag;
+        forged[BLOCK_SIZE - 1] ^= 1;
+        assert_eq!(siv.verify(&forged, &example.ad), Err(Error::Authentication));
+
+        let mut ad = example.ad.clone();
+        ad.push(Vec::new());
+        assert_eq!(siv.verify(&tag, &ad), Err(Error::Authentication));
+    }
+}
+
+/// A single associated data item (RFC 5297 Appendix A.1)
+#[test]
+fn aes_siv_single_ad_example() {
+    let mut siv = Aes128Siv::new(&[
+        0xff, 0xfe, 0xfd, 0xfc, 0xfb, 0xfa, 0xf9, 0xf8, 0xf7, 0xf6, 0xf5, 0xf4, 0xf3, 0xf2, 0xf1, 0xf0,
+        0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9, 0xfa, 0xfb, 0xfc, 0xfd, 0xfe, 0xff,
+    ]);
+
+    let ad = [
+        0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f,
+        0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27,
+    ];
+
+    let plaintext = [
+        0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee,
+    ];
+
+    let ciphertext = [
+        0x85, 0x63, 0x2d, 0x07, 0xc6, 0xe8, 0xf3, 0x7f, 0x95, 0x0a, 0xcd, 0x32, 0x0a, 0x2e, 0xcc, 0x93,
+        0x40, 0xc0, 0x2b, 0x96, 0x90, 0xc4, 0xdc, 0x04, 0xda, 0xef, 0x7f, 0x6a, 0xfe, 0x5c,
+    ];
+
+    let mut buffer = [0u8; 30];
+    buffer[..plaintext.len()].copy_from_slice(&plaintext);
+    siv.seal_in_place_single_ad(&ad, &mut buffer);
+    assert_eq!(&buffer[..], &ciphertext[..]);
+
+    assert_eq!(siv.open_in_place_single_ad(&ad, &mut buffer).unwrap(), &plaintext[..]);
+}
+
+/// RFC 5297 Appendix A.1: Deterministic Authenticated Encryption Example
+#[cfg(feature = "std")]
+#[test]
+fn aes_siv_key_wrap_rfc5297_example() {
+    let key_wrap = Aes128KeyWrap::new(&[
+        0xff, 0xfe, 0xfd, 0xfc, 0xfb, 0xfa, 0xf9, 0xf8, 0xf7, 0xf6, 0xf5, 0xf4, 0xf3, 0xf2, 0xf1, 0xf0,
+        0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9, 0xfa, 0xfb, 0xfc, 0xfd, 0xfe, 0xff,
+    ]);
+
+    let ad: &[u8] = &[
+        0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f,
+        0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27,
+    ];
+
+    let key_to_wrap = [
+        0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee,
+    ];
+
+    let wrapped_key = [
+        0x85, 0x63, 0x2d, 0x07, 0xc6, 0xe8, 0xf3, 0x7f, 0x95, 0x0a, 0xcd, 0x32, 0x0a, 0x2e, 0xcc, 0x93,
+        0x40, 0xc0, 0x2b, 0x96, 0x90, 0xc4, 0xdc, 0x04, 0xda, 0xef, 0x7f, 0x6a, 0xfe, 0x5c,
+    ];
+
+    assert_eq!(key_wrap.wrap(&key_to_wrap, &[ad]), &wrapped_key[..]);
+    assert_eq!(key_wrap.unwrap(&wrapped_key, &[ad]).unwrap(), &key_to_wrap[..]);
+
+    let mut tampered = wrapped_key;
+    tampered[BLOCK_SIZE] ^= 1;
+    assert_eq!(key_wrap.unwrap(&tampered, &[ad]), Err(Error::Authentication));
+    assert_eq!(key_wrap.unwrap(&wrapped_key, &[]), Err(Error::Authentication));
+    assert_eq!(key_wrap.unwrap(&wrapped_key[..BLOCK_SIZE - 1], &[ad]), Err(Error::Authentication));
+}
+
+/// Key wrapping is deterministic SIV, so it must match every AES-SIV example
+/// whose last S2V input is the plaintext
+#[cfg(feature = "std")]
+#[test]
+fn aes_siv_key_wrap_examples() {
+    for example in AesSivExample::load_all() {
+        let ad: Vec<&[u8]> = example.ad.iter().map(|ad| ad.as_slice()).collect();
+
+        let (wrapped_key, unwrapped_key) = match example.key.len() {
+            32 => {
+                let key_wrap = Aes128KeyWrap::new(array_ref!(example.key, 0, 32));
+                let wrapped_key = key_wrap.wrap(&example.plaintext, &ad);
+                (wrapped_key.clone(), key_wrap.unwrap(&wrapped_key, &ad).unwrap())
+            }
+            64 => {
+                let key_wrap = Aes256KeyWrap::new(array_ref!(example.key, 0, 64));
+                let wrapped_key = key_wrap.wrap(&example.plaintext, &ad);
+                (wrapped_key.clone(), key_wrap.unwrap(&wrapped_key, &ad).unwrap())
+            }
+            _ => panic!("unexpected key size: {}", example.key.len()),
+        };
+
+        assert_eq!(wrapped_key, example.ciphertext);
+        assert_eq!(unwrapped_key, example.plaintext);
+    }
+}
+
+#[test]
+fn debug_redacts_secrets() {
+    let key = [0xABu8; 32];
+
+    let aes128 = Aes128::new(array_ref!(key, 0, 16));
+    let aes256 = Aes256::new(&key);
+    let block = Block::from([0xABu8; BLOCK_SIZE]);
+    let cmac = Cmac::new(Aes128::new(array_ref!(key, 0, 16)));
+    let pmac = Pmac::new(Aes128::new(array_ref!(key, 0, 16)));
+    let ctr = Ctr::new(Aes256::new(&key), &Block::new());
+    let tag = Tag::from_slice(&[0xABu8; BLOCK_SIZE]).unwrap();
+
+    for output in &[
+        format!("{:?}", aes128),
+        format!("{:?}", aes256),
+        format!("{:?}", cmac),
+        format!("{:?}", pmac),
+        format!("{:?}", ctr),
+    ] {
+        assert!(output.contains("redacted"));
+        assert!(!output.to_lowercase().contains("ab"));
+    }
+
+    if cfg!(not(feature = "danger-debug")) {
+        assert_eq!(format!("{:?}", block), "Block(...redacted...)");
+        assert_eq!(format!("{:?}", tag), "Tag(...redacted...)");
+    }
+}
+
+#[test]
+fn dbl_examples() {
+    let examples = DblExample::load_all();
+    for example in examples {
+        let mut block = Block::try_from_slice(&example.input).unwrap();
+        block.dbl();
+        assert_eq!(block.as_ref(), &example.output[..]);
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn aes_siv_stream_examples() {
+    for example in AesSivStreamExample::load_all() {
+        let nonce = array_ref!(example.nonce, 0, 8);
+
+        match (example.alg.as_ref(), example.key.len()) {
+            ("AES-SIV", 32) => check_stream(
+                &example,
+                Aes128SivEncryptor::new(array_ref!(example.key, 0, 32), nonce),
+                Aes128SivDecryptor::new(array_ref!(example.key, 0, 32), nonce),
+            ),
+            ("AES-SIV", 64) => check_stream(
+                &example,
+                Aes256SivEncryptor::new(array_ref!(example.key, 0, 64), nonce),
+                Aes256SivDecryptor::new(array_ref!(example.key, 0, 64), nonce),
+            ),
+            ("AES-PMAC-SIV", 32) => check_stream(
+                &example,
+                Aes128PmacSivEncryptor::new(array_ref!(example.key, 0, 32), nonce),
+                Aes128PmacSivDecryptor::new(array_ref!(example.key, 0, 32), nonce),
+            ),
+            ("AES-PMAC-SIV", 64) => check_stream(
+                &example,
+                Aes256PmacSivEncryptor::new(array_ref!(example.key, 0, 64), nonce),
+                Aes256PmacSivDecryptor::new(array_ref!(example.key, 0, 64), nonce),
+            ),
+            (alg, len) => panic!("unexpected STREAM example: {} with {}-byte key", alg, len),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn check_stream<C: BlockCipher, M: Mac<C>>(
+    example: &AesSivStreamExample,
+    mut encryptor: StreamEncryptor<C, M>,
+    mut decryptor: StreamDecryptor<C, M>,
+) {
+    let (last, blocks) = example.blocks.split_last().unwrap();
+
+    for block in blocks {
+        let ciphertext = encryptor.encrypt_next(&block.ad, &block.plaintext).unwrap();
+        assert_eq!(ciphertext, block.ciphertext);
+
+        let plaintext = decryptor.decrypt_next(&block.ad, &block.ciphertext).unwrap();
+        assert_eq!(plaintext, block.plaintext);
+    }
+
+    let ciphertext = encryptor.encrypt_last(&last.ad, &last.plaintext).unwrap();
+    assert_eq!(ciphertext, last.ciphertext);
+
+    let plaintext = decryptor.decrypt_last(&last.ad, &last.ciphertext).unwrap();
+    assert_eq!(plaintext, last.plaintext);
+}
+
+#[test]
+fn aes_gcm_siv_examples_seal() {
+    for example in AesGcmSivExample::load_all() {
+        let len = example.plaintext.len();
+        let mut buffer = vec![0; len + BLOCK_SIZE];
+        buffer[..len].copy_from_slice(&example.plaintext);
+
+        let ad = [&example.ad[..], &example.nonce[..]];
+
+        match example.key.len() {
+            16 => Aes128GcmSiv::new(array_ref!(example.key, 0, 16)).seal_in_place(&ad, &mut buffer),
+            32 => Aes256GcmSiv::new(array_ref!(example.key, 0, 32)).seal_in_place(&ad, &mut buffer),
+            _ => panic!("unexpected key size: {}", example.key.len()),
+        };
+
+        assert_eq!(buffer, example.ciphertext);
+    }
+}
+
+#[test]
+fn aes_gcm_siv_examples_open() {
+    for example in AesGcmSivExample::load_all() {
+        let mut buffer = example.ciphertext.clone();
+
+        assert_eq!(
+            open_gcm_siv(&example, &example.ad, &example.nonce, &mut buffer),
+            Some(example.plaintext)
+        );
+    }
+}
+
+/// Negative tests in the style of Wycheproof's AES-GCM-SIV vectors: any
+/// corrupted tag bit, ciphertext byte, associated data, or nonce is rejected
+#[test]
+fn aes_gcm_siv_examples_open_tampered() {
+    for example in AesGcmSivExample::load_all() {
+        let tag_pos = example.ciphertext.len() - BLOCK_SIZE;
+
+        for i in 0..BLOCK_SIZE {
+            for &bit in &[0x01u8, 0x80] {
+                let mut buffer = example.ciphertext.clone();
+                buffer[tag_pos + i] ^= bit;
+                assert_eq!(open_gcm_siv(&example, &example.ad, &example.nonce, &mut buffer), None);
+            }
+        }
+
+        if tag_pos > 0 {
+            let mut buffer = example.ciphertext.clone();
+            buffer[0] ^= 1;
+            assert_eq!(open_gcm_siv(&example, &example.ad, &example.nonce, &mut buffer), None);
+        }
+
+        let mut ad = example.ad.clone();
+        ad.push(0);
+        let mut buffer = example.ciphertext.clone();
+        assert_eq!(open_gcm_siv(&example, &ad, &example.nonce, &mut buffer), None);
+
+        let mut nonce = example.nonce.clone();
+        nonce[11] ^= 1;
+        let mut buffer = example.ciphertext.clone();
+        assert_eq!(open_gcm_siv(&example, &example.ad, &nonce, &mut buffer), None);
+
+        let mut buffer = example.ciphertext[..BLOCK_SIZE - 1].to_vec();
+        assert_eq!(open_gcm_siv(&example, &example.ad, &example.nonce, &mut buffer), None);
+    }
+}
+
+/// Open an AES-GCM-SIV message with the example's key, returning the
+/// plaintext if it authenticates
+fn open_gcm_siv(
+    example: &AesGcmSivExample,
+    ad: &[u8],
+    nonce: &[u8],
+    buffer: &mut [u8],
+) -> Option<Vec<u8>> {
+    let ad = [ad, nonce];
+
+    let result = match example.key.len() {
+        16 => Aes128GcmSiv::new(array_ref!(example.key, 0, 16)).open_in_place(&ad, buffer),
+        32 => Aes256GcmSiv::new(array_ref!(example.key, 0, 32)).open_in_place(&ad, buffer),
+        _ => panic!("unexpected key size: {}", example.key.len()),
+    };
+
+    result.ok().map(|plaintext| plaintext.to_vec())
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn aes_gcm_siv_examples_detached() {
+    for example in AesGcmSivExample::load_all() {
+        if example.key.len() != 16 {
+            continue;
+        }
+
+        let mut gcm_siv = Aes128GcmSiv::new(array_ref!(example.key, 0, 16));
+        let ad = [&example.ad[..], &example.nonce[..]];
+        let (tag, ciphertext) = gcm_siv.seal_detached(&ad, &example.plaintext);
+
+        let tag_pos = example.ciphertext.len() - BLOCK_SIZE;
+        assert_eq!(&ciphertext[..], &example.ciphertext[..tag_pos]);
+        assert_eq!(&tag[..], &example.ciphertext[tag_pos..]);
+
+        let plaintext = gcm_siv.open_detached(&tag, &ad, &ciphertext).unwrap();
+        assert_eq!(plaintext, example.plaintext);
+
+        let mut buffer = example.ciphertext.clone();
+        gcm_siv.open_in_place_vec(&ad, &mut buffer).unwrap();
+        assert_eq!(buffer, example.plaintext);
+    }
+}
+
+#[cfg(feature = "aead")]
+#[test]
+fn aes_gcm_siv_aead_trait() {
+    use aead::{Aead, NewAead, Payload};
+    use aead::generic_array::GenericArray;
+
+    for example in AesGcmSivExample::load_all() {
+        if example.key.len() != 32 {
+            continue;
+        }
+
+        let gcm_siv = <Aes256GcmSiv as NewAead>::new(&GenericArray::clone_from_slice(&example.key));
+        let nonce = GenericArray::clone_from_slice(&example.nonce);
+
+        let ciphertext = gcm_siv
+            .encrypt(&nonce, Payload { msg: &example.plaintext, aad: &example.ad })
+            .unwrap();
+        assert_eq!(ciphertext, example.ciphertext);
+
+        let plaintext = gcm_siv
+            .decrypt(&nonce, Payload { msg: &ciphertext, aad: &example.ad })
+            .unwrap();
+        assert_eq!(plaintext, example.plaintext);
+
+        let mut forged = ciphertext.clone();
+        *forged.last_mut().unwrap() ^= 0x80;
+        assert!(gcm_siv.decrypt(&nonce, Payload { msg: &forged, aad: &example.ad }).is_err());
+    }
+}
+
+/// Code written against `Siv<C, M: Mac<C>>` works with either MAC
+#[test]
+fn siv_generic_over_mac() {
+    fn roundtrip<M: Mac<Aes128>>(mut siv: Siv<Aes128, M>, ad: &[Vec<u8>], plaintext: &[u8]) -> Vec<u8> {
+        let mut buffer = plaintext.to_vec();
+        buffer.extend_from_slice(&[0u8; BLOCK_SIZE]);
+        siv.seal_in_place(ad, &mut buffer);
+
+        let sealed = buffer.clone();
+        assert_eq!(siv.open_in_place(ad, &mut buffer).unwrap(), plaintext);
+        sealed
+    }
+
+    for example in AesSivExample::load_all().iter().filter(|ex| ex.key.len() == 32) {
+        let siv: Aes128Siv = Siv::<Aes128, Cmac<Aes128>>::new(array_ref!(example.key, 0, 32));
+        assert_eq!(roundtrip(siv, &example.ad, &example.plaintext), example.ciphertext);
+    }
+
+    for example in AesPmacSivExample::load_all().iter().filter(|ex| ex.key.len() == 32) {
+        let siv: Aes128PmacSiv = Siv::<Aes128, Pmac<Aes128>>::new(array_ref!(example.key, 0, 32));
+        assert_eq!(roundtrip(siv, &example.ad, &example.plaintext), example.ciphertext);
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn encrypt_file_roundtrip() {
+    use std::fs::{self, File};
+    use std::io::{Read, Write};
+    use std::path::Path;
+
+    fn write_file(path: &Path, contents: &[u8]) {
+        File::create(path).unwrap().write_all(contents).unwrap();
+    }
+
+    fn read_file(path: &Path) -> Vec<u8> {
+        let mut contents = vec![];
+        File::open(path).unwrap().read_to_end(&mut contents).unwrap();
+        contents
+    }
+
+    let dir = std::env::temp_dir();
+    let name = "miscreant-encrypt_file_roundtrip";
+    let plaintext_path = dir.join(format!("{}.txt", name));
+    let encrypted_path = dir.join(format!("{}.enc", name));
+    let truncated_path = dir.join(format!("{}.truncated", name));
+    let decrypted_path = dir.join(format!("{}.dec", name));
+
+    let key = [0x42u8; 32];
+    let chunk_size = 1024;
+
+    // Two and a half chunks of plaintext
+    let plaintext: Vec<u8> = (0..2560u32).map(|i| i as u8).collect();
+    write_file(&plaintext_path, &plaintext);
+
+    encrypt_file(&key, &plaintext_path, &encrypted_path, chunk_size).unwrap();
+    let encrypted = read_file(&encrypted_path);
+    assert_eq!(encrypted.len(), 12 + plaintext.len() + 3 * BLOCK_SIZE);
+
+    decrypt_file(&key, &encrypted_path, &decrypted_path).unwrap();
+    assert_eq!(read_file(&decrypted_path), plaintext);
+
+    // Dropping the last chunk, or part of it, is detected
+    for &len in &[12 + 2 * (1024 + BLOCK_SIZE), encrypted.len() - 1] {
+        write_file(&truncated_path, &encrypted[..len]);
+
+        match decrypt_file(&key, &truncated_path, &decrypted_path) {
+            Err(FileError::Authentication) => (),
+            other => panic!("expected authentication failure, got {:?}", other),
+        }
+
+        assert!(!decrypted_path.exists());
+    }
+
+    // Each encryption uses a fresh nonce prefix
+    encrypt_file(&key, &plaintext_path, &truncated_path, chunk_size).unwrap();
+    assert_ne!(read_file(&truncated_path), encrypted);
+
+    match decrypt_file(&[0u8; 32], &encrypted_path, &decrypted_path) {
+        Err(FileError::Authentication) => (),
+        other => panic!("expected authentication failure, got {:?}", other),
+    }
+
+    for path in &[plaintext_path, encrypted_path, truncated_path] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn encrypt_file_rejects_zero_chunk_size() {
+    let dir = std::env::temp_dir();
+    let input_path = dir.join("miscreant-encrypt_file_rejects_zero_chunk_size.txt");
+    let output_path = dir.join("miscreant-encrypt_file_rejects_zero_chunk_size.enc");
+
+    match encrypt_file(&[0x42u8; 32], &input_path, &output_path, 0) {
+        Err(FileError::InvalidChunkSize) => (),
+        other => panic!("expected invalid chunk size, got {:?}", other),
+    }
+
+    assert!(!output_path.exists());
+}
+
+#[cfg(feature = "rand_core")]
+#[test]
+fn generate_key_sizes() {
+    /// Counts the bytes it's asked for, so we can check they're all used
+    struct CountingRng(usize);
+
+    impl rand_core::RngCore for CountingRng {
+        fn next_u32(&mut self) -> u32 {
+            rand_core::impls::next_u32_via_fill(self)
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            rand_core::impls::next_u64_via_fill(self)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for byte in dest.iter_mut() {
+                self.0 += 1;
+                *byte = self.0 as u8;
+            }
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl rand_core::CryptoRng for CountingRng {}
+
+    let mut rng = CountingRng(0);
+    let key = Aes128Siv::generate_key(&mut rng);
+    assert_eq!(rng.0, 32);
+    assert!(key.iter().all(|&b| b != 0));
+    Aes128Siv::new(&key);
+
+    let mut rng = CountingRng(0);
+    Aes192Siv::new(&Aes192Siv::generate_key(&mut rng));
+    assert_eq!(rng.0, 48);
+
+    let mut rng = CountingRng(0);
+    Aes256PmacSiv::new(&Aes256PmacSiv::generate_key(&mut rng));
+    assert_eq!(rng.0, 64);
+
+    let mut rng = CountingRng(0);
+    Aes128GcmSiv::new(&Aes128GcmSiv::generate_key(&mut rng));
+    assert_eq!(rng.0, 16);
+
+    let mut rng = CountingRng(0);
+    Aes256GcmSiv::new(&Aes256GcmSiv::generate_key(&mut rng));
+    assert_eq!(rng.0, 32);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn tag_serde_roundtrip() {
+    let example = &AesCmacExample::load_all()[0];
+    let mut mac = Cmac::new(Aes128::new(array_ref!(example.key, 0, 16)));
+    mac.update(&example.message).unwrap();
+    let tag = mac.finish().unwrap();
+
+    // Truncated tags keep their length through a round trip
+    let truncated = Tag::from_slice(&example.tag[..8]).unwrap();
+
+    for tag in &[tag, truncated] {
+        let json = serde_json::to_string(tag).unwrap();
+        let hex: String = tag.as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(json, format!("\"{}\"", hex));
+        assert_eq!(&serde_json::from_str::<Tag>(&json).unwrap(), tag);
+
+        let bytes = bincode::serialize(tag).unwrap();
+        assert_eq!(&bincode::deserialize::<Tag>(&bytes).unwrap(), tag);
+    }
+
+    assert!(serde_json::from_str::<Tag>("\"\"").is_err());
+    assert!(serde_json::from_str::<Tag>("\"0\"").is_err());
+    assert!(serde_json::from_str::<Tag>("\"zz\"").is_err());
+    assert!(serde_json::from_str::<Tag>(&format!("\"{}\"", "00".repeat(BLOCK_SIZE + 1))).is_err());
+    assert!(bincode::deserialize::<Tag>(&bincode::serialize(&vec![0u8; BLOCK_SIZE + 1]).unwrap()).is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn siv_tag_serde_roundtrip() {
+    let example = AesSivExample::load_all().into_iter().find(|ex| ex.key.len() == 32).unwrap();
+    let mut siv = Aes128Siv::new(array_ref!(example.key, 0, 32));
+    let mut buffer = example.plaintext.clone();
+
+    // SIV tags are returned as arrays, and serialize once converted to a `Tag`
+    let tag = Tag::from(siv.seal_in_place_detached(&example.ad, &mut buffer));
+    assert_eq!(tag.as_ref(), &example.ciphertext[..BLOCK_SIZE]);
+
+    let json = serde_json::to_string(&tag).unwrap();
+    assert_eq!(serde_json::from_str::<Tag>(&json).unwrap(), tag);
+
+    let bytes = bincode::serialize(&tag).unwrap();
+    assert_eq!(bincode::deserialize::<Tag>(&bytes).unwrap(), tag);
+}
+
+/// RFC 5869 Appendix A.3 (HKDF-SHA256 with no salt or info), whose output
+/// doesn't depend on the length requested beyond truncation
+#[cfg(feature = "hkdf")]
+#[test]
+fn derive_key_rfc5869() {
+    let master_secret = [0x0bu8; 22];
+    let expected = [
+        0x8d, 0xa4, 0xe7, 0x75, 0xa5, 0x63, 0xc1, 0x8f, 0x71, 0x5f, 0x80, 0x2a, 0x06, 0x3c, 0x5a,
+        0x31, 0xb8, 0xa1, 0x1f, 0x5c, 0x5e, 0xe1, 0x87, 0x9e, 0xc3, 0x45, 0x4e, 0x5f, 0x3c, 0x73,
+        0x8d, 0x2d, 0x9d, 0x20, 0x13, 0x95, 0xfa, 0xa4, 0xb6, 0x1a, 0x96, 0xc8, 0xb2, 0xfb, 0x61,
+        0x05, 0x72, 0x44, 0xb3, 0x6c, 0x6d, 0xdd, 0x28, 0x7f, 0x63, 0x47, 0x95, 0xe7, 0xd8, 0x0d,
+        0x5f, 0xe2, 0x6b, 0xfc,
+    ];
+
+    assert_eq!(&Aes128Siv::derive_key(&master_secret, b"")[..], &expected[..32]);
+    assert_eq!(&Aes192Siv::derive_key(&master_secret, b"")[..], &expected[..48]);
+    assert_eq!(&Aes256Siv::derive_key(&master_secret, b"")[..], &expected[..]);
+
+    // Different contexts give unrelated keys
+    assert_ne!(
+        Aes128Siv::derive_key(&master_secret, b"context a"),
+        Aes128Siv::derive_key(&master_secret, b"context b")
+    );
+}
+
+/// `Aes128Ctr` and `Aes256Ctr` produce the same keystream as the RustCrypto
+/// `ctr` crate's `Ctr128BE`, so either can be used through the traits
+#[cfg(feature = "cipher")]
+#[test]
+fn stream_cipher_matches_ctr_crate() {
+    use cipher::{NewCipher, StreamCipher, StreamCipherSeek};
+    use miscreant::{Aes128Ctr, Aes256Ctr};
+
+    let key = [0x42u8; 32];
+
+    // Carries across several bytes of the counter
+    let mut iv = [0x01u8; 16];
+    for b in &mut iv[11..] {
+        *b = 0xff;
+    }
+
+    let mut plaintext = [0u8; 300];
+    for (i, byte) in plaintext.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+
+    for &len in &[0, 1, 15, 16, 17, 127, 128, 129, 300] {
+        let mut expected = plaintext;
+        let mut actual = plaintext;
+
+        let mut reference = ctr::Ctr128BE::<aes::Aes128>::new_from_slices(&key[..16], &iv).unwrap();
+        let mut ours = Aes128Ctr::new_from_slices(&key[..16], &iv).unwrap();
+        reference.apply_keystream(&mut expected[..len]);
+        StreamCipher::apply_keystream(&mut ours, &mut actual[..len]);
+        assert_eq!(&actual[..], &expected[..], "AES-128, len {}", len);
+        assert_eq!(ours.current_pos::<u64>(), reference.current_pos::<u64>());
+
+        let mut reference = ctr::Ctr128BE::<aes::Aes256>::new_from_slices(&key, &iv).unwrap();
+        let mut ours = Aes256Ctr::new_from_slices(&key, &iv).unwrap();
+        reference.apply_keystream(&mut expected[..len]);
+        StreamCipher::apply_keystream(&mut ours, &mut actual[..len]);
+        assert_eq!(&actual[..], &expected[..], "AES-256, len {}", len);
+
+        // Seeking back and decrypting a sub-range recovers the plaintext
+        let start = len / 3;
+        reference.seek(start as u64);
+        StreamCipherSeek::seek(&mut ours, start as u64);
+        reference.apply_keystream(&mut expected[start..len]);
+        StreamCipher::apply_keystream(&mut ours, &mut actual[start..len]);
+        assert_eq!(&actual[..], &expected[..], "AES-256 seek, len {}", len);
+    }
+}
+
+#[cfg(feature = "encoding")]
+#[test]
+fn seal_to_base64_and_hex_roundtrip() {
+    let mut siv = Aes128Siv::new(&[0x42u8; 32]);
+    let ad = [b"header", b"nonce!"];
+
+    for len in &[0, 1, 15, 16, 17, 100] {
+        let plaintext = vec![0x24u8; *len];
+
+        let base64 = siv.seal_to_base64(&ad, &plaintext);
+        assert_eq!(siv.open_from_base64(&ad, &base64).unwrap(), plaintext);
+
+        let hex = siv.seal_to_hex(&ad, &plaintext);
+        assert_eq!(hex.len(), (len + BLOCK_SIZE) * 2);
+        assert_eq!(siv.open_from_hex(&ad, &hex).unwrap(), plaintext);
+        assert_eq!(siv.open_from_hex(&ad, &hex.to_uppercase()).unwrap(), plaintext);
+    }
+
+    // Both encode the same bytes as `seal_to_slice`
+    let mut message = [0u8; 21];
+    siv.seal_to_slice(&ad, b"hello", &mut message).unwrap();
+    let hex = siv.seal_to_hex(&ad, b"hello");
+
+    let mut from_hex = [0u8; 21];
+    for (byte, pair) in from_hex.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap();
+    }
+    assert_eq!(from_hex, message);
+}
+
+#[cfg(feature = "encoding")]
+#[test]
+fn open_from_malformed_base64_and_hex() {
+    let mut siv = Aes128Siv::new(&[0x42u8; 32]);
+    let ad = [b"header"];
+
+    for malformed in &["!!!!", "QUJ*", "Q", "QUJD\n"] {
+        assert_eq!(siv.open_from_base64(&ad, malformed).err(), Some(Error::Base64));
+    }
+
+    let hex = siv.seal_to_hex(&ad, b"attack at dawn");
+    assert_eq!(siv.open_from_hex(&ad, &hex[1..]).err(), Some(Error::HexLength));
+    assert_eq!(siv.open_from_hex(&ad, &format!("g{}", &hex[1..])).err(), Some(Error::HexCharacter));
+
+    // Well-formed but forged or truncated messages fail authentication
+    let mut forged = hex.clone().into_bytes();
+    forged[0] = if forged[0] == b'0' { b'1' } else { b'0' };
+    let forged = String::from_utf8(forged).unwrap();
+    assert_eq!(siv.open_from_hex(&ad, &forged).err(), Some(Error::Authentication));
+    assert_eq!(siv.open_from_base64(&ad, "AAAA").err(), Some(Error::Authentication));
+}
+
+/// `seal_with` matches the vectors with the last associated data item as the
+/// nonce, however the rest are supplied
+#[cfg(feature = "std")]
+#[test]
+fn aes_siv_seal_with_examples() {
+    for example in AesSivExample::load_all() {
+        let (nonce, ad) = match example.ad.split_last() {
+            Some((nonce, ad)) => (nonce, ad),
+            None => continue,
+        };
+
+        let sealed = match example.key.len() {
+            32 => {
+                let mut siv = Aes128Siv::new(array_ref!(example.key, 0, 32));
+                let from_vec = siv.seal_with(nonce, ad.to_vec(), &example.plaintext);
+                let lazy = siv.seal_with(nonce, ad.iter().map(|a| &a[..]), &example.plaintext);
+                assert_eq!(from_vec, lazy);
+
+                let opened = siv.open_with(nonce, ad.iter(), &from_vec).unwrap();
+                assert_eq!(opened, example.plaintext);
+                from_vec
+            }
+            64 => {
+                let mut siv = Aes256Siv::new(array_ref!(example.key, 0, 64));
+                let from_vec = siv.seal_with(nonce, ad.to_vec(), &example.plaintext);
+                let lazy = siv.seal_with(nonce, ad.iter().map(|a| &a[..]), &example.plaintext);
+                assert_eq!(from_vec, lazy);
+
+                let opened = siv.open_with(nonce, ad.iter(), &from_vec).unwrap();
+                assert_eq!(opened, example.plaintext);
+                from_vec
+            }
+            _ => panic!("unexpected key size: {}", example.key.len()),
+        };
+
+        assert_eq!(sealed, example.ciphertext);
+    }
+}
+
+/// An empty iterator gives no associated data at all, and the order of the
+/// items matters
+#[cfg(feature = "std")]
+#[test]
+fn aes_siv_associated_data_iterators() {
+    let example = AesSivExample::load_all()
+        .into_iter()
+        .find(|example| example.ad.is_empty())
+        .expect("example without associated data");
+
+    let mut siv = Aes128Siv::new(array_ref!(example.key, 0, 32));
+    let mut buffer = vec![0u8; example.plaintext.len() + BLOCK_SIZE];
+    buffer[..example.plaintext.len()].copy_from_slice(&example.plaintext);
+    siv.seal_in_place(std::iter::empty::<&[u8]>(), &mut buffer);
+    assert_eq!(buffer, example.ciphertext);
+
+    let forwards = siv.seal_with(b"nonce", (0..3u8).map(|i| [i]), b"plaintext");
+    let backwards = siv.seal_with(b"nonce", (0..3u8).rev().map(|i| [i]), b"plaintext");
+    assert_ne!(forwards, backwards);
+    assert!(siv.open_with(b"nonce", (0..3u8).rev().map(|i| [i]), &forwards).is_err());
+    assert_eq!(siv.open_with(b"nonce", (0..3u8).map(|i| [i]), &forwards).unwrap(), b"plaintext");
+}
@@ -0,0 +1,198 @@
+//! Property-based tests: random keys, nonces, associated data, and messages
+//! of lengths spanning several blocks, to catch block boundary bugs which
+//! the fixed test vectors might miss
+
+use miscreant::{Aes128PmacSiv, Aes128Siv, Aes256Siv, Ctr, Error};
+use miscreant::internals::{cmac, Aes128, Block, BlockCipher, Cmac, Mac, BLOCK_SIZE};
+use miscreant::siv::Siv;
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+/// Longest plaintext generated: several blocks, plus a partial one
+const MAX_MESSAGE_SIZE: usize = BLOCK_SIZE * 6 + 7;
+
+/// Seal `plaintext` with the associated data items followed by the nonce
+fn seal<C, M>(siv: &mut Siv<C, M>, nonce: &[u8], ad: &[Vec<u8>], plaintext: &[u8]) -> Vec<u8>
+where
+    C: BlockCipher,
+    M: Mac<C>,
+{
+    let mut buffer = plaintext.to_vec();
+    buffer.extend_from_slice(&[0u8; BLOCK_SIZE]);
+    siv.seal_in_place(inputs(nonce, ad), &mut buffer);
+    buffer
+}
+
+/// Open a sealed message, returning the plaintext
+fn open<C, M>(
+    siv: &mut Siv<C, M>,
+    nonce: &[u8],
+    ad: &[Vec<u8>],
+    sealed: &[u8],
+) -> Result<Vec<u8>, Error>
+where
+    C: BlockCipher,
+    M: Mac<C>,
+{
+    let mut buffer = sealed.to_vec();
+    let len = siv.open_in_place(inputs(nonce, ad), &mut buffer)?.len();
+    buffer.truncate(len);
+    Ok(buffer)
+}
+
+/// The S2V inputs: each associated data item, then the nonce
+fn inputs<'a>(nonce: &'a [u8], ad: &'a [Vec<u8>]) -> Vec<&'a [u8]> {
+    ad.iter().map(|item| &item[..]).chain(Some(nonce)).collect()
+}
+
+/// Flip the given bit (modulo the length in bits) of a nonempty buffer
+fn flip_bit(buffer: &mut [u8], bit: usize) {
+    let bit = bit % (buffer.len() * 8);
+    buffer[bit / 8] ^= 1 << (bit % 8);
+}
+
+/// Check a message round-trips, and that flipping any one bit of the tag,
+/// ciphertext, nonce, or associated data makes it fail to open
+fn check_siv<C, M>(
+    siv: &mut Siv<C, M>,
+    nonce: &[u8],
+    ad: &[Vec<u8>],
+    plaintext: &[u8],
+    bit: usize,
+) -> Result<(), TestCaseError>
+where
+    C: BlockCipher,
+    M: Mac<C>,
+{
+    let sealed = seal(siv, nonce, ad, plaintext);
+    prop_assert_eq!(sealed.len(), plaintext.len() + BLOCK_SIZE);
+    prop_assert_eq!(open(siv, nonce, ad, &sealed), Ok(plaintext.to_vec()));
+
+    let mut tampered = sealed.clone();
+    flip_bit(&mut tampered[..BLOCK_SIZE], bit);
+    prop_assert_eq!(open(siv, nonce, ad, &tampered), Err(Error::Authentication));
+
+    if !plaintext.is_empty() {
+        let mut tampered = sealed.clone();
+        flip_bit(&mut tampered[BLOCK_SIZE..], bit);
+        prop_assert_eq!(open(siv, nonce, ad, &tampered), Err(Error::Authentication));
+    }
+
+    let mut tampered_nonce = nonce.to_vec();
+    flip_bit(&mut tampered_nonce, bit);
+    prop_assert_eq!(open(siv, &tampered_nonce, ad, &sealed), Err(Error::Authentication));
+
+    // Flip a bit of the associated data as a whole, whichever item it's in
+    let ad_bits: usize = ad.iter().map(|item| item.len() * 8).sum();
+
+    if ad_bits > 0 {
+        let mut tampered_ad = ad.to_vec();
+        let mut remaining = bit % ad_bits;
+
+        for item in tampered_ad.iter_mut() {
+            if remaining < item.len() * 8 {
+                flip_bit(item, remaining);
+                break;
+            }
+
+            remaining -= item.len() * 8;
+        }
+
+        prop_assert_eq!(open(siv, nonce, &tampered_ad, &sealed), Err(Error::Authentication));
+    }
+
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn aes128_siv_round_trip_and_tampering(
+        key in vec(any::<u8>(), 32),
+        nonce in vec(any::<u8>(), 1..24),
+        ad in vec(vec(any::<u8>(), 0..40), 0..4),
+        plaintext in vec(any::<u8>(), 0..MAX_MESSAGE_SIZE),
+        bit in any::<usize>(),
+    ) {
+        let mut siv = Aes128Siv::new(array_ref!(key, 0, 32));
+        check_siv(&mut siv, &nonce, &ad, &plaintext, bit)?;
+    }
+
+    #[test]
+    fn aes256_siv_round_trip_and_tampering(
+        key in vec(any::<u8>(), 64),
+        nonce in vec(any::<u8>(), 1..24),
+        ad in vec(vec(any::<u8>(), 0..40), 0..4),
+        plaintext in vec(any::<u8>(), 0..MAX_MESSAGE_SIZE),
+        bit in any::<usize>(),
+    ) {
+        let mut siv = Aes256Siv::new(array_ref!(key, 0, 64));
+        check_siv(&mut siv, &nonce, &ad, &plaintext, bit)?;
+    }
+
+    #[test]
+    fn aes128_pmac_siv_round_trip_and_tampering(
+        key in vec(any::<u8>(), 32),
+        nonce in vec(any::<u8>(), 1..24),
+        ad in vec(vec(any::<u8>(), 0..40), 0..4),
+        plaintext in vec(any::<u8>(), 0..MAX_MESSAGE_SIZE),
+        bit in any::<usize>(),
+    ) {
+        let mut siv = Aes128PmacSiv::new(array_ref!(key, 0, 32));
+        check_siv(&mut siv, &nonce, &ad, &plaintext, bit)?;
+    }
+
+    /// Feeding CMAC a message in arbitrary pieces gives the one-shot tag
+    #[test]
+    fn cmac_update_at_any_split(
+        key in vec(any::<u8>(), 16),
+        message in vec(any::<u8>(), 0..MAX_MESSAGE_SIZE),
+        splits in vec(any::<usize>(), 0..4),
+    ) {
+        let expected = cmac(Aes128::new(array_ref!(key, 0, 16)), &message);
+
+        let mut mac = Cmac::new(Aes128::new(array_ref!(key, 0, 16)));
+        let mut remaining = &message[..];
+
+        for split in splits {
+            let (piece, rest) = remaining.split_at(split % (remaining.len() + 1));
+            mac.update(piece).unwrap();
+            remaining = rest;
+        }
+
+        mac.update(remaining).unwrap();
+        let tag = mac.finish().unwrap();
+        prop_assert_eq!(tag.as_ref(), expected.as_ref());
+    }
+
+    /// Applying the CTR keystream in arbitrary pieces matches one call
+    #[test]
+    fn ctr_apply_keystream_at_any_split(
+        key in vec(any::<u8>(), 16),
+        iv in vec(any::<u8>(), 16),
+        message in vec(any::<u8>(), 0..MAX_MESSAGE_SIZE),
+        splits in vec(any::<usize>(), 0..4),
+    ) {
+        // Leave room after the IV so the big endian counter can't run out
+        let mut iv_bytes = *array_ref!(iv, 0, BLOCK_SIZE);
+        iv_bytes[0] &= 0x7f;
+        let iv = Block::from(iv_bytes);
+
+        let mut expected = message.clone();
+        Ctr::new(Aes128::new(array_ref!(key, 0, 16)), &iv)
+            .apply_keystream(&mut expected)
+            .unwrap();
+
+        let mut ctr = Ctr::new(Aes128::new(array_ref!(key, 0, 16)), &iv);
+        let mut buffer = message.clone();
+        let mut offset = 0;
+
+        for split in splits {
+            let end = offset + split % (buffer.len() - offset + 1);
+            ctr.apply_keystream(&mut buffer[offset..end]).unwrap();
+            offset = end;
+        }
+
+        ctr.apply_keystream(&mut buffer[offset..]).unwrap();
+        prop_assert_eq!(buffer, expected);
+    }
+}
@@ -108,6 +108,160 @@ impl AesCmacExample {
     }
 }
 
+/// SP 800-108 counter mode AES-CMAC KDF test vectors
+// TODO: switch to the tjson crate (based on serde)
+#[derive(Debug)]
+pub struct AesCmacKdfExample {
+    pub key: Vec<u8>,
+    pub label: Vec<u8>,
+    pub context: Vec<u8>,
+    pub output: Vec<u8>,
+}
+
+impl AesCmacKdfExample {
+    /// Load examples from aes_cmac_kdf.tjson
+    pub fn load_all() -> Vec<Self> {
+        Self::load_from_file(Path::new("../vectors/aes_cmac_kdf.tjson"))
+    }
+
+    /// Load examples from a file at the given path
+    pub fn load_from_file(path: &Path) -> Vec<Self> {
+        let mut file = File::open(&path).expect("valid aes_cmac_kdf.tjson");
+        let mut tjson_string = String::new();
+        file.read_to_string(&mut tjson_string).expect(
+            "aes_cmac_kdf.tjson read successfully",
+        );
+
+        let tjson: serde_json::Value =
+            serde_json::from_str(&tjson_string).expect("aes_cmac_kdf.tjson parses successfully");
+        let examples = &tjson["examples:A<O>"].as_array().expect(
+            "aes_cmac_kdf.tjson examples array",
+        );
+
+        examples
+            .into_iter()
+            .map(|ex| {
+                Self {
+                    key: HEXLOWER
+                        .decode(ex["key:d16"].as_str().expect("encoded example").as_bytes())
+                        .expect("hex encoded"),
+                    label: HEXLOWER
+                        .decode(ex["label:d16"].as_str().expect("encoded example").as_bytes())
+                        .expect("hex encoded"),
+                    context: HEXLOWER
+                        .decode(ex["context:d16"].as_str().expect("encoded example").as_bytes())
+                        .expect("hex encoded"),
+                    output: HEXLOWER
+                        .decode(ex["output:d16"].as_str().expect("encoded example").as_bytes())
+                        .expect("hex encoded"),
+                }
+            })
+            .collect()
+    }
+}
+
+/// SP 800-108 counter mode AES-CMAC KDF test vectors from CAVP, with the
+/// fixed input data given directly
+// TODO: switch to the tjson crate (based on serde)
+#[derive(Debug)]
+pub struct AesCmacKdfCavpExample {
+    pub key: Vec<u8>,
+    pub fixed_input: Vec<u8>,
+    pub output: Vec<u8>,
+}
+
+impl AesCmacKdfCavpExample {
+    /// Load examples from aes_cmac_kdf_cavp.tjson
+    pub fn load_all() -> Vec<Self> {
+        Self::load_from_file(Path::new("../vectors/aes_cmac_kdf_cavp.tjson"))
+    }
+
+    /// Load examples from a file at the given path
+    pub fn load_from_file(path: &Path) -> Vec<Self> {
+        let mut file = File::open(&path).expect("valid aes_cmac_kdf_cavp.tjson");
+        let mut tjson_string = String::new();
+        file.read_to_string(&mut tjson_string).expect(
+            "aes_cmac_kdf_cavp.tjson read successfully",
+        );
+
+        let tjson: serde_json::Value = serde_json::from_str(&tjson_string)
+            .expect("aes_cmac_kdf_cavp.tjson parses successfully");
+        let examples = &tjson["examples:A<O>"].as_array().expect(
+            "aes_cmac_kdf_cavp.tjson examples array",
+        );
+
+        examples
+            .into_iter()
+            .map(|ex| {
+                Self {
+                    key: HEXLOWER
+                        .decode(ex["key:d16"].as_str().expect("encoded example").as_bytes())
+                        .expect("hex encoded"),
+                    fixed_input: HEXLOWER
+                        .decode(ex["fixed_input:d16"].as_str().expect("encoded example").as_bytes())
+                        .expect("hex encoded"),
+                    output: HEXLOWER
+                        .decode(ex["output:d16"].as_str().expect("encoded example").as_bytes())
+                        .expect("hex encoded"),
+                }
+            })
+            .collect()
+    }
+}
+
+/// AES-PMAC test vectors
+// TODO: switch to the tjson crate (based on serde)
+#[derive(Debug)]
+pub struct AesPmacExample {
+    pub key: Vec<u8>,
+    pub message: Vec<u8>,
+    pub tag: Vec<u8>,
+}
+
+impl AesPmacExample {
+    /// Load examples from aes_pmac.tjson
+    pub fn load_all() -> Vec<Self> {
+        Self::load_from_file(Path::new("../vectors/aes_pmac.tjson"))
+    }
+
+    /// Load examples from a file at the given path
+    pub fn load_from_file(path: &Path) -> Vec<Self> {
+        let mut file = File::open(&path).expect("valid aes_pmac.tjson");
+        let mut tjson_string = String::new();
+        file.read_to_string(&mut tjson_string).expect(
+            "aes_pmac.tjson read successfully",
+        );
+
+        let tjson: serde_json::Value =
+            serde_json::from_str(&tjson_string).expect("aes_pmac.tjson parses successfully");
+        let examples = &tjson["examples:A<O>"].as_array().expect(
+            "aes_pmac.tjson examples array",
+        );
+
+        examples
+            .into_iter()
+            .map(|ex| {
+                Self {
+                    key: HEXLOWER
+                        .decode(ex["key:d16"].as_str().expect("encoded example").as_bytes())
+                        .expect("hex encoded"),
+                    message: HEXLOWER
+                        .decode(
+                            ex["message:d16"]
+                                .as_str()
+                                .expect("encoded example")
+                                .as_bytes(),
+                        )
+                        .expect("hex encoded"),
+                    tag: HEXLOWER
+                        .decode(ex["tag:d16"].as_str().expect("encoded example").as_bytes())
+                        .expect("hex encoded"),
+                }
+            })
+            .collect()
+    }
+}
+
 /// AES-CTR test vectors
 // TODO: switch to the tjson crate (based on serde)
 #[derive(Debug)]
@@ -170,6 +324,65 @@ impl AesCtrExample {
     }
 }
 
+/// AES-256 CTR_DRBG (no derivation function) test vectors
+///
+/// Each example instantiates a DRBG, optionally reseeds it, then makes two
+/// requests, keeping only the output of the second (as in the CAVP tests)
+// TODO: switch to the tjson crate (based on serde)
+#[derive(Debug)]
+pub struct AesCtrDrbgExample {
+    pub entropy_input: Vec<u8>,
+    pub personalization_string: Vec<u8>,
+    pub entropy_input_reseed: Vec<u8>,
+    pub additional_input_reseed: Vec<u8>,
+    pub additional_input_1: Vec<u8>,
+    pub additional_input_2: Vec<u8>,
+    pub returned_bytes: Vec<u8>,
+}
+
+impl AesCtrDrbgExample {
+    /// Load examples from aes_ctr_drbg.tjson
+    pub fn load_all() -> Vec<Self> {
+        Self::load_from_file(Path::new("../vectors/aes_ctr_drbg.tjson"))
+    }
+
+    /// Load examples from a file at the given path
+    pub fn load_from_file(path: &Path) -> Vec<Self> {
+        let mut file = File::open(&path).expect("valid aes_ctr_drbg.tjson");
+        let mut tjson_string = String::new();
+        file.read_to_string(&mut tjson_string).expect(
+            "aes_ctr_drbg.tjson read successfully",
+        );
+
+        let tjson: serde_json::Value =
+            serde_json::from_str(&tjson_string).expect("aes_ctr_drbg.tjson parses successfully");
+        let examples = &tjson["examples:A<O>"].as_array().expect(
+            "aes_ctr_drbg.tjson examples array",
+        );
+
+        examples
+            .into_iter()
+            .map(|ex| {
+                let field = |name: &str| {
+                    HEXLOWER
+                        .decode(ex[name].as_str().expect("encoded example").as_bytes())
+                        .expect("hex encoded")
+                };
+
+                Self {
+                    entropy_input: field("entropy_input:d16"),
+                    personalization_string: field("personalization_string:d16"),
+                    entropy_input_reseed: field("entropy_input_reseed:d16"),
+                    additional_input_reseed: field("additional_input_reseed:d16"),
+                    additional_input_1: field("additional_input_1:d16"),
+                    additional_input_2: field("additional_input_2:d16"),
+                    returned_bytes: field("returned_bytes:d16"),
+                }
+            })
+            .collect()
+    }
+}
+
 /// AES-SIV test vectors
 // TODO: switch to the tjson crate (based on serde)
 #[derive(Debug)]
@@ -186,6 +399,11 @@ impl AesSivExample {
         Self::load_from_file(Path::new("../vectors/aes_siv.tjson"))
     }
 
+    /// Load AES-192-SIV examples from aes192_siv.tjson
+    pub fn load_aes192() -> Vec<Self> {
+        Self::load_from_file(Path::new("../vectors/aes192_siv.tjson"))
+    }
+
     /// Load examples from a file at the given path
     pub fn load_from_file(path: &Path) -> Vec<Self> {
         let mut file = File::open(&path).expect("valid aes_siv.tjson");
@@ -239,6 +457,226 @@ impl AesSivExample {
     }
 }
 
+/// AES-GCM-SIV test vectors
+// TODO: switch to the tjson crate (based on serde)
+#[derive(Debug)]
+pub struct AesGcmSivExample {
+    pub key: Vec<u8>,
+    pub nonce: Vec<u8>,
+    pub ad: Vec<u8>,
+    pub plaintext: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
+
+impl AesGcmSivExample {
+    /// Load examples from aes_gcm_siv.tjson
+    pub fn load_all() -> Vec<Self> {
+        Self::load_from_file(Path::new("../vectors/aes_gcm_siv.tjson"))
+    }
+
+    /// Load examples from a file at the given path
+    pub fn load_from_file(path: &Path) -> Vec<Self> {
+        let mut file = File::open(&path).expect("valid aes_gcm_siv.tjson");
+        let mut tjson_string = String::new();
+        file.read_to_string(&mut tjson_string).expect(
+            "aes_gcm_siv.tjson read successfully",
+        );
+
+        let tjson: serde_json::Value =
+            serde_json::from_str(&tjson_string).expect("aes_gcm_siv.tjson parses successfully");
+        let examples = &tjson["examples:A<O>"].as_array().expect(
+            "aes_gcm_siv.tjson examples array",
+        );
+
+        examples
+            .into_iter()
+            .map(|ex| {
+                Self {
+                    key: HEXLOWER
+                        .decode(ex["key:d16"].as_str().expect("encoded example").as_bytes())
+                        .expect("hex encoded"),
+                    nonce: HEXLOWER
+                        .decode(ex["nonce:d16"].as_str().expect("encoded example").as_bytes())
+                        .expect("hex encoded"),
+                    ad: HEXLOWER
+                        .decode(ex["ad:d16"].as_str().expect("encoded example").as_bytes())
+                        .expect("hex encoded"),
+                    plaintext: HEXLOWER
+                        .decode(
+                            ex["plaintext:d16"]
+                                .as_str()
+                                .expect("encoded example")
+                                .as_bytes(),
+                        )
+                        .expect("hex encoded"),
+                    ciphertext: HEXLOWER
+                        .decode(
+                            ex["ciphertext:d16"]
+                                .as_str()
+                                .expect("encoded example")
+                                .as_bytes(),
+                        )
+                        .expect("hex encoded"),
+                }
+            })
+            .collect()
+    }
+}
+
+/// AES-PMAC-SIV test vectors
+// TODO: switch to the tjson crate (based on serde)
+#[derive(Debug)]
+pub struct AesPmacSivExample {
+    pub key: Vec<u8>,
+    pub ad: Vec<Vec<u8>>,
+    pub plaintext: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
+
+impl AesPmacSivExample {
+    /// Load examples from aes_pmac_siv.tjson
+    pub fn load_all() -> Vec<Self> {
+        Self::load_from_file(Path::new("../vectors/aes_pmac_siv.tjson"))
+    }
+
+    /// Load examples from a file at the given path
+    pub fn load_from_file(path: &Path) -> Vec<Self> {
+        let mut file = File::open(&path).expect("valid aes_pmac_siv.tjson");
+        let mut tjson_string = String::new();
+        file.read_to_string(&mut tjson_string).expect(
+            "aes_pmac_siv.tjson read successfully",
+        );
+
+        let tjson: serde_json::Value =
+            serde_json::from_str(&tjson_string).expect("aes_pmac_siv.tjson parses successfully");
+        let examples = &tjson["examples:A<O>"].as_array().expect(
+            "aes_pmac_siv.tjson examples array",
+        );
+
+        examples
+            .into_iter()
+            .map(|ex| {
+                Self {
+                    key: HEXLOWER
+                        .decode(ex["key:d16"].as_str().expect("encoded example").as_bytes())
+                        .expect("hex encoded"),
+                    ad: ex["ad:A<d16>"]
+                        .as_array()
+                        .expect("encoded example")
+                        .iter()
+                        .map(|ex| {
+                            HEXLOWER
+                                .decode(ex.as_str().expect("encoded example").as_bytes())
+                                .expect("hex encoded")
+                        })
+                        .collect(),
+                    plaintext: HEXLOWER
+                        .decode(
+                            ex["plaintext:d16"]
+                                .as_str()
+                                .expect("encoded example")
+                                .as_bytes(),
+                        )
+                        .expect("hex encoded"),
+                    ciphertext: HEXLOWER
+                        .decode(
+                            ex["ciphertext:d16"]
+                                .as_str()
+                                .expect("encoded example")
+                                .as_bytes(),
+                        )
+                        .expect("hex encoded"),
+                }
+            })
+            .collect()
+    }
+}
+
+/// STREAM test vectors
+// TODO: switch to the tjson crate (based on serde)
+#[derive(Debug)]
+pub struct AesSivStreamExample {
+    pub alg: String,
+    pub key: Vec<u8>,
+    pub nonce: Vec<u8>,
+    pub blocks: Vec<AesSivStreamBlock>,
+}
+
+/// A single chunk of a STREAM example
+#[derive(Debug)]
+pub struct AesSivStreamBlock {
+    pub ad: Vec<u8>,
+    pub plaintext: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
+
+impl AesSivStreamExample {
+    /// Load examples from aes_siv_stream.tjson
+    pub fn load_all() -> Vec<Self> {
+        Self::load_from_file(Path::new("../vectors/aes_siv_stream.tjson"))
+    }
+
+    /// Load examples from a file at the given path
+    pub fn load_from_file(path: &Path) -> Vec<Self> {
+        let mut file = File::open(&path).expect("valid aes_siv_stream.tjson");
+        let mut tjson_string = String::new();
+        file.read_to_string(&mut tjson_string).expect(
+            "aes_siv_stream.tjson read successfully",
+        );
+
+        let tjson: serde_json::Value =
+            serde_json::from_str(&tjson_string).expect("aes_siv_stream.tjson parses successfully");
+        let examples = &tjson["examples:A<O>"].as_array().expect(
+            "aes_siv_stream.tjson examples array",
+        );
+
+        examples
+            .into_iter()
+            .map(|ex| {
+                Self {
+                    alg: ex["alg:s"].as_str().expect("encoded example").to_owned(),
+                    key: HEXLOWER
+                        .decode(ex["key:d16"].as_str().expect("encoded example").as_bytes())
+                        .expect("hex encoded"),
+                    nonce: HEXLOWER
+                        .decode(ex["nonce:d16"].as_str().expect("encoded example").as_bytes())
+                        .expect("hex encoded"),
+                    blocks: ex["blocks:A<O>"]
+                        .as_array()
+                        .expect("encoded example")
+                        .iter()
+                        .map(|block| {
+                            AesSivStreamBlock {
+                                ad: HEXLOWER
+                                    .decode(
+                                        block["ad:d16"].as_str().expect("encoded example").as_bytes(),
+                                    )
+                                    .expect("hex encoded"),
+                                plaintext: HEXLOWER
+                                    .decode(
+                                        block["plaintext:d16"]
+                                            .as_str()
+                                            .expect("encoded example")
+                                            .as_bytes(),
+                                    )
+                                    .expect("hex encoded"),
+                                ciphertext: HEXLOWER
+                                    .decode(
+                                        block["ciphertext:d16"]
+                                            .as_str()
+                                            .expect("encoded example")
+                                            .as_bytes(),
+                                    )
+                                    .expect("hex encoded"),
+                            }
+                        })
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+}
+
 /// dbl() test vectors
 // TODO: switch to the tjson crate (based on serde)
 #[derive(Debug)]